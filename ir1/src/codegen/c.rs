@@ -1,47 +1,101 @@
-use crate::ir::{function::*, inst::*, module::*};
-use id_arena::Arena;
+use crate::ir::{function::*, global::*, inst::*, memory::*, module::*};
 use parser::common::JoinView;
+use parser::ty::Type;
 use std::fmt;
 
 pub fn codegen(f: &mut fmt::Formatter, module: &Module) -> fmt::Result {
   write!(f, "// ModuleName = '{}'", module.name())?;
+  for (_id, global) in module.globals() {
+    gen_global(f, global)?;
+  }
   for (_id, fun) in module.functions() {
-    gen_fun(f, fun, module.functions())?;
+    gen_fun(f, fun, module)?;
   }
   Ok(())
 }
 
-fn gen_fun(f: &mut fmt::Formatter, fun: &Function, funs: &Arena<Function>) -> fmt::Result {
+fn gen_global(f: &mut fmt::Formatter, global: &Global) -> fmt::Result {
+  // a variable of internal linkage is `static`, and one defined elsewhere is `extern`
+  let storage = match (global.linkage(), global.init()) {
+    (Linkage::Internal, _) => "static ",
+    (Linkage::External, None) => "extern ",
+    (Linkage::External, Some(_)) => "",
+  };
+  let init = match global.init() {
+    Some(GlobalInit::Int(n)) => format!(" = {}", c_int(n)),
+    Some(GlobalInit::Float(x)) => format!(" = {}", c_double(x)),
+    None => String::new(),
+  };
+  write!(
+    f,
+    "\n\n{}{} {}{};",
+    storage,
+    c_type(global.ty()),
+    global.name(),
+    init
+  )
+}
+
+fn gen_fun(f: &mut fmt::Formatter, fun: &Function, module: &Module) -> fmt::Result {
+  // a function of internal linkage is `static` so that it does not clash with other modules
+  let storage = match fun.linkage() {
+    Linkage::External => "",
+    Linkage::Internal => "static ",
+  };
   if fun.is_declaration() {
     // Emit function declaration
-    let iter = fun.param_tys().iter().map(|ty| format!("{}", ty));
-    let param_tys = JoinView::new(iter, ", ");
-    write!(f, "\n\n{} {}({});", fun.ret_ty(), fun.name(), param_tys)
+    let iter = fun.param_tys().iter().map(c_type);
+    let variadic = fun.is_variadic().then(|| "...".to_string());
+    let param_tys = JoinView::new(iter.chain(variadic), ", ");
+    write!(
+      f,
+      "\n\n{}{} {}({});",
+      storage,
+      c_type(fun.ret_ty()),
+      fun.name(),
+      param_tys
+    )
   } else {
     // Emit function return type, name and parameters
     let iter = fun
       .param_tys()
       .iter()
       .enumerate()
-      .map(|(i, ty)| format!("{} a{}", ty, i));
+      .map(|(i, ty)| format!("{} a{}", c_type(ty), i));
     let param_tys = JoinView::new(iter, ", ");
-    write!(f, "\n\n{} {}({}) {{", fun.ret_ty(), fun.name(), param_tys)?;
+    write!(
+      f,
+      "\n\n{}{} {}({}) {{",
+      storage,
+      c_type(fun.ret_ty()),
+      fun.name(),
+      param_tys
+    )?;
 
-    // Allocate memory
-    if fun.memory_arena().len() != 0 {
-      write!(f, "\n  int m[{}];", fun.memory_arena().len())?;
+    // Allocate memory, where floating values are held in `fm`, pointers to functions in `pm`
+    // and the others in `m`. Memory referring to a global variable is not allocated.
+    let mems = fun.memory_arena();
+    let locals = || mems.iter().filter(|(_, mem)| mem.global().is_none());
+    if locals().any(|(_, mem)| !mem.ty().is_float() && !is_fun_pointer(mem.ty())) {
+      write!(f, "\n  long long m[{}];", mems.len())?;
+    }
+    if locals().any(|(_, mem)| mem.ty().is_float()) {
+      write!(f, "\n  double fm[{}];", mems.len())?;
+    }
+    if locals().any(|(_, mem)| is_fun_pointer(mem.ty())) {
+      write!(f, "\n  void *pm[{}];", mems.len())?;
     }
 
     // Store function parameters to memory
-    for i in 0..fun.param_tys().len() {
-      write!(f, "\n  m[{i}] = a{i};")?;
+    for (i, (m1, _)) in mems.iter().take(fun.param_tys().len()).enumerate() {
+      write!(f, "\n  {} = a{};", memory(fun, module, m1), i)?;
     }
 
     // Emit function body
     for &block_id in fun.blocks() {
       write!(f, "\nblock{}:;", block_id.index())?;
       for &inst_id in fun.get(block_id).insts() {
-        gen_inst(f, fun.get(inst_id), funs)?;
+        gen_inst(f, fun.get(inst_id), fun, module)?;
       }
     }
 
@@ -49,76 +103,295 @@ fn gen_fun(f: &mut fmt::Formatter, fun: &Function, funs: &Arena<Function>) -> fm
   }
 }
 
-fn gen_inst(f: &mut fmt::Formatter, inst: &Inst, funs: &Arena<Function>) -> fmt::Result {
+fn gen_inst(f: &mut fmt::Formatter, inst: &Inst, fun: &Function, module: &Module) -> fmt::Result {
   match inst.kind() {
     InstKind::Eq(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} == r{};",
+      "\n  long long r{} = r{} == r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Ne(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} != r{};",
+      "\n  long long r{} = r{} != r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Lt(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} < r{};",
+      "\n  long long r{} = r{} < r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Le(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} <= r{};",
+      "\n  long long r{} = r{} <= r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::ULt(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} < (unsigned long long)r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::ULe(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} <= (unsigned long long)r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Add(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} + r{};",
+      "\n  long long r{} = (unsigned long long)r{} + r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Sub(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} - r{};",
+      "\n  long long r{} = (unsigned long long)r{} - r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Mul(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} * r{};",
+      "\n  long long r{} = (unsigned long long)r{} * r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
     InstKind::Div(v1, v2) => write!(
       f,
-      "\n  int r{} = r{} / r{};",
+      "\n  long long r{} = r{} / r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::Mod(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} % r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::UDiv(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} / (unsigned long long)r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::UMod(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} % (unsigned long long)r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::BitAnd(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} & r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::BitOr(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} | r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::BitXor(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} ^ r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::Shl(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} << r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::Shr(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} >> r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::UShr(v1, v2) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{} >> r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::BitNot(v1) => write!(
+      f,
+      "\n  long long r{} = ~r{};",
+      inst.id().index(),
+      v1.index()
+    ),
+    InstKind::SExt(v1, bits) => write!(
+      f,
+      "\n  long long r{} = ({})r{};",
+      inst.id().index(),
+      c_int_type(*bits, false),
+      v1.index()
+    ),
+    InstKind::ZExt(v1, bits) => write!(
+      f,
+      "\n  long long r{} = ({})r{};",
+      inst.id().index(),
+      c_int_type(*bits, true),
+      v1.index()
+    ),
+    InstKind::FEq(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} == r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FNe(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} != r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FLt(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} < r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FLe(v1, v2) => write!(
+      f,
+      "\n  long long r{} = r{} <= r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FAdd(v1, v2) => write!(
+      f,
+      "\n  double r{} = r{} + r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FSub(v1, v2) => write!(
+      f,
+      "\n  double r{} = r{} - r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FMul(v1, v2) => write!(
+      f,
+      "\n  double r{} = r{} * r{};",
+      inst.id().index(),
+      v1.index(),
+      v2.index()
+    ),
+    InstKind::FDiv(v1, v2) => write!(
+      f,
+      "\n  double r{} = r{} / r{};",
       inst.id().index(),
       v1.index(),
       v2.index()
     ),
-    InstKind::Load(m1) => write!(f, "\n  int r{} = m[{}];", inst.id().index(), m1.index()),
+    InstKind::FNeg(v1) => write!(f, "\n  double r{} = -r{};", inst.id().index(), v1.index()),
+    InstKind::IToF(v1) => write!(f, "\n  double r{} = r{};", inst.id().index(), v1.index()),
+    InstKind::UIToF(v1) => write!(
+      f,
+      "\n  double r{} = (unsigned long long)r{};",
+      inst.id().index(),
+      v1.index()
+    ),
+    InstKind::FToI(v1) => write!(f, "\n  long long r{} = r{};", inst.id().index(), v1.index()),
+    InstKind::FToUI(v1) => write!(
+      f,
+      "\n  long long r{} = (unsigned long long)r{};",
+      inst.id().index(),
+      v1.index()
+    ),
+    InstKind::FTrunc(v1) => write!(
+      f,
+      "\n  double r{} = (float)r{};",
+      inst.id().index(),
+      v1.index()
+    ),
+    InstKind::Load(m1) => write!(
+      f,
+      "\n  {} r{} = {};",
+      c_value_type(fun.get(*m1).ty()),
+      inst.id().index(),
+      memory(fun, module, *m1)
+    ),
     InstKind::Call(fun, args) => {
       let args = JoinView::new(args.iter().map(|id| format!("r{}", id.index())), ", ");
-      write!(
-        f,
-        "\n  int r{} = {}({});",
-        inst.id().index(),
-        funs.get(*fun).unwrap().name(),
-        args
-      )
+      let callee = module.get_function(*fun);
+      if callee.ret_ty() == &Type::Void {
+        write!(f, "\n  {}({});", callee.name(), args)
+      } else {
+        write!(
+          f,
+          "\n  {} r{} = {}({});",
+          c_value_type(callee.ret_ty()),
+          inst.id().index(),
+          callee.name(),
+          args
+        )
+      }
     }
-    InstKind::Const(n) => write!(f, "\n  int r{} = {};", inst.id().index(), n),
+    InstKind::CallIndirect(fun_ty, v1, args) => {
+      let args = JoinView::new(args.iter().map(|id| format!("r{}", id.index())), ", ");
+      let (ret_ty, param_tys, variadic) = match fun_ty {
+        Type::FunTy(ret_ty, param_tys, _, variadic) => (ret_ty, param_tys, variadic),
+        _ => unreachable!(),
+      };
+      let variadic = variadic.then(|| "...".to_string());
+      let callee = format!(
+        "(({} (*)({}))r{})",
+        c_type(ret_ty),
+        JoinView::new(param_tys.iter().map(c_type).chain(variadic), ", "),
+        v1.index()
+      );
+      if **ret_ty == Type::Void {
+        write!(f, "\n  {}({});", callee, args)
+      } else {
+        write!(
+          f,
+          "\n  {} r{} = {}({});",
+          c_value_type(ret_ty),
+          inst.id().index(),
+          callee,
+          args
+        )
+      }
+    }
+    InstKind::FunAddr(fun) => write!(
+      f,
+      "\n  void *r{} = (void *){};",
+      inst.id().index(),
+      module.get_function(*fun).name()
+    ),
+    InstKind::Const(n) => write!(f, "\n  long long r{} = {};", inst.id().index(), c_int(*n)),
+    InstKind::FConst(x) => write!(f, "\n  double r{} = {};", inst.id().index(), c_double(*x)),
     InstKind::Br(v1, block1, block2) => write!(
       f,
       "\n  if (r{}) goto block{}; else goto block{};",
@@ -126,8 +399,92 @@ fn gen_inst(f: &mut fmt::Formatter, inst: &Inst, funs: &Arena<Function>) -> fmt:
       block1.index(),
       block2.index()
     ),
+    InstKind::Switch(v1, default, cases) => {
+      write!(f, "\n  switch (r{}) {{", v1.index())?;
+      for (n, block) in cases {
+        write!(f, "\n  case {}: goto block{};", c_int(*n), block.index())?;
+      }
+      write!(f, "\n  default: goto block{};\n  }}", default.index())
+    }
     InstKind::Jmp(block1) => write!(f, "\n  goto block{};", block1.index()),
-    InstKind::Store(m1, v2) => write!(f, "\n  m[{}] = r{};", m1.index(), v2.index()),
-    InstKind::Ret(v1) => write!(f, "\n  return r{};", v1.index()),
+    InstKind::Store(m1, v2) => write!(f, "\n  {} = r{};", memory(fun, module, *m1), v2.index()),
+    InstKind::Ret(Some(v1)) => write!(f, "\n  return r{};", v1.index()),
+    InstKind::Ret(None) => write!(f, "\n  return;"),
+  }
+}
+
+/// Returns the C integer type of `bits` bits, through which a value is cast to sign- or
+/// zero-extend its lowest bits.
+fn c_int_type(bits: u32, is_unsigned: bool) -> &'static str {
+  match (bits, is_unsigned) {
+    (8, false) => "signed char",
+    (8, true) => "unsigned char",
+    (16, false) => "short",
+    (16, true) => "unsigned short",
+    (_, false) => "int",
+    (_, true) => "unsigned",
+  }
+}
+
+/// Returns the C type of a register holding a value of `ty`. Floating values are held as
+/// `double`, pointers to functions as `void *`, and the others as `long long`, in which integer
+/// arithmetic is computed in 64 bits. Arithmetic which wraps around is done in
+/// `unsigned long long` so that it does not overflow in C.
+fn c_value_type(ty: &Type) -> &'static str {
+  if ty.is_float() {
+    "double"
+  } else if is_fun_pointer(ty) {
+    "void *"
+  } else {
+    "long long"
+  }
+}
+
+/// Returns the C type of a parameter or return value of `ty`. Pointers to functions are
+/// passed as `void *` and cast back to their type when called.
+fn c_type(ty: &Type) -> String {
+  if is_fun_pointer(ty) {
+    "void *".to_string()
+  } else {
+    format!("{}", ty)
+  }
+}
+
+fn is_fun_pointer(ty: &Type) -> bool {
+  matches!(ty, Type::Pointer(ty) if matches!(**ty, Type::FunTy(..)))
+}
+
+/// Returns the C lvalue of memory `m1`.
+fn memory(fun: &Function, module: &Module, m1: MemoryId) -> String {
+  if let Some(global_id) = fun.get(m1).global() {
+    module.get_global(global_id).name().to_string()
+  } else if fun.get(m1).ty().is_float() {
+    format!("fm[{}]", m1.index())
+  } else if is_fun_pointer(fun.get(m1).ty()) {
+    format!("pm[{}]", m1.index())
+  } else {
+    format!("m[{}]", m1.index())
+  }
+}
+
+/// Returns a C expression of the `long long` value `n`. The magnitude of the minimum value does
+/// not fit in `long long`, so it is not written as a negated literal.
+fn c_int(n: i64) -> String {
+  if n == i64::MIN {
+    format!("({} - 1)", n + 1)
+  } else {
+    n.to_string()
+  }
+}
+
+/// Returns a C expression of the `double` value `x`, which may be infinite or a NaN.
+fn c_double(x: f64) -> String {
+  if x.is_nan() {
+    "(0.0 / 0.0)".to_string()
+  } else if x.is_infinite() {
+    format!("({}1.0 / 0.0)", if x < 0.0 { "-" } else { "" })
+  } else {
+    // `Debug` prints the shortest representation that reads back as the same value
+    format!("{:?}", x)
   }
 }
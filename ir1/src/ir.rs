@@ -2,6 +2,7 @@ pub mod block;
 pub mod builder;
 pub mod builder_trait;
 pub mod function;
+pub mod global;
 pub mod inst;
 pub mod irdump;
 pub mod memory;
@@ -1,8 +1,10 @@
 use crate::ir::block::*;
 use crate::ir::function::*;
+use crate::ir::global::*;
 use crate::ir::inst::*;
 use crate::ir::memory::*;
 use crate::ir::visitor_trait::*;
+use parser::ty::Type;
 
 pub trait BuilderTrait: VisitorTrait {
   fn function_mut(&mut self) -> &mut Function;
@@ -43,27 +45,62 @@ pub trait BuilderTrait: VisitorTrait {
       | Ne(v1, v2)
       | Lt(v1, v2)
       | Le(v1, v2)
+      | ULt(v1, v2)
+      | ULe(v1, v2)
       | Add(v1, v2)
       | Sub(v1, v2)
       | Mul(v1, v2)
-      | Div(v1, v2) => {
+      | Div(v1, v2)
+      | Mod(v1, v2)
+      | UDiv(v1, v2)
+      | UMod(v1, v2)
+      | BitAnd(v1, v2)
+      | BitOr(v1, v2)
+      | BitXor(v1, v2)
+      | Shl(v1, v2)
+      | Shr(v1, v2)
+      | UShr(v1, v2)
+      | FEq(v1, v2)
+      | FNe(v1, v2)
+      | FLt(v1, v2)
+      | FLe(v1, v2)
+      | FAdd(v1, v2)
+      | FSub(v1, v2)
+      | FMul(v1, v2)
+      | FDiv(v1, v2) => {
         self.function_mut().get_mut(v1).remove_use(inst_id);
         self.function_mut().get_mut(v2).remove_use(inst_id);
       }
+      BitNot(v1)
+      | SExt(v1, _)
+      | ZExt(v1, _)
+      | IToF(v1)
+      | UIToF(v1)
+      | FToI(v1)
+      | FToUI(v1)
+      | FTrunc(v1)
+      | FNeg(v1) => self.function_mut().get_mut(v1).remove_use(inst_id),
       Load(m1) => self.function_mut().get_mut(m1).remove_load(inst_id),
       Call(_, args) => {
         for arg in args {
           self.function_mut().get_mut(arg).remove_use(inst_id);
         }
       }
-      Const(_) => (),
-      Br(v1, _, _) => self.function_mut().get_mut(v1).remove_use(inst_id),
+      CallIndirect(_, v1, args) => {
+        self.function_mut().get_mut(v1).remove_use(inst_id);
+        for arg in args {
+          self.function_mut().get_mut(arg).remove_use(inst_id);
+        }
+      }
+      FunAddr(_) | Const(_) | FConst(_) => (),
+      Br(v1, _, _) | Switch(v1, _, _) => self.function_mut().get_mut(v1).remove_use(inst_id),
       Jmp(_) => (),
       Store(m1, v1) => {
         self.function_mut().get_mut(m1).remove_store(inst_id);
         self.function_mut().get_mut(v1).remove_use(inst_id);
       }
-      Ret(v1) => self.function_mut().get_mut(v1).remove_use(inst_id),
+      Ret(Some(v1)) => self.function_mut().get_mut(v1).remove_use(inst_id),
+      Ret(None) => (),
     }
 
     self.position_at_index(block_id, index);
@@ -108,6 +145,20 @@ pub trait BuilderTrait: VisitorTrait {
     v0
   }
 
+  fn build_ult(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::ULt(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_ule(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::ULe(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
   fn build_add(&mut self, v1: InstId, v2: InstId) -> InstId {
     let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Add(v1, v2), id));
     self.function_mut().get_mut(v1).append_use(v0);
@@ -136,6 +187,179 @@ pub trait BuilderTrait: VisitorTrait {
     v0
   }
 
+  fn build_mod(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Mod(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_udiv(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::UDiv(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_umod(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::UMod(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_bit_and(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::BitAnd(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_bit_or(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::BitOr(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_bit_xor(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::BitXor(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_shl(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Shl(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_shr(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Shr(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_ushr(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::UShr(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_bit_not(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::BitNot(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_sext(&mut self, v1: InstId, bits: u32) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::SExt(v1, bits), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_zext(&mut self, v1: InstId, bits: u32) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::ZExt(v1, bits), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_feq(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FEq(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fne(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FNe(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_flt(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FLt(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fle(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FLe(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fadd(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FAdd(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fsub(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FSub(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fmul(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FMul(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fdiv(&mut self, v1: InstId, v2: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FDiv(v1, v2), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    self.function_mut().get_mut(v2).append_use(v0);
+    v0
+  }
+
+  fn build_fneg(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FNeg(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_itof(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::IToF(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_uitof(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::UIToF(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_ftoi(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FToI(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_ftoui(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FToUI(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
+  fn build_ftrunc(&mut self, v1: InstId) -> InstId {
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::FTrunc(v1), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
   fn build_load(&mut self, m1: MemoryId) -> InstId {
     let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Load(m1), id));
     self.function_mut().get_mut(m1).append_load(v0);
@@ -150,11 +374,29 @@ pub trait BuilderTrait: VisitorTrait {
     v0
   }
 
+  fn build_call_indirect(&mut self, fun_ty: Type, v1: InstId, args: Vec<InstId>) -> InstId {
+    let v0 =
+      self.build_inst_with_id(|id| Inst::new(InstKind::CallIndirect(fun_ty, v1, args.clone()), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    for inst_id in args {
+      self.function_mut().get_mut(inst_id).append_use(v0);
+    }
+    v0
+  }
+
+  fn build_fun_addr(&mut self, fun_id: FunctionId) -> InstId {
+    self.build_inst_with_id(|id| Inst::new(InstKind::FunAddr(fun_id), id))
+  }
+
   fn build_const(&mut self, n: i64) -> InstId {
     let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Const(n), id));
     v0
   }
 
+  fn build_fconst(&mut self, x: f64) -> InstId {
+    self.build_inst_with_id(|id| Inst::new(InstKind::FConst(x), id))
+  }
+
   // ----- inst -> effect -----
 
   fn build_conditional_branch(&mut self, v1: InstId, block1: BlockId, block2: BlockId) -> InstId {
@@ -169,6 +411,18 @@ pub trait BuilderTrait: VisitorTrait {
     v0
   }
 
+  fn build_switch(&mut self, v1: InstId, default: BlockId, cases: Vec<(i64, BlockId)>) -> InstId {
+    let block0 = self.get_insert_block().unwrap();
+    for block1 in cases.iter().map(|&(_, block)| block).chain([default]) {
+      self.function_mut().get_mut(block0).append_succ(block1);
+      self.function_mut().get_mut(block1).append_pred(block0);
+    }
+
+    let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Switch(v1, default, cases), id));
+    self.function_mut().get_mut(v1).append_use(v0);
+    v0
+  }
+
   fn build_unconditional_branch(&mut self, block1: BlockId) -> InstId {
     let block0 = self.get_insert_block().unwrap();
     self.function_mut().get_mut(block0).append_succ(block1);
@@ -185,15 +439,21 @@ pub trait BuilderTrait: VisitorTrait {
     v0
   }
 
-  fn build_return(&mut self, v1: InstId) -> InstId {
+  fn build_return(&mut self, v1: Option<InstId>) -> InstId {
     let v0 = self.build_inst_with_id(|id| Inst::new(InstKind::Ret(v1), id));
-    self.function_mut().get_mut(v1).append_use(v0);
+    if let Some(v1) = v1 {
+      self.function_mut().get_mut(v1).append_use(v0);
+    }
     v0
   }
 
   // ----- memory -----
 
-  fn build_alloca(&mut self) -> MemoryId {
-    self.function_mut().append_memory()
+  fn build_alloca(&mut self, ty: Type) -> MemoryId {
+    self.function_mut().append_memory(ty)
+  }
+
+  fn build_global_memory(&mut self, ty: Type, global_id: GlobalId) -> MemoryId {
+    self.function_mut().append_global_memory(ty, global_id)
   }
 }
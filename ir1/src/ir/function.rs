@@ -1,14 +1,28 @@
 use crate::ir::block::*;
+use crate::ir::global::*;
 use crate::ir::inst::*;
 use crate::ir::memory::*;
 use id_arena::{Arena, Id};
 use parser::ty::Type;
 
+/// Linkage of a function or a global variable, which decides whether other modules can refer to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+  /// Visible to other modules.
+  External,
+  /// Only visible in its own module, as a `static` function is.
+  Internal,
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
   name: String,
   ret_ty: Type,
   param_tys: Vec<Type>,
+  // Whether the function takes variable arguments after the parameters, which only an
+  // external declaration can
+  variadic: bool,
+  linkage: Linkage,
   blocks: Vec<BlockId>,
   block_arena: Arena<Block>,
   inst_arena: Arena<Inst>,
@@ -23,6 +37,8 @@ impl Function {
       name,
       ret_ty,
       param_tys,
+      variadic: false,
+      linkage: Linkage::External,
       blocks: Vec::new(),
       block_arena: Arena::new(),
       inst_arena: Arena::new(),
@@ -48,6 +64,22 @@ impl Function {
     self.param_tys.as_slice()
   }
 
+  pub fn is_variadic(&self) -> bool {
+    self.variadic
+  }
+
+  pub fn set_variadic(&mut self, variadic: bool) {
+    self.variadic = variadic;
+  }
+
+  pub fn linkage(&self) -> Linkage {
+    self.linkage
+  }
+
+  pub fn set_linkage(&mut self, linkage: Linkage) {
+    self.linkage = linkage;
+  }
+
   pub fn blocks(&self) -> &[BlockId] {
     self.blocks.as_slice()
   }
@@ -125,8 +157,12 @@ impl Function {
 
   // ----- memory -----
 
-  pub fn append_memory(&mut self) -> MemoryId {
-    self.memory_arena.alloc(Memory::new())
+  pub fn append_memory(&mut self, ty: Type) -> MemoryId {
+    self.memory_arena.alloc(Memory::new(ty))
+  }
+
+  pub fn append_global_memory(&mut self, ty: Type, global_id: GlobalId) -> MemoryId {
+    self.memory_arena.alloc(Memory::new_global(ty, global_id))
   }
 }
 
@@ -0,0 +1,63 @@
+use crate::ir::function::Linkage;
+use id_arena::Id;
+use parser::ty::Type;
+
+/// Constant initial value of a global variable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlobalInit {
+  Int(i64),
+  Float(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Global {
+  name: String,
+  ty: Type,
+  // Initial value of the variable, which is `None` if it is only declared and defined
+  // elsewhere, as an `extern` variable is
+  init: Option<GlobalInit>,
+  linkage: Linkage,
+}
+
+pub type GlobalId = Id<Global>;
+
+impl Global {
+  pub fn new(name: String, ty: Type) -> Global {
+    Global {
+      name,
+      ty,
+      init: None,
+      linkage: Linkage::External,
+    }
+  }
+
+  pub fn is_declaration(&self) -> bool {
+    self.init.is_none()
+  }
+
+  // ----- accessor -----
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn ty(&self) -> &Type {
+    &self.ty
+  }
+
+  pub fn init(&self) -> Option<GlobalInit> {
+    self.init
+  }
+
+  pub fn set_init(&mut self, init: GlobalInit) {
+    self.init = Some(init);
+  }
+
+  pub fn linkage(&self) -> Linkage {
+    self.linkage
+  }
+
+  pub fn set_linkage(&mut self, linkage: Linkage) {
+    self.linkage = linkage;
+  }
+}
@@ -2,6 +2,7 @@ use crate::ir::block::BlockId;
 use crate::ir::function::FunctionId;
 use crate::ir::memory::MemoryId;
 use id_arena::Id;
+use parser::ty::Type;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
@@ -11,18 +12,65 @@ pub enum InstKind {
   Ne(InstId, InstId),
   Lt(InstId, InstId),
   Le(InstId, InstId),
+  ULt(InstId, InstId),
+  ULe(InstId, InstId),
   Add(InstId, InstId),
   Sub(InstId, InstId),
   Mul(InstId, InstId),
   Div(InstId, InstId),
+  Mod(InstId, InstId),
+  UDiv(InstId, InstId),
+  UMod(InstId, InstId),
+  BitAnd(InstId, InstId),
+  BitOr(InstId, InstId),
+  BitXor(InstId, InstId),
+  Shl(InstId, InstId),
+  Shr(InstId, InstId),
+  UShr(InstId, InstId),
+  BitNot(InstId),
+  /// Sign-extends the value of the lowest bits of the operand, whose number is given, to the
+  /// whole value. Used for conversions to narrower signed integer types.
+  SExt(InstId, u32),
+  /// Zero-extends the value of the lowest bits of the operand, whose number is given, to the
+  /// whole value. Used for conversions to narrower unsigned integer types.
+  ZExt(InstId, u32),
+  // Floating operations take and yield `double` values. A `float` value is held as a `double`
+  // rounded to single precision, which makes `float` arithmetic exact when rounded by `FTrunc`.
+  FEq(InstId, InstId),
+  FNe(InstId, InstId),
+  FLt(InstId, InstId),
+  FLe(InstId, InstId),
+  FAdd(InstId, InstId),
+  FSub(InstId, InstId),
+  FMul(InstId, InstId),
+  FDiv(InstId, InstId),
+  /// Negates a floating value, which flips the sign of a zero unlike subtracting it from zero.
+  FNeg(InstId),
+  /// Converts a signed integer to a floating value.
+  IToF(InstId),
+  /// Converts an unsigned integer to a floating value.
+  UIToF(InstId),
+  /// Converts a floating value to a signed integer, truncating it toward zero.
+  FToI(InstId),
+  /// Converts a floating value to an unsigned integer, truncating it toward zero.
+  FToUI(InstId),
+  /// Rounds a floating value to single precision. Used for conversions to `float`.
+  FTrunc(InstId),
   Load(MemoryId),
   Call(FunctionId, Vec<InstId>),
+  /// Calls the function the operand points to, whose function type is given.
+  CallIndirect(Type, InstId, Vec<InstId>),
+  /// Yields a pointer to the function.
+  FunAddr(FunctionId),
   Const(i64),
+  FConst(f64),
   // Effect
   Br(InstId, BlockId, BlockId),
+  /// Jumps to the block of the case whose value equals the operand, or to the default block.
+  Switch(InstId, BlockId, Vec<(i64, BlockId)>),
   Jmp(BlockId),
   Store(MemoryId, InstId),
-  Ret(InstId),
+  Ret(Option<InstId>),
 }
 
 #[derive(Debug, Clone)]
@@ -57,8 +105,65 @@ impl Inst {
 
   // ----- kind -----
 
-  pub fn replace_kind(&mut self, _from: InstId, _to: InstId) {
-    todo!()
+  /// Replaces every operand `from` of this inst with `to`.
+  pub fn replace_kind(&mut self, from: InstId, to: InstId) {
+    use InstKind::*;
+    let replace = |v: &mut InstId| {
+      if *v == from {
+        *v = to;
+      }
+    };
+    match &mut self.kind {
+      Eq(v1, v2)
+      | Ne(v1, v2)
+      | Lt(v1, v2)
+      | Le(v1, v2)
+      | ULt(v1, v2)
+      | ULe(v1, v2)
+      | Add(v1, v2)
+      | Sub(v1, v2)
+      | Mul(v1, v2)
+      | Div(v1, v2)
+      | Mod(v1, v2)
+      | UDiv(v1, v2)
+      | UMod(v1, v2)
+      | BitAnd(v1, v2)
+      | BitOr(v1, v2)
+      | BitXor(v1, v2)
+      | Shl(v1, v2)
+      | Shr(v1, v2)
+      | UShr(v1, v2)
+      | FEq(v1, v2)
+      | FNe(v1, v2)
+      | FLt(v1, v2)
+      | FLe(v1, v2)
+      | FAdd(v1, v2)
+      | FSub(v1, v2)
+      | FMul(v1, v2)
+      | FDiv(v1, v2) => {
+        replace(v1);
+        replace(v2);
+      }
+      Call(_, args) => args.iter_mut().for_each(replace),
+      CallIndirect(_, v, args) => {
+        replace(v);
+        args.iter_mut().for_each(replace);
+      }
+      BitNot(v)
+      | SExt(v, _)
+      | ZExt(v, _)
+      | IToF(v)
+      | UIToF(v)
+      | FToI(v)
+      | FToUI(v)
+      | FTrunc(v)
+      | FNeg(v)
+      | Br(v, ..)
+      | Switch(v, ..)
+      | Store(_, v)
+      | Ret(Some(v)) => replace(v),
+      Load(..) | FunAddr(..) | Const(..) | FConst(..) | Jmp(..) | Ret(None) => (),
+    }
   }
 
   // ----- use -----
@@ -76,7 +181,15 @@ impl Inst {
 
 pub fn has_side_effect(inst: &Inst) -> bool {
   use InstKind::*;
-  matches!(inst.kind(), Br(..) | Jmp(..) | Store(..) | Ret(..))
+  matches!(
+    inst.kind(),
+    Call(..) | CallIndirect(..) | Br(..) | Switch(..) | Jmp(..) | Store(..) | Ret(..)
+  )
+}
+
+pub fn is_terminator(inst: &Inst) -> bool {
+  use InstKind::*;
+  matches!(inst.kind(), Br(..) | Switch(..) | Jmp(..) | Ret(..))
 }
 
 pub fn is_dead(inst: &Inst, deadness: &mut HashMap<InstId, bool>) -> bool {
@@ -1,4 +1,5 @@
 use crate::ir::function::*;
+use crate::ir::global::*;
 use crate::ir::inst::*;
 use crate::ir::module::*;
 use parser::common::JoinView;
@@ -22,27 +23,79 @@ impl<'a, 'b> DumpIr<'a, 'b> {
 
   pub fn run_on_module(&mut self) -> fmt::Result {
     write!(self.f, "; ModuleName = '{}'", self.module.name())?;
+    for (_id, global) in self.module.globals().iter() {
+      self.run_on_global(global)?;
+    }
     for (_id, fun) in self.module.functions().iter() {
       self.run_on_function(fun)?;
     }
     Ok(())
   }
 
+  fn run_on_global(&mut self, global: &Global) -> fmt::Result {
+    let linkage = match global.linkage() {
+      Linkage::External => "",
+      Linkage::Internal => "internal ",
+    };
+    match global.init() {
+      Some(GlobalInit::Int(n)) => write!(
+        self.f,
+        "\n\n{}global {}: {} = {}",
+        linkage,
+        global.name(),
+        global.ty(),
+        n
+      ),
+      Some(GlobalInit::Float(x)) => write!(
+        self.f,
+        "\n\n{}global {}: {} = {:?}",
+        linkage,
+        global.name(),
+        global.ty(),
+        x
+      ),
+      None => write!(
+        self.f,
+        "\n\ndeclare {}global {}: {}",
+        linkage,
+        global.name(),
+        global.ty()
+      ),
+    }
+  }
+
   fn run_on_function(&mut self, fun: &Function) -> fmt::Result {
-    let iter = fun.param_tys().iter();
-    let param_tys = JoinView::new(iter, ", ");
+    let iter = fun.param_tys().iter().map(|ty| ty.to_string());
+    let variadic = fun.is_variadic().then(|| "...".to_string());
+    let param_tys = JoinView::new(iter.chain(variadic), ", ");
+    let linkage = match fun.linkage() {
+      Linkage::External => "",
+      Linkage::Internal => "internal ",
+    };
 
     if fun.is_declaration() {
-      return write!(self.f, "\n\ndeclare {}({})", fun.name(), param_tys);
+      return write!(
+        self.f,
+        "\n\ndeclare {}{}({})",
+        linkage,
+        fun.name(),
+        param_tys
+      );
     }
 
     // Emit function return type, name and parameters
-    write!(self.f, "\n\n{}({}):", fun.name(), param_tys)?;
+    write!(self.f, "\n\n{}{}({}):", linkage, fun.name(), param_tys)?;
 
     // Allocate memory
     if fun.memory_arena().len() != 0 {
       write!(self.f, "\n  m = alloca {}", fun.memory_arena().len())?;
     }
+    for (m1, mem) in fun.memory_arena().iter() {
+      if let Some(global_id) = mem.global() {
+        let name = self.module.get_global(global_id).name();
+        write!(self.f, "\n  m{} = global {}", m1.index(), name)?;
+      }
+    }
 
     // Emit function body
     for &block_id in fun.blocks() {
@@ -88,6 +141,20 @@ impl<'a, 'b> DumpIr<'a, 'b> {
         v1.index(),
         v2.index()
       ),
+      InstKind::ULt(v1, v2) => write!(
+        self.f,
+        "\n  r{} = ult r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::ULe(v1, v2) => write!(
+        self.f,
+        "\n  r{} = ule r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
       InstKind::Add(v1, v2) => write!(
         self.f,
         "\n  r{} = add r{}, r{}",
@@ -116,6 +183,151 @@ impl<'a, 'b> DumpIr<'a, 'b> {
         v1.index(),
         v2.index()
       ),
+      InstKind::Mod(v1, v2) => write!(
+        self.f,
+        "\n  r{} = mod r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::UDiv(v1, v2) => write!(
+        self.f,
+        "\n  r{} = udiv r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::UMod(v1, v2) => write!(
+        self.f,
+        "\n  r{} = umod r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::BitAnd(v1, v2) => write!(
+        self.f,
+        "\n  r{} = and r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::BitOr(v1, v2) => write!(
+        self.f,
+        "\n  r{} = or r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::BitXor(v1, v2) => write!(
+        self.f,
+        "\n  r{} = xor r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::Shl(v1, v2) => write!(
+        self.f,
+        "\n  r{} = shl r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::Shr(v1, v2) => write!(
+        self.f,
+        "\n  r{} = shr r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::UShr(v1, v2) => write!(
+        self.f,
+        "\n  r{} = ushr r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::BitNot(v1) => write!(self.f, "\n  r{} = not r{}", inst.id().index(), v1.index()),
+      InstKind::SExt(v1, bits) => write!(
+        self.f,
+        "\n  r{} = sext r{}, {}",
+        inst.id().index(),
+        v1.index(),
+        bits
+      ),
+      InstKind::ZExt(v1, bits) => write!(
+        self.f,
+        "\n  r{} = zext r{}, {}",
+        inst.id().index(),
+        v1.index(),
+        bits
+      ),
+      InstKind::FEq(v1, v2) => write!(
+        self.f,
+        "\n  r{} = feq r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FNe(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fne r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FLt(v1, v2) => write!(
+        self.f,
+        "\n  r{} = flt r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FLe(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fle r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FAdd(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fadd r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FSub(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fsub r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FMul(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fmul r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FDiv(v1, v2) => write!(
+        self.f,
+        "\n  r{} = fdiv r{}, r{}",
+        inst.id().index(),
+        v1.index(),
+        v2.index()
+      ),
+      InstKind::FNeg(v1) => write!(self.f, "\n  r{} = fneg r{}", inst.id().index(), v1.index()),
+      InstKind::IToF(v1) => write!(self.f, "\n  r{} = itof r{}", inst.id().index(), v1.index()),
+      InstKind::UIToF(v1) => write!(self.f, "\n  r{} = uitof r{}", inst.id().index(), v1.index()),
+      InstKind::FToI(v1) => write!(self.f, "\n  r{} = ftoi r{}", inst.id().index(), v1.index()),
+      InstKind::FToUI(v1) => write!(self.f, "\n  r{} = ftoui r{}", inst.id().index(), v1.index()),
+      InstKind::FTrunc(v1) => write!(
+        self.f,
+        "\n  r{} = ftrunc r{}",
+        inst.id().index(),
+        v1.index()
+      ),
       InstKind::Load(m1) => write!(self.f, "\n  r{} = load m{}", inst.id().index(), m1.index()),
       InstKind::Call(fun_id, args) => {
         let args = JoinView::new(args.iter().map(|id| format!("r{}", id.index())), ", ");
@@ -127,7 +339,24 @@ impl<'a, 'b> DumpIr<'a, 'b> {
           args
         )
       }
+      InstKind::CallIndirect(_, v1, args) => {
+        let args = JoinView::new(args.iter().map(|id| format!("r{}", id.index())), ", ");
+        write!(
+          self.f,
+          "\n  r{} = call_indirect r{}({});",
+          inst.id().index(),
+          v1.index(),
+          args
+        )
+      }
+      InstKind::FunAddr(fun_id) => write!(
+        self.f,
+        "\n  r{} = funaddr {}",
+        inst.id().index(),
+        self.module.get_function(*fun_id).name()
+      ),
       InstKind::Const(n) => write!(self.f, "\n  r{} = const {}", inst.id().index(), n),
+      InstKind::FConst(x) => write!(self.f, "\n  r{} = fconst {:?}", inst.id().index(), x),
       InstKind::Br(v1, block1, block2) => {
         write!(
           self.f,
@@ -137,9 +366,22 @@ impl<'a, 'b> DumpIr<'a, 'b> {
           block2.index()
         )
       }
+      InstKind::Switch(v1, default, cases) => {
+        let cases = cases
+          .iter()
+          .map(|(n, block)| format!("{}: block{}", n, block.index()));
+        write!(
+          self.f,
+          "\n  switch r{}, block{} [{}]",
+          v1.index(),
+          default.index(),
+          JoinView::new(cases, ", ")
+        )
+      }
       InstKind::Jmp(block1) => write!(self.f, "\n  jmp block{}", block1.index()),
       InstKind::Store(m1, v2) => write!(self.f, "\n  store m{}, r{}", m1.index(), v2.index()),
-      InstKind::Ret(v1) => write!(self.f, "\n  ret r{}", v1.index()),
+      InstKind::Ret(Some(v1)) => write!(self.f, "\n  ret r{}", v1.index()),
+      InstKind::Ret(None) => write!(self.f, "\n  ret"),
     }
   }
 }
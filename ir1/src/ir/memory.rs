@@ -1,9 +1,15 @@
+use crate::ir::global::GlobalId;
 use crate::ir::inst::InstId;
 use id_arena::Id;
+use parser::ty::Type;
 use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
 pub struct Memory {
+  /// Type of the value held in the memory.
+  ty: Type,
+  // Global variable which the memory refers to, instead of being allocated in the function
+  global: Option<GlobalId>,
   store: HashSet<InstId>,
   load: HashSet<InstId>,
 }
@@ -11,13 +17,30 @@ pub struct Memory {
 pub type MemoryId = Id<Memory>;
 
 impl Memory {
-  pub fn new() -> Memory {
+  pub fn new(ty: Type) -> Memory {
     Memory {
+      ty,
+      global: None,
       store: HashSet::new(),
       load: HashSet::new(),
     }
   }
 
+  pub fn new_global(ty: Type, global_id: GlobalId) -> Memory {
+    Memory {
+      global: Some(global_id),
+      ..Memory::new(ty)
+    }
+  }
+
+  pub fn ty(&self) -> &Type {
+    &self.ty
+  }
+
+  pub fn global(&self) -> Option<GlobalId> {
+    self.global
+  }
+
   // ----- store -----
 
   pub fn append_store(&mut self, inst_id: InstId) {
@@ -1,9 +1,11 @@
 use crate::ir::function::*;
+use crate::ir::global::*;
 use id_arena::*;
 
 pub struct Module {
   name: String,
   functions: Arena<Function>,
+  globals: Arena<Global>,
 }
 
 impl Module {
@@ -11,6 +13,7 @@ impl Module {
     Module {
       name,
       functions: Arena::new(),
+      globals: Arena::new(),
     }
   }
 
@@ -22,6 +25,10 @@ impl Module {
     &self.functions
   }
 
+  pub fn globals(&self) -> &Arena<Global> {
+    &self.globals
+  }
+
   pub fn get_function(&self, fun_id: FunctionId) -> &Function {
     self.functions.get(fun_id).unwrap()
   }
@@ -51,4 +58,26 @@ impl Module {
   pub fn replace_function(&mut self, fun_id: FunctionId, fun: Function) {
     *self.functions.get_mut(fun_id).unwrap() = fun;
   }
+
+  // ----- global -----
+
+  pub fn get_global(&self, global_id: GlobalId) -> &Global {
+    self.globals.get(global_id).unwrap()
+  }
+
+  pub fn get_global_mut(&mut self, global_id: GlobalId) -> &mut Global {
+    self.globals.get_mut(global_id).unwrap()
+  }
+
+  pub fn get_global_by_name(&self, name: &str) -> Option<GlobalId> {
+    self
+      .globals
+      .iter()
+      .find(|(_id, global)| global.name() == name)
+      .map(|(id, _global)| id)
+  }
+
+  pub fn add_global(&mut self, global: Global) -> GlobalId {
+    self.globals.alloc(global)
+  }
 }
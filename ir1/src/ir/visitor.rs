@@ -73,7 +73,7 @@ fn test_ir_visitor() {
 
   builder.position_at_end(next_block);
   let v4 = builder.build_add(v3, v2);
-  builder.build_return(v4);
+  builder.build_return(Some(v4));
 
   let fun = builder.retrieve_function();
   let mut vis = Visitor::new(&fun);
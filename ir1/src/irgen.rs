@@ -1,23 +1,32 @@
 use crate::ir::{
-  block::*, builder::*, builder_trait::*, function::*, inst::*, memory::*, module::*,
+  block::*, builder::*, builder_trait::*, function::*, global::*, inst::*, memory::*, module::*,
   visitor_trait::*,
 };
+use crate::pass::folder::ConstantFolding;
 use parser::common::{self, Expected};
+use parser::diagnostic::Diagnostic;
 use parser::err;
-use parser::parse::{Stmt, TopLevel, AST};
+use parser::parse::{
+  self, ASTKind, Init, Stmt, StmtKind, StorageClass, TopLevel, TopLevelKind, AST,
+};
+use parser::source::Span;
 use parser::ty::Type;
+use std::collections::HashMap;
 type Scope = common::Scope<MemoryId>;
 
 // ----- irgen -----
 
 pub struct IRGen {
   module: Module,
+  // Location of the latest definition, or of the first declaration, of each function
+  decl_spans: HashMap<FunctionId, Span>,
 }
 
 impl IRGen {
   pub fn new(name: String) -> IRGen {
     IRGen {
       module: Module::new(name),
+      decl_spans: HashMap::new(),
     }
   }
 
@@ -29,21 +38,49 @@ impl IRGen {
   }
 
   fn gen_toplevel(&mut self, fun: TopLevel) -> Expected<()> {
-    match fun {
-      TopLevel::FunDecl(ret_ty, name, param_tys) => {
-        self.gen_fun_decl(ret_ty, name, param_tys)?;
+    let span = fun.span;
+    match fun.kind {
+      TopLevelKind::FunDecl(ret_ty, name, param_tys, variadic, storage) => {
+        self.gen_fun_decl(ret_ty, name, param_tys, variadic, storage, span)?;
         Ok(())
       }
-      TopLevel::FunDef(ret_ty, name, param_tys, param_names, body) => {
+      TopLevelKind::FunDef(ret_ty, name, param_tys, param_names, variadic, body, storage) => {
+        if variadic {
+          return err!(
+            span,
+            "definition of variadic function `{}` is not supported",
+            name
+          );
+        }
         // Check consistency with forward declaration
-        let fun_id = self.gen_fun_decl(ret_ty, name, param_tys)?;
+        let fun_id = self.gen_fun_decl(ret_ty, name, param_tys, variadic, storage, span)?;
+        // Check function is not defined
+        if !self.module.get_function(fun_id).is_declaration() {
+          let name = self.module.get_function(fun_id).name();
+          return Err(
+            Diagnostic::error(format!("redefinition of function `{}`", name))
+              .at(span)
+              .with_label(self.decl_spans[&fun_id], "previous definition is here"),
+          );
+        }
+        self.decl_spans.insert(fun_id, span);
         let new_fun = self.module.get_function(fun_id).clone();
-        let new_fun = GenFun::new(&mut self.module, new_fun).gen_fun(param_names, body)?;
+        let new_fun = GenFun::new(&mut self.module, new_fun)
+          .gen_fun(param_names, body, span)
+          .map_err(|e| e.or_at(span))?;
         self.module.replace_function(fun_id, new_fun);
         Ok(())
       }
-      TopLevel::VarDef(..) => todo!(),
-      TopLevel::StructDef(..) => todo!(),
+      TopLevelKind::VarDef(ty, name, init, storage) => {
+        if storage == StorageClass::Extern && init.is_none() {
+          declare_global(&mut self.module, ty, &name, span)?;
+        } else {
+          define_global(&mut self.module, ty, &name, init, storage, span)?;
+        }
+        Ok(())
+      }
+      // the parser has already computed the layout of the struct or union
+      TopLevelKind::StructDef(_) => Ok(()),
     }
   }
 
@@ -52,29 +89,88 @@ impl IRGen {
     ret_ty: Type,
     name: String,
     param_tys: Vec<Type>,
+    variadic: bool,
+    storage: StorageClass,
+    span: Span,
   ) -> Expected<FunctionId> {
+    if self.module.get_global_by_name(&name).is_some() {
+      return err!(span, "`{}` redeclared as a different kind of symbol", name);
+    }
     if let Some(fun_id) = self.module.get_function_by_name(&name) {
       let previous_ret_ty = self.module.get_function(fun_id).ret_ty();
       let previous_param_tys = self.module.get_function(fun_id).param_tys();
-      if &ret_ty == previous_ret_ty && &param_tys == previous_param_tys {
+      let previous_variadic = self.module.get_function(fun_id).is_variadic();
+      let previous_linkage = self.module.get_function(fun_id).linkage();
+      if storage == StorageClass::Static && previous_linkage == Linkage::External {
+        // a function declared without `static` keeps its external linkage
+        Err(
+          Diagnostic::error(format!(
+            "static declaration of `{}` follows non-static declaration",
+            name
+          ))
+          .at(span)
+          .with_label(self.decl_spans[&fun_id], "previous declaration is here"),
+        )
+      } else if &ret_ty == previous_ret_ty
+        && param_tys == previous_param_tys
+        && variadic == previous_variadic
+      {
         Ok(fun_id)
       } else {
-        err!("function type differs from the previous declaration")
+        let previous_ty = Type::FunTy(
+          Box::new(previous_ret_ty.clone()),
+          previous_param_tys.to_vec(),
+          Vec::new(),
+          previous_variadic,
+        );
+        let ty = Type::FunTy(Box::new(ret_ty), param_tys, Vec::new(), variadic);
+        Err(
+          Diagnostic::error(format!(
+            "function type differs from the previous declaration of `{}`",
+            name
+          ))
+          .with_primary(span, format!("`{}` declared here as `{}`", name, ty))
+          .with_label(
+            self.decl_spans[&fun_id],
+            format!("previous declaration of `{}` as `{}`", name, previous_ty),
+          ),
+        )
       }
     } else {
-      let fun = Function::new(name, ret_ty, param_tys);
+      let mut fun = Function::new(name, ret_ty, param_tys);
+      fun.set_variadic(variadic);
+      if storage == StorageClass::Static {
+        fun.set_linkage(Linkage::Internal);
+      }
       let fun_id = self.module.add_function(fun);
+      self.decl_spans.insert(fun_id, span);
       Ok(fun_id)
     }
   }
 }
 
+/// Blocks of the `case` and `default` labels of a switch statement.
+#[derive(Default)]
+struct SwitchLabels {
+  cases: Vec<(i64, BlockId)>,
+  default: Option<BlockId>,
+}
+
 struct GenFun<'a> {
   module: &'a mut Module,
   builder: Builder,
   scope: Scope,
   break_label: Vec<BlockId>,
   cont_label: Vec<BlockId>,
+  switch_labels: Vec<SwitchLabels>,
+  // Block of each label in the function, and the location of a `goto` to it while it is not
+  // defined yet
+  labels: HashMap<String, (BlockId, Option<Span>)>,
+  // Value of the lhs of each enclosing compound assignment or postfix increment or decrement
+  // before the assignment
+  assigned_values: Vec<InstId>,
+  // Memory referring to each global variable used in the function
+  globals: HashMap<GlobalId, MemoryId>,
 }
 
 impl<'a> GenFun<'a> {
@@ -85,17 +181,21 @@ impl<'a> GenFun<'a> {
       scope: Scope::new(),
       break_label: Vec::new(),
       cont_label: Vec::new(),
+      switch_labels: Vec::new(),
+      labels: HashMap::new(),
+      assigned_values: Vec::new(),
+      globals: HashMap::new(),
     }
   }
 
   // ----- gen_fun -----
 
-  fn gen_fun(mut self, param_names: Vec<String>, body: Vec<Stmt>) -> Expected<Function> {
-    // Check function is not defined
-    if !self.builder.function().is_declaration() {
-      return err!("function already exists");
-    }
-
+  fn gen_fun(
+    mut self,
+    param_names: Vec<String>,
+    body: Vec<Stmt>,
+    span: Span,
+  ) -> Expected<Function> {
     // Create entry block
     let bb = self.builder.append_basic_block();
     self.builder.position_at_end(bb);
@@ -115,22 +215,32 @@ impl<'a> GenFun<'a> {
       if self.scope.get(&name).is_none() {
         self.create_entry_block_alloca(ty, name);
       } else {
-        return err!("function parameter already exists");
+        return err!(span, "function parameter `{}` already exists", name);
       }
     }
 
     // Generate function body
-    let mut has_terminator = false;
-    for stmt in body {
-      has_terminator = self.gen_stmt(stmt)?;
-      if has_terminator {
-        break;
+    let has_terminator = self.gen_stmts(body)?;
+
+    // Check labels
+    for (name, (_, undefined_use)) in &self.labels {
+      if let Some(span) = undefined_use {
+        return err!(*span, "label `{}` is used but not defined", name);
       }
     }
 
     // Check terminator
     if !has_terminator {
-      return err!("no terminator in function");
+      if self.builder.function().ret_ty() == &Type::Void {
+        // a void function returns when it reaches the end of its body
+        self.builder.build_return(None);
+      } else {
+        return err!(
+          span,
+          "no terminator in function `{}`",
+          self.builder.function().name()
+        );
+      }
     }
 
     // Pop first scope
@@ -142,63 +252,220 @@ impl<'a> GenFun<'a> {
   // ----- gen_stmt -----
 
   fn gen_stmt(&mut self, stmt: Stmt) -> Expected<bool> {
+    let span = stmt.span;
+    self
+      .gen_stmt_impl(stmt.kind, span)
+      .map_err(|e| e.or_at(span))
+  }
+
+  fn gen_stmt_impl(&mut self, stmt: StmtKind, span: Span) -> Expected<bool> {
     match stmt {
-      Stmt::VarDef(var_defs) => {
+      StmtKind::VarDef(var_defs, storage) => {
         for (ty, name, init) in var_defs.into_iter() {
           if self.scope.get(&name).is_some() {
-            return err!("variable already exists");
+            return err!(span, "variable `{}` already exists", name);
           }
 
-          let mem = self.create_entry_block_alloca(ty, name);
-          if let Some(expr) = init {
-            let rhs = self.gen_expr(expr)?;
-            self.gen_assign_impl(mem, rhs)?;
+          match storage {
+            StorageClass::Default => {
+              let mem = self.create_entry_block_alloca(ty, name);
+              match init {
+                Some(Init::Expr(expr)) => {
+                  let rhs = self.gen_expr(expr)?;
+                  self.gen_assign_impl(mem, rhs)?;
+                }
+                Some(Init::Zero) => {
+                  let zero = self.builder.build_const(0);
+                  self.gen_assign_impl(mem, zero)?;
+                }
+                Some(Init::List(_)) => return err!(span, "initializer list is not supported"),
+                None => (),
+              }
+            }
+            StorageClass::Static => {
+              let global_id = self.gen_static_local(ty, &name, init, span)?;
+              let mem = self.global_memory(global_id);
+              self.scope.insert(name, mem);
+            }
+            StorageClass::Extern => {
+              if init.is_some() {
+                return err!(span, "`extern` variable `{}` has an initializer", name);
+              }
+              let global_id = declare_global(self.module, ty, &name, span)?;
+              let mem = self.global_memory(global_id);
+              self.scope.insert(name, mem);
+            }
           }
         }
         Ok(false)
       }
-      Stmt::StructDef(..) => todo!(),
-      Stmt::IfElse(cond, then, else_) => self.gen_if_else(cond, then, else_),
-      Stmt::For(init, cond, inc, body) => self.gen_for(init, cond, inc, *body),
-      Stmt::Break => {
+      StmtKind::StructDef(_) => Ok(false),
+      StmtKind::IfElse(cond, then, else_) => self.gen_if_else(cond, then, else_),
+      StmtKind::For(init, cond, inc, body) => self.gen_for(init, cond, inc, *body),
+      StmtKind::DoWhile(body, cond) => self.gen_do_while(*body, cond),
+      StmtKind::Switch(cond, body) => self.gen_switch(cond, *body),
+      StmtKind::Case(n, stmt) => self.gen_switch_label(Some(n), *stmt),
+      StmtKind::Default(stmt) => self.gen_switch_label(None, *stmt),
+      StmtKind::Break => {
         self
           .builder
           .build_unconditional_branch(*self.break_label.last().unwrap());
         Ok(true)
       }
-      Stmt::Cont => {
+      StmtKind::Cont => {
         self
           .builder
           .build_unconditional_branch(*self.cont_label.last().unwrap());
         Ok(true)
       }
-      Stmt::Return(expr) => {
-        let v1 = self.gen_expr(expr)?;
-        self.builder.build_return(v1);
+      StmtKind::Goto(name) => {
+        let block = match self.labels.get(&name) {
+          Some(&(block, _)) => block,
+          None => {
+            let block = self.builder.append_basic_block();
+            self.labels.insert(name, (block, Some(span)));
+            block
+          }
+        };
+        self.builder.build_unconditional_branch(block);
         Ok(true)
       }
-      Stmt::Block(stmts) => {
-        self.scope.push();
-        let mut has_terminator = false;
-        for stmt in stmts {
-          has_terminator = self.gen_stmt(stmt)?;
-          if has_terminator {
-            break;
+      StmtKind::Label(name, stmt) => {
+        let block = match self.labels.get_mut(&name) {
+          Some((block, undefined_use @ Some(_))) => {
+            *undefined_use = None;
+            *block
           }
+          Some((_, None)) => return err!(span, "redefinition of label `{}`", name),
+          None => {
+            let current_block = self.builder.get_insert_block().unwrap();
+            let block = self.builder.insert_basic_block_after(current_block);
+            self.labels.insert(name, (block, None));
+            block
+          }
+        };
+        if !self.has_terminator() {
+          self.builder.build_unconditional_branch(block);
+        }
+        self.builder.position_at_end(block);
+        self.gen_stmt(*stmt)
+      }
+      StmtKind::Return(None) => {
+        let ret_ty = self.builder.function().ret_ty();
+        if ret_ty == &Type::Void {
+          self.builder.build_return(None);
+          Ok(true)
+        } else {
+          err!(
+            span,
+            "non-void function should return a value of type `{}`",
+            ret_ty
+          )
+        }
+      }
+      StmtKind::Return(Some(expr)) => {
+        if self.builder.function().ret_ty() == &Type::Void {
+          return err!(expr.span, "void function should not return a value");
         }
+        let v1 = self.gen_expr(expr)?;
+        self.builder.build_return(Some(v1));
+        Ok(true)
+      }
+      StmtKind::Block(stmts) => {
+        self.scope.push();
+        let has_terminator = self.gen_stmts(stmts)?;
         self.scope.pop();
         Ok(has_terminator)
       }
-      Stmt::Expr(expr) => {
-        self.gen_expr(expr)?;
+      StmtKind::Expr(expr) => {
+        self.gen_void_expr(expr)?;
         Ok(false)
       }
     }
   }
 
-  fn create_entry_block_alloca(&mut self, _ty: Type, name: String) -> MemoryId {
+  // Returns if the last basic block has a terminator
+  fn gen_stmts(&mut self, stmts: Vec<Stmt>) -> Expected<bool> {
+    let mut has_terminator = false;
+    for stmt in stmts {
+      if has_terminator {
+        // statements following a terminator are unreachable unless they contain a label, and
+        // the ones before the label are generated in a block without predecessors
+        if !stmt.contains_label() {
+          continue;
+        }
+        if !stmt.is_labeled() {
+          let current_block = self.builder.get_insert_block().unwrap();
+          let block = self.builder.insert_basic_block_after(current_block);
+          self.builder.position_at_end(block);
+        }
+      }
+      has_terminator = self.gen_stmt(stmt)?;
+    }
+    Ok(has_terminator)
+  }
+
+  /// Defines a `static` local variable, which is a global variable of internal linkage named
+  /// after the function and the variable as `__static_f_x`. The name, which is also its name in
+  /// C, is reserved and made unique among the globals and functions of the module.
+  fn gen_static_local(
+    &mut self,
+    ty: Type,
+    name: &str,
+    init: Option<Init>,
+    span: Span,
+  ) -> Expected<GlobalId> {
+    let fun_name = self.builder.function().name();
+    let base_name = format!("__static_{}_{}", fun_name, name);
+    let mut global_name = base_name.clone();
+    let mut n = 0;
+    while self.module.get_global_by_name(&global_name).is_some()
+      || self.module.get_function_by_name(&global_name).is_some()
+    {
+      n += 1;
+      global_name = format!("{}_{}", base_name, n);
+    }
+    define_global(
+      self.module,
+      ty,
+      &global_name,
+      init,
+      StorageClass::Static,
+      span,
+    )
+  }
+
+  /// Returns the memory referring to the global variable `global_id`, which is created on its
+  /// first use in the function.
+  fn global_memory(&mut self, global_id: GlobalId) -> MemoryId {
+    if let Some(&mem) = self.globals.get(&global_id) {
+      return mem;
+    }
+    let ty = self.module.get_global(global_id).ty().clone();
+    let mem = self.builder.build_global_memory(ty, global_id);
+    self.globals.insert(global_id, mem);
+    mem
+  }
+
+  /// Returns the memory of the variable `name`, which is a local variable in scope or a global
+  /// variable.
+  fn get_var(&mut self, name: &str) -> Option<MemoryId> {
+    match self.scope.get_all(name) {
+      Some(&mem) => Some(mem),
+      None => {
+        let global_id = self.module.get_global_by_name(name)?;
+        Some(self.global_memory(global_id))
+      }
+    }
+  }
+
+  fn is_var(&self, name: &str) -> bool {
+    self.scope.get_all(name).is_some() || self.module.get_global_by_name(name).is_some()
+  }
+
+  fn create_entry_block_alloca(&mut self, ty: Type, name: String) -> MemoryId {
     // Push mem_arena
-    let mem_id = self.builder.build_alloca();
+    let mem_id = self.builder.build_alloca(ty);
     // Insert scope
     self.scope.insert(name, mem_id);
     mem_id
@@ -220,7 +487,7 @@ impl<'a> GenFun<'a> {
     };
 
     // cond:
-    let expr = self.gen_expr(cond)?;
+    let expr = self.gen_cond(cond)?;
     self
       .builder
       .build_conditional_branch(expr, then_block, else_block);
@@ -256,9 +523,9 @@ impl<'a> GenFun<'a> {
 
   fn gen_for(
     &mut self,
-    init: Option<AST>,
-    cond: Option<AST>,
-    inc: Option<AST>,
+    init: Option<Box<AST>>,
+    cond: Option<Box<AST>>,
+    inc: Option<Box<AST>>,
     body: Stmt,
   ) -> Expected<bool> {
     let current_block = self.builder.get_insert_block().unwrap();
@@ -271,14 +538,14 @@ impl<'a> GenFun<'a> {
 
     // init:
     if let Some(expr) = init {
-      self.gen_expr(expr)?;
+      self.gen_expr(*expr)?;
     }
     self.builder.build_unconditional_branch(cond_block);
 
     // cond:
     self.builder.position_at_end(cond_block);
     if let Some(expr) = cond {
-      let expr = self.gen_expr(expr)?;
+      let expr = self.gen_cond(*expr)?;
       self
         .builder
         .build_conditional_branch(expr, body_block, end_block);
@@ -296,7 +563,7 @@ impl<'a> GenFun<'a> {
     // inc:
     self.builder.position_at_end(inc_block);
     if let Some(expr) = inc {
-      self.gen_expr(expr)?;
+      self.gen_expr(*expr)?;
     }
     self.builder.build_unconditional_branch(cond_block);
 
@@ -313,80 +580,345 @@ impl<'a> GenFun<'a> {
     Ok(has_no_branch_to_end)
   }
 
+  fn gen_do_while(&mut self, body: Stmt, cond: AST) -> Expected<bool> {
+    let current_block = self.builder.get_insert_block().unwrap();
+    let body_block = self.builder.insert_basic_block_after(current_block);
+    let cond_block = self.builder.insert_basic_block_after(body_block);
+    let end_block = self.builder.insert_basic_block_after(cond_block);
+    self.break_label.push(end_block);
+    self.cont_label.push(cond_block);
+    self.builder.build_unconditional_branch(body_block);
+
+    // body:
+    self.builder.position_at_end(body_block);
+    let has_terminator_in_body = self.gen_stmt(body)?;
+    if !has_terminator_in_body {
+      self.builder.build_unconditional_branch(cond_block);
+    }
+
+    // cond:
+    let has_no_branch_to_cond = self.builder.function().get(cond_block).pred().is_empty();
+    if has_no_branch_to_cond {
+      self.builder.remove_basic_block(cond_block);
+    } else {
+      self.builder.position_at_end(cond_block);
+      let expr = self.gen_cond(cond)?;
+      self
+        .builder
+        .build_conditional_branch(expr, body_block, end_block);
+    }
+
+    // end:
+    let has_no_branch_to_end = self.builder.function().get(end_block).pred().is_empty();
+    if has_no_branch_to_end {
+      self.builder.remove_basic_block(end_block);
+    } else {
+      self.builder.position_at_end(end_block);
+    }
+
+    self.break_label.pop();
+    self.cont_label.pop();
+    Ok(has_no_branch_to_end)
+  }
+
+  fn gen_switch(&mut self, cond: AST, body: Stmt) -> Expected<bool> {
+    let expr = self.gen_int_expr(cond)?;
+    let switch_block = self.builder.get_insert_block().unwrap();
+    let body_block = self.builder.insert_basic_block_after(switch_block);
+    let end_block = self.builder.insert_basic_block_after(body_block);
+    self.break_label.push(end_block);
+    self.switch_labels.push(SwitchLabels::default());
+
+    // body:
+    self.builder.position_at_end(body_block);
+    let has_terminator_in_body = self.gen_stmt(body)?;
+    if !has_terminator_in_body {
+      self.builder.build_unconditional_branch(end_block);
+    }
+
+    // switch:
+    let labels = self.switch_labels.pop().unwrap();
+    self.builder.position_at_end(switch_block);
+    let default = labels.default.unwrap_or(end_block);
+    self.builder.build_switch(expr, default, labels.cases);
+
+    // end:
+    let has_no_branch_to_end = self.builder.function().get(end_block).pred().is_empty();
+    if has_no_branch_to_end {
+      self.builder.remove_basic_block(end_block);
+    } else {
+      self.builder.position_at_end(end_block);
+    }
+
+    self.break_label.pop();
+    Ok(has_no_branch_to_end)
+  }
+
+  /// Generates a `case` label of value `n`, or a `default` label if `n` is `None`, which starts
+  /// a new block that the previous one falls through to.
+  fn gen_switch_label(&mut self, n: Option<i64>, stmt: Stmt) -> Expected<bool> {
+    let current_block = self.builder.get_insert_block().unwrap();
+    let label_block = self.builder.insert_basic_block_after(current_block);
+    if !self.has_terminator() {
+      self.builder.build_unconditional_branch(label_block);
+    }
+
+    self.builder.position_at_end(label_block);
+    let labels = self.switch_labels.last_mut().unwrap();
+    match n {
+      Some(n) => labels.cases.push((n, label_block)),
+      None => labels.default = Some(label_block),
+    }
+    self.gen_stmt(stmt)
+  }
+
+  /// Returns whether the current block already ends with a terminator.
+  fn has_terminator(&self) -> bool {
+    let fun = self.builder.function();
+    let current_block = self.builder.get_insert_block().unwrap();
+    match fun.get(current_block).insts().last() {
+      Some(&inst_id) => is_terminator(fun.get(inst_id)),
+      None => false,
+    }
+  }
+
   // ----- gen_expr -----
 
   fn gen_expr(&mut self, expr: AST) -> Expected<InstId> {
-    match expr {
-      AST::Eq(n, m) => {
+    let span = expr.span;
+    match expr.kind {
+      ASTKind::PostfixAssign(n, m) => {
+        let (_, v0) = self.gen_op_assign(*n, *m, span)?;
+        Ok(v0)
+      }
+      ASTKind::LogOr(n, m) => self.gen_logical(*n, *m, false),
+      ASTKind::LogAnd(n, m) => self.gen_logical(*n, *m, true),
+      ASTKind::Eq(n, m) => {
+        let is_float = n.ty.is_float();
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
-        let v0 = self.builder.build_eq(v1, v2);
+        let v0 = if is_float {
+          self.builder.build_feq(v1, v2)
+        } else {
+          self.builder.build_eq(v1, v2)
+        };
         Ok(v0)
       }
-      AST::Ne(n, m) => {
+      ASTKind::Ne(n, m) => {
+        let is_float = n.ty.is_float();
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
-        let v0 = self.builder.build_ne(v1, v2);
+        let v0 = if is_float {
+          self.builder.build_fne(v1, v2)
+        } else {
+          self.builder.build_ne(v1, v2)
+        };
         Ok(v0)
       }
-      AST::Lt(n, m) => {
+      ASTKind::Lt(n, m) => {
+        let ty = n.ty.clone();
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
-        let v0 = self.builder.build_lt(v1, v2);
+        let v0 = if ty.is_float() {
+          self.builder.build_flt(v1, v2)
+        } else if ty.is_unsigned() {
+          self.builder.build_ult(v1, v2)
+        } else {
+          self.builder.build_lt(v1, v2)
+        };
         Ok(v0)
       }
-      AST::Le(n, m) => {
+      ASTKind::Le(n, m) => {
+        let ty = n.ty.clone();
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
-        let v0 = self.builder.build_le(v1, v2);
+        let v0 = if ty.is_float() {
+          self.builder.build_fle(v1, v2)
+        } else if ty.is_unsigned() {
+          self.builder.build_ule(v1, v2)
+        } else {
+          self.builder.build_le(v1, v2)
+        };
         Ok(v0)
       }
-      AST::Add(n, m) => {
+      ASTKind::Add(n, m) if n.ty.is_float() => {
+        let ty = n.ty.clone();
+        let v1 = self.gen_expr(*n)?;
+        let v2 = self.gen_expr(*m)?;
+        let v0 = self.builder.build_fadd(v1, v2);
+        Ok(self.round_float(v0, &ty))
+      }
+      ASTKind::Add(n, m) => {
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
         let v0 = self.builder.build_add(v1, v2);
-        Ok(v0)
+        Ok(self.wrap_unsigned(v0, &expr.ty))
       }
-      AST::Sub(n, m) => {
+      ASTKind::Sub(n, m) if n.ty.is_float() => {
+        let ty = n.ty.clone();
+        let v1 = self.gen_expr(*n)?;
+        let v2 = self.gen_expr(*m)?;
+        let v0 = self.builder.build_fsub(v1, v2);
+        Ok(self.round_float(v0, &ty))
+      }
+      ASTKind::Sub(n, m) => {
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
         let v0 = self.builder.build_sub(v1, v2);
-        Ok(v0)
+        Ok(self.wrap_unsigned(v0, &expr.ty))
       }
-      AST::Mul(n, m) => {
+      ASTKind::Mul(n, m) if n.ty.is_float() => {
+        let ty = n.ty.clone();
+        let v1 = self.gen_expr(*n)?;
+        let v2 = self.gen_expr(*m)?;
+        let v0 = self.builder.build_fmul(v1, v2);
+        Ok(self.round_float(v0, &ty))
+      }
+      ASTKind::Mul(n, m) => {
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
         let v0 = self.builder.build_mul(v1, v2);
-        Ok(v0)
+        Ok(self.wrap_unsigned(v0, &expr.ty))
+      }
+      ASTKind::Div(n, m) if n.ty.is_float() => {
+        let ty = n.ty.clone();
+        let v1 = self.gen_expr(*n)?;
+        let v2 = self.gen_expr(*m)?;
+        let v0 = self.builder.build_fdiv(v1, v2);
+        Ok(self.round_float(v0, &ty))
       }
-      AST::Div(n, m) => {
+      ASTKind::Div(n, m) => {
+        let is_unsigned = n.ty.is_unsigned();
         let v1 = self.gen_expr(*n)?;
         let v2 = self.gen_expr(*m)?;
-        let v0 = self.builder.build_div(v1, v2);
+        let v0 = if is_unsigned {
+          self.builder.build_udiv(v1, v2)
+        } else {
+          self.builder.build_div(v1, v2)
+        };
         Ok(v0)
       }
-      AST::Call(name, args) => {
-        if let Some(fun) = self.module.get_function_by_name(&name) {
-          let args = args
-            .into_iter()
-            .map(|expr| self.gen_expr(expr))
-            .collect::<Result<Vec<_>, _>>()?;
-          // TODO: type check
-          // let arg_types: Vec<_> = args.iter().map(|arg| arg.get_type()).collect();
-          // let param_types = &self.module.funs[fun].param_tys;
-          // if arg_types != param_types {
-          //   return err!("argument types mismatch function parameter types");
-          // }
-
-          let v0 = self.builder.build_call(fun, args);
-          Ok(v0)
+      ASTKind::Mod(n, m) => {
+        let is_unsigned = n.ty.is_unsigned();
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = if is_unsigned {
+          self.builder.build_umod(v1, v2)
         } else {
-          err!("function does not exist")
-        }
+          self.builder.build_mod(v1, v2)
+        };
+        Ok(v0)
+      }
+      ASTKind::BitAnd(n, m) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = self.builder.build_bit_and(v1, v2);
+        Ok(v0)
       }
-      AST::Num(n) => Ok(self.builder.build_const(n)),
-      AST::Assign(..) | AST::Ident(..) => {
-        let mem = self.gen_addr(expr)?;
+      ASTKind::BitOr(n, m) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = self.builder.build_bit_or(v1, v2);
+        Ok(v0)
+      }
+      ASTKind::BitXor(n, m) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = self.builder.build_bit_xor(v1, v2);
+        Ok(v0)
+      }
+      ASTKind::Shl(n, m) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = self.builder.build_shl(v1, v2);
+        Ok(self.wrap_unsigned(v0, &expr.ty))
+      }
+      ASTKind::Shr(n, m) => {
+        let is_unsigned = n.ty.is_unsigned();
+        let v1 = self.gen_int_expr(*n)?;
+        let v2 = self.gen_int_expr(*m)?;
+        let v0 = if is_unsigned {
+          self.builder.build_ushr(v1, v2)
+        } else {
+          self.builder.build_shr(v1, v2)
+        };
+        Ok(v0)
+      }
+      ASTKind::Not(n) => {
+        let v1 = self.gen_cond(*n)?;
+        let zero = self.builder.build_const(0);
+        let v0 = self.builder.build_eq(v1, zero);
+        Ok(v0)
+      }
+      ASTKind::BitNot(n) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let v0 = self.builder.build_bit_not(v1);
+        Ok(self.wrap_unsigned(v0, &expr.ty))
+      }
+      ASTKind::Neg(n) if n.ty.is_float() => {
+        let v1 = self.gen_expr(*n)?;
+        Ok(self.builder.build_fneg(v1))
+      }
+      ASTKind::Neg(n) => {
+        let v1 = self.gen_int_expr(*n)?;
+        let zero = self.builder.build_const(0);
+        let v0 = self.builder.build_sub(zero, v1);
+        Ok(self.wrap_unsigned(v0, &expr.ty))
+      }
+      ASTKind::Cast(Type::Bool, n) if n.ty.is_float() => self.gen_cond(*n),
+      ASTKind::Cast(ty, n) if ty.is_integer() && n.ty.is_integer() => {
+        let v1 = self.gen_expr(*n)?;
+        Ok(self.convert_int(v1, &ty))
+      }
+      ASTKind::Cast(ty, n) if ty.is_integer() && n.ty.is_float() => {
+        let v1 = self.gen_expr(*n)?;
+        let v2 = if ty.is_unsigned() {
+          self.builder.build_ftoui(v1)
+        } else {
+          self.builder.build_ftoi(v1)
+        };
+        Ok(self.convert_int(v2, &ty))
+      }
+      ASTKind::Cast(ty, n) if ty.is_float() && n.ty.is_integer() => {
+        let is_unsigned = n.ty.is_unsigned();
+        let v1 = self.gen_expr(*n)?;
+        let v2 = if is_unsigned {
+          self.builder.build_uitof(v1)
+        } else {
+          self.builder.build_itof(v1)
+        };
+        Ok(self.round_float(v2, &ty))
+      }
+      ASTKind::Cast(ty, n) if ty.is_float() && n.ty.is_float() => {
+        let v1 = self.gen_expr(*n)?;
+        Ok(self.round_float(v1, &ty))
+      }
+      ASTKind::Call(callee, args) => match self.gen_call(*callee, args, span)? {
+        Some(v0) => Ok(v0),
+        None => err!(span, "void value not ignored as it ought to be"),
+      },
+      ASTKind::AssignedValue(_) => Ok(*self.assigned_values.last().unwrap()),
+      ASTKind::Enumerator(_, n) | ASTKind::Num(n, _) => Ok(self.builder.build_const(n)),
+      ASTKind::Float(x, _) => Ok(self.builder.build_fconst(x)),
+      ASTKind::SizeOf(ty) => match ty.layout(&|_| None) {
+        Some(layout) => Ok(self.builder.build_const(layout.size.into())),
+        None => err!(span, "incomplete type `{}` has no size", ty),
+      },
+      ASTKind::AlignOf(ty) => match ty.layout(&|_| None) {
+        Some(layout) => Ok(self.builder.build_const(layout.align.into())),
+        None => err!(span, "incomplete type `{}` has no size", ty),
+      },
+      // a function designator is converted to a pointer to the function
+      ASTKind::Ident(name)
+        if !self.is_var(&name) && self.module.get_function_by_name(&name).is_some() =>
+      {
+        let fun = self.module.get_function_by_name(&name).unwrap();
+        Ok(self.builder.build_fun_addr(fun))
+      }
+      ASTKind::Addr(n) if matches!(n.ty, Type::FunTy(..)) => self.gen_expr(*n),
+      ASTKind::Deref(n) if matches!(expr.ty, Type::FunTy(..)) => self.gen_expr(*n),
+      kind @ (ASTKind::Assign(..) | ASTKind::CompoundAssign(..) | ASTKind::Ident(..)) => {
+        let mem = self.gen_addr(AST::new(kind, span, expr.ty))?;
         // TODO: check if mem.get_type().get_element_type().is_array_type()
         if false {
           todo!()
@@ -400,14 +932,173 @@ impl<'a> GenFun<'a> {
     }
   }
 
+  /// Generates an expression whose value is discarded, which may be a call to a void
+  /// function. Returns `None` if the expression has no value.
+  fn gen_void_expr(&mut self, expr: AST) -> Expected<Option<InstId>> {
+    match expr.kind {
+      ASTKind::Call(callee, args) => self.gen_call(*callee, args, expr.span),
+      kind => self.gen_expr(AST::new(kind, expr.span, expr.ty)).map(Some),
+    }
+  }
+
+  /// Generates a call of `callee`. A function named by an identifier is called directly, and
+  /// other callees are evaluated to a pointer to function and called indirectly.
+  fn gen_call(&mut self, callee: AST, args: Vec<AST>, span: Span) -> Expected<Option<InstId>> {
+    let name = match callee.kind {
+      ASTKind::Ident(name) if !self.is_var(&name) => name,
+      kind => {
+        let callee = AST::new(kind, callee.span, callee.ty);
+        let fun_ty = match parse::callee_type(&callee.ty) {
+          Some(fun_ty) => fun_ty.clone(),
+          None => {
+            return err!(
+              callee.span,
+              "called object is not a function or function pointer"
+            )
+          }
+        };
+        let v1 = self.gen_expr(callee)?;
+        let args = args
+          .into_iter()
+          .map(|expr| self.gen_expr(expr))
+          .collect::<Result<Vec<_>, _>>()?;
+        let is_void = matches!(&fun_ty, Type::FunTy(ret_ty, ..) if **ret_ty == Type::Void);
+        let v0 = self.builder.build_call_indirect(fun_ty, v1, args);
+        return Ok(if is_void { None } else { Some(v0) });
+      }
+    };
+    if let Some(fun) = self.module.get_function_by_name(&name) {
+      let args = args
+        .into_iter()
+        .map(|expr| self.gen_expr(expr))
+        .collect::<Result<Vec<_>, _>>()?;
+      // TODO: type check
+      // let arg_types: Vec<_> = args.iter().map(|arg| arg.get_type()).collect();
+      // let param_types = &self.module.funs[fun].param_tys;
+      // if arg_types != param_types {
+      //   return err!("argument types mismatch function parameter types");
+      // }
+
+      let v0 = self.builder.build_call(fun, args);
+      if self.module.get_function(fun).ret_ty() == &Type::Void {
+        Ok(None)
+      } else {
+        Ok(Some(v0))
+      }
+    } else {
+      err!(span, "function `{}` does not exist", name)
+    }
+  }
+
+  /// Generates `expr`, which must be of an integer type.
+  fn gen_int_expr(&mut self, expr: AST) -> Expected<InstId> {
+    if expr.ty.is_float() {
+      err!(
+        expr.span,
+        "unexpected type in expression, expecting int type"
+      )
+    } else {
+      self.gen_expr(expr)
+    }
+  }
+
+  /// Generates `expr` as a condition, which is nonzero if `expr` compares unequal to zero. A
+  /// NaN compares unequal to zero.
+  fn gen_cond(&mut self, expr: AST) -> Expected<InstId> {
+    if expr.ty.is_float() {
+      let v1 = self.gen_expr(expr)?;
+      let zero = self.builder.build_fconst(0.0);
+      Ok(self.builder.build_fne(v1, zero))
+    } else {
+      self.gen_expr(expr)
+    }
+  }
+
+  /// Converts the integer `v1` to the integer type `ty`, by truncating and extending it to the
+  /// width of `ty`.
+  fn convert_int(&mut self, v1: InstId, ty: &Type) -> InstId {
+    let bits = ty.layout(&|_| None).unwrap().size * 8;
+    if *ty == Type::Bool {
+      // converting to `_Bool` yields whether the value is nonzero
+      let zero = self.builder.build_const(0);
+      self.builder.build_ne(v1, zero)
+    } else if bits >= 64 {
+      v1
+    } else if ty.is_unsigned() {
+      self.builder.build_zext(v1, bits)
+    } else {
+      self.builder.build_sext(v1, bits)
+    }
+  }
+
+  /// Wraps the result `v1` of integer arithmetic around at the width of `ty` if it is unsigned,
+  /// as it is computed in 64 bits.
+  fn wrap_unsigned(&mut self, v1: InstId, ty: &Type) -> InstId {
+    if ty.is_unsigned() {
+      self.convert_int(v1, ty)
+    } else {
+      v1
+    }
+  }
+
+  /// Rounds the floating value `v1` to single precision if it is of type `float`.
+  fn round_float(&mut self, v1: InstId, ty: &Type) -> InstId {
+    if *ty == Type::Float {
+      self.builder.build_ftrunc(v1)
+    } else {
+      v1
+    }
+  }
+
+  /// Generates `n && m` if `is_and`, or `n || m` otherwise. `m` is evaluated only if `n` does
+  /// not determine the result, which is passed through memory to the merge block.
+  fn gen_logical(&mut self, n: AST, m: AST, is_and: bool) -> Expected<InstId> {
+    let mem = self.builder.build_alloca(Type::Int);
+
+    // lhs:
+    let v1 = self.gen_cond(n)?;
+    let zero = self.builder.build_const(0);
+    let v2 = self.builder.build_ne(v1, zero);
+    self.builder.build_store(mem, v2);
+    let current_block = self.builder.get_insert_block().unwrap();
+    let rhs_block = self.builder.insert_basic_block_after(current_block);
+    let merge_block = self.builder.insert_basic_block_after(rhs_block);
+    if is_and {
+      self
+        .builder
+        .build_conditional_branch(v2, rhs_block, merge_block);
+    } else {
+      self
+        .builder
+        .build_conditional_branch(v2, merge_block, rhs_block);
+    }
+
+    // rhs:
+    self.builder.position_at_end(rhs_block);
+    let v3 = self.gen_cond(m)?;
+    let zero = self.builder.build_const(0);
+    let v4 = self.builder.build_ne(v3, zero);
+    self.builder.build_store(mem, v4);
+    self.builder.build_unconditional_branch(merge_block);
+
+    // merge:
+    self.builder.position_at_end(merge_block);
+    Ok(self.builder.build_load(mem))
+  }
+
   // ----- gen_addr -----
 
   fn gen_addr(&mut self, expr: AST) -> Expected<MemoryId> {
-    match expr {
-      AST::Assign(n, m) => {
+    let span = expr.span;
+    match expr.kind {
+      ASTKind::Assign(n, m) => {
         let rhs = self.gen_expr(*m)?;
         let mem = self.gen_addr(*n)?;
-        self.gen_assign_impl(mem, rhs)
+        self.gen_assign_impl(mem, rhs).map_err(|e| e.or_at(span))
+      }
+      ASTKind::CompoundAssign(n, m) => {
+        let (mem, _) = self.gen_op_assign(*n, *m, span)?;
+        Ok(mem)
       }
       // AST::Deref(n) => {
       //   let ptr = self.gen_expr(*n)?;
@@ -417,14 +1108,31 @@ impl<'a> GenFun<'a> {
       //     err!("cannot dereference int value")
       //   }
       // }
-      AST::Ident(name) => match self.scope.get_all(&name) {
-        Some(&mem) => Ok(mem),
-        None => err!("variable should be declared before its first use"),
+      ASTKind::Ident(name) => match self.get_var(&name) {
+        Some(mem) => Ok(mem),
+        None => err!(
+          span,
+          "variable `{}` should be declared before its first use",
+          name
+        ),
       },
-      _ => err!("cannot obtain address of rvalue"),
+      _ => err!(span, "cannot obtain address of rvalue"),
     }
   }
 
+  /// Generates the assignment of `m` to the lvalue `n`, which is evaluated once. `m` refers to
+  /// the value of `n` before the assignment by `AssignedValue`. Returns the memory of `n` and
+  /// its old value.
+  fn gen_op_assign(&mut self, n: AST, m: AST, span: Span) -> Expected<(MemoryId, InstId)> {
+    let mem = self.gen_addr(n)?;
+    let v1 = self.builder.build_load(mem);
+    self.assigned_values.push(v1);
+    let rhs = self.gen_expr(m);
+    self.assigned_values.pop();
+    self.gen_assign_impl(mem, rhs?).map_err(|e| e.or_at(span))?;
+    Ok((mem, v1))
+  }
+
   fn gen_assign_impl(&mut self, mem: MemoryId, rhs: InstId) -> Expected<MemoryId> {
     // TODO: check if lhs.get_type().get_element_type() == rhs.get_type().as_any_type_enum()
     if true {
@@ -435,3 +1143,103 @@ impl<'a> GenFun<'a> {
     }
   }
 }
+
+// ----- global -----
+
+/// Declares a global variable defined elsewhere, as `extern` does, unless a variable of the
+/// same name is declared already.
+fn declare_global(module: &mut Module, ty: Type, name: &str, span: Span) -> Expected<GlobalId> {
+  check_global_type(&ty, span)?;
+  match module.get_global_by_name(name) {
+    Some(global_id) if module.get_global(global_id).ty() == &ty => Ok(global_id),
+    Some(_) => err!(
+      span,
+      "type of global variable `{}` differs from the previous declaration",
+      name
+    ),
+    None if module.get_function_by_name(name).is_some() => {
+      err!(span, "`{}` redeclared as a different kind of symbol", name)
+    }
+    None => Ok(module.add_global(Global::new(name.to_string(), ty))),
+  }
+}
+
+/// Defines a global variable initialized with the constant `init`, or with zero. A `static`
+/// variable has internal linkage.
+fn define_global(
+  module: &mut Module,
+  ty: Type,
+  name: &str,
+  init: Option<Init>,
+  storage: StorageClass,
+  span: Span,
+) -> Expected<GlobalId> {
+  let global_id = match module.get_global_by_name(name) {
+    Some(global_id) if !module.get_global(global_id).is_declaration() => {
+      return err!(span, "global variable `{}` already exists", name);
+    }
+    Some(global_id)
+      if storage == StorageClass::Static
+        && module.get_global(global_id).linkage() == Linkage::External =>
+    {
+      return err!(
+        span,
+        "static declaration of `{}` follows non-static declaration",
+        name
+      );
+    }
+    // a variable declared `extern` is defined here
+    _ => declare_global(module, ty.clone(), name, span)?,
+  };
+  let init = match init {
+    None | Some(Init::Zero) if ty.is_float() => GlobalInit::Float(0.0),
+    None | Some(Init::Zero) => GlobalInit::Int(0),
+    Some(Init::Expr(expr)) => eval_const_init(module, expr)?,
+    Some(Init::List(_)) => return err!(span, "initializer list is not supported"),
+  };
+  let global = module.get_global_mut(global_id);
+  global.set_init(init);
+  if storage == StorageClass::Static {
+    global.set_linkage(Linkage::Internal);
+  }
+  Ok(global_id)
+}
+
+/// Checks that a global variable may be of type `ty`. Only scalar values are held in memory.
+fn check_global_type(ty: &Type, span: Span) -> Expected<()> {
+  if ty.is_arithmetic() || matches!(ty, Type::Pointer(_)) {
+    Ok(())
+  } else {
+    err!(span, "global variable of type `{}` is not supported", ty)
+  }
+}
+
+/// Evaluates the initializer `expr` of a global variable, which is generated as the return
+/// value of a function and folded to a constant.
+fn eval_const_init(module: &mut Module, expr: AST) -> Expected<GlobalInit> {
+  let span = expr.span;
+  let fun = Function::new(String::new(), expr.ty.clone(), Vec::new());
+  let mut gen = GenFun::new(module, fun);
+  let bb = gen.builder.append_basic_block();
+  gen.builder.position_at_end(bb);
+  // local variables of the enclosing function, which a constant does not refer to, are not in
+  // scope of the initializer
+  let v1 = gen
+    .gen_expr(expr)
+    .map_err(|_| Diagnostic::error("initializer element is not constant").at(span))?;
+  let ret = gen.builder.build_return(Some(v1));
+
+  let mut init_module = Module::new(String::new());
+  let fun_id = init_module.add_function(gen.builder.retrieve_function());
+  let init_module = ConstantFolding::new(init_module).run();
+  let fun = init_module.get_function(fun_id);
+  let v1 = match fun.get(ret).kind() {
+    InstKind::Ret(Some(v1)) => *v1,
+    _ => unreachable!(),
+  };
+  match *fun.get(v1).kind() {
+    InstKind::Const(n) => Ok(GlobalInit::Int(n)),
+    InstKind::FConst(x) => Ok(GlobalInit::Float(x)),
+    _ => err!(span, "initializer element is not constant"),
+  }
+}
@@ -32,6 +32,9 @@ impl ConstantFolding {
         if let Some(n) = maybe_fold_const(inst_id, builder.function()) {
           let new_const = builder.build_const(n);
           builder.replace_all_uses(inst_id, new_const);
+        } else if let Some(x) = maybe_fold_float(inst_id, builder.function()) {
+          let new_const = builder.build_fconst(x);
+          builder.replace_all_uses(inst_id, new_const);
         }
       }
     }
@@ -42,15 +45,61 @@ impl ConstantFolding {
 fn maybe_fold_const(inst_id: InstId, fun: &Function) -> Option<i64> {
   use InstKind::*;
   let kind = fun.get(inst_id).kind();
+  match kind {
+    BitNot(v1) => return Some(!maybe_const(fun.get(*v1))?),
+    SExt(v1, bits) => {
+      let shift = 64 - bits;
+      return Some(maybe_const(fun.get(*v1))? << shift >> shift);
+    }
+    ZExt(v1, bits) => {
+      let shift = 64 - bits;
+      return Some(((maybe_const(fun.get(*v1))? as u64) << shift >> shift) as i64);
+    }
+    // leave conversions of out-of-range values, whose results are undefined, to run time
+    FToI(v1) => {
+      let x = maybe_fconst(fun.get(*v1))?.trunc();
+      let limit = -(i64::MIN as f64);
+      return (-limit <= x && x < limit).then_some(x as i64);
+    }
+    FToUI(v1) => {
+      let x = maybe_fconst(fun.get(*v1))?.trunc();
+      let limit = 2.0 * -(i64::MIN as f64);
+      return (0.0 <= x && x < limit).then_some(x as u64 as i64);
+    }
+    FEq(v1, v2) | FNe(v1, v2) | FLt(v1, v2) | FLe(v1, v2) => {
+      let x1 = maybe_fconst(fun.get(*v1))?;
+      let x2 = maybe_fconst(fun.get(*v2))?;
+      // comparisons with a NaN are false except for `!=`
+      let res = match kind {
+        FEq(..) => x1 == x2,
+        FNe(..) => x1 != x2,
+        FLt(..) => x1 < x2,
+        _ => x1 <= x2,
+      };
+      return Some(res as i64);
+    }
+    _ => (),
+  }
   let (n1, n2) = match kind {
     Eq(v1, v2)
     | Ne(v1, v2)
     | Lt(v1, v2)
     | Le(v1, v2)
+    | ULt(v1, v2)
+    | ULe(v1, v2)
     | Add(v1, v2)
     | Sub(v1, v2)
     | Mul(v1, v2)
-    | Div(v1, v2) => {
+    | Div(v1, v2)
+    | Mod(v1, v2)
+    | UDiv(v1, v2)
+    | UMod(v1, v2)
+    | BitAnd(v1, v2)
+    | BitOr(v1, v2)
+    | BitXor(v1, v2)
+    | Shl(v1, v2)
+    | Shr(v1, v2)
+    | UShr(v1, v2) => {
       let n1 = maybe_const(fun.get(*v1))?;
       let n2 = maybe_const(fun.get(*v2))?;
       (n1, n2)
@@ -63,14 +112,45 @@ fn maybe_fold_const(inst_id: InstId, fun: &Function) -> Option<i64> {
     Ne(..) => Some((n1 != n2) as i64),
     Lt(..) => Some((n1 < n2) as i64),
     Le(..) => Some((n1 <= n2) as i64),
-    Add(..) => Some(n1 + n2),
-    Sub(..) => Some(n1 - n2),
-    Mul(..) => Some(n1 * n2),
-    Div(..) => Some(n1 / n2),
+    ULt(..) => Some(((n1 as u64) < n2 as u64) as i64),
+    ULe(..) => Some((n1 as u64 <= n2 as u64) as i64),
+    // arithmetic wraps around in 64 bits, as unsigned arithmetic does
+    Add(..) => Some(n1.wrapping_add(n2)),
+    Sub(..) => Some(n1.wrapping_sub(n2)),
+    Mul(..) => Some(n1.wrapping_mul(n2)),
+    // leave a division or remainder by zero or of overflowing operands and out-of-range shifts
+    // to run time
+    Div(..) => n1.checked_div(n2),
+    Mod(..) => n1.checked_rem(n2),
+    UDiv(..) => Some((n1 as u64).checked_div(n2 as u64)? as i64),
+    UMod(..) => Some((n1 as u64).checked_rem(n2 as u64)? as i64),
+    BitAnd(..) => Some(n1 & n2),
+    BitOr(..) => Some(n1 | n2),
+    BitXor(..) => Some(n1 ^ n2),
+    Shl(..) => n1.checked_shl(n2.try_into().ok()?),
+    Shr(..) => n1.checked_shr(n2.try_into().ok()?),
+    UShr(..) => Some((n1 as u64).checked_shr(n2.try_into().ok()?)? as i64),
     _ => return None,
   }
 }
 
+/// Folds a floating operation on constants as IEEE 754 arithmetic in double precision, which is
+/// how it is performed at run time. Division by zero yields an infinity or a NaN.
+fn maybe_fold_float(inst_id: InstId, fun: &Function) -> Option<f64> {
+  use InstKind::*;
+  match fun.get(inst_id).kind() {
+    FAdd(v1, v2) => Some(maybe_fconst(fun.get(*v1))? + maybe_fconst(fun.get(*v2))?),
+    FSub(v1, v2) => Some(maybe_fconst(fun.get(*v1))? - maybe_fconst(fun.get(*v2))?),
+    FMul(v1, v2) => Some(maybe_fconst(fun.get(*v1))? * maybe_fconst(fun.get(*v2))?),
+    FDiv(v1, v2) => Some(maybe_fconst(fun.get(*v1))? / maybe_fconst(fun.get(*v2))?),
+    FNeg(v1) => Some(-maybe_fconst(fun.get(*v1))?),
+    IToF(v1) => Some(maybe_const(fun.get(*v1))? as f64),
+    UIToF(v1) => Some(maybe_const(fun.get(*v1))? as u64 as f64),
+    FTrunc(v1) => Some(maybe_fconst(fun.get(*v1))? as f32 as f64),
+    _ => None,
+  }
+}
+
 fn maybe_const(inst: &Inst) -> Option<i64> {
   if let &InstKind::Const(n) = inst.kind() {
     Some(n)
@@ -79,6 +159,14 @@ fn maybe_const(inst: &Inst) -> Option<i64> {
   }
 }
 
+fn maybe_fconst(inst: &Inst) -> Option<f64> {
+  if let &InstKind::FConst(x) = inst.kind() {
+    Some(x)
+  } else {
+    None
+  }
+}
+
 #[test]
 fn test_constant_folding() {
   use crate::irgen::IRGen;
@@ -104,3 +192,97 @@ int main() {
   let after = count_ops(module.get_function(fun_id));
   assert!(after < before);
 }
+
+#[test]
+fn test_constant_folding_enumerator() {
+  use crate::irgen::IRGen;
+  use crate::pass::{count_ops, DeadCodeElimination};
+  use parser::parse::parse;
+  use parser::tokenize::Tokenizer;
+
+  let input = r"
+enum { A, B = 5, C };
+int main() {
+  return B * C + A;
+}
+  ";
+  let it = Tokenizer::new(input);
+  let funs = parse(it).unwrap();
+  let module = IRGen::new("mod".to_string()).irgen(funs).unwrap();
+
+  let fun_id = module.get_function_by_name("main").unwrap();
+  let module = ConstantFolding::new(module).run();
+  let module = DeadCodeElimination::new(module).run();
+  // only `ret 30` and its operand remain
+  assert_eq!(count_ops(module.get_function(fun_id)), 2);
+}
+
+#[test]
+fn test_constant_folding_bitwise() {
+  use crate::irgen::IRGen;
+  use crate::pass::{count_ops, DeadCodeElimination};
+  use parser::parse::parse;
+  use parser::tokenize::Tokenizer;
+
+  let input = r"
+int main() {
+  return (~0 ^ 5) & (1 << 4 | 7 % 4) >> 1;
+}
+  ";
+  let it = Tokenizer::new(input);
+  let funs = parse(it).unwrap();
+  let module = IRGen::new("mod".to_string()).irgen(funs).unwrap();
+
+  let fun_id = module.get_function_by_name("main").unwrap();
+  let module = ConstantFolding::new(module).run();
+  let module = DeadCodeElimination::new(module).run();
+  assert_eq!(count_ops(module.get_function(fun_id)), 2);
+}
+
+#[test]
+fn test_constant_folding_float() {
+  use crate::irgen::IRGen;
+  use crate::pass::{count_ops, DeadCodeElimination};
+  use parser::parse::parse;
+  use parser::tokenize::Tokenizer;
+
+  // `0.1f + 0.2f == 0.3f` holds in single precision but not in double precision
+  let input = r"
+int main() {
+  return (0.1f + 0.2f == 0.3f) + (0.1 + 0.2 == 0.3) * 2 + (1 / 0.0 > 1e308) * 4 + (int)-2.5 * 8;
+}
+  ";
+  let it = Tokenizer::new(input);
+  let funs = parse(it).unwrap();
+  let module = IRGen::new("mod".to_string()).irgen(funs).unwrap();
+
+  let fun_id = module.get_function_by_name("main").unwrap();
+  let module = ConstantFolding::new(module).run();
+  let module = DeadCodeElimination::new(module).run();
+  assert_eq!(count_ops(module.get_function(fun_id)), 2);
+  assert!(module.to_string().contains("const -11"));
+}
+
+#[test]
+fn test_constant_folding_overflow() {
+  use crate::irgen::IRGen;
+  use parser::parse::parse;
+  use parser::tokenize::Tokenizer;
+
+  let input = r"
+int main() {
+  unsigned long x = 0x8000000000000000 + 0x8000000000000000 - 1;
+  long y = -9223372036854775807 - 1;
+  return x + 1 / 0 + y / -1;
+}
+  ";
+  let it = Tokenizer::new(input);
+  let funs = parse(it).unwrap();
+  let module = IRGen::new("mod".to_string()).irgen(funs).unwrap();
+
+  let module = ConstantFolding::new(module).run();
+  let dump = module.to_string();
+  assert!(dump.contains("const -1"));
+  // divisions by zero and of overflowing operands are not folded
+  assert_eq!(dump.matches(" = div ").count(), 2);
+}
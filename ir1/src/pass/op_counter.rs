@@ -52,7 +52,7 @@ fn test_count_ops() {
 
   builder.position_at_end(next_block);
   let v4 = builder.build_add(v3, v2);
-  builder.build_return(v4);
+  builder.build_return(Some(v4));
 
   let fun = builder.retrieve_function();
   assert_eq!(6, count_ops(&fun));
@@ -1,3 +1,4 @@
+use inkwell::attributes::{Attribute, AttributeLoc};
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
@@ -5,11 +6,15 @@ use inkwell::module::{Linkage, Module};
 use inkwell::types::*;
 use inkwell::values::*;
 use inkwell::AddressSpace;
-use inkwell::IntPredicate;
-use parser::common::{Expected, Scope};
+use inkwell::{FloatPredicate, IntPredicate};
+use parser::common::{Expected, JoinView, Scope};
+use parser::diagnostic::Diagnostic;
 use parser::err;
-use parser::parse::{Stmt, TopLevel, AST};
-use parser::ty::Type;
+use parser::parse::{self, ASTKind, Init, Stmt, StorageClass, TopLevel, TopLevelKind, AST};
+use parser::source::Span;
+use parser::ty::{self, Layout, RegClass, Type};
+use std::cmp;
+use std::collections::HashMap;
 
 // Module ∋ Function ∋ BasicBlock ∋ Instruction
 pub struct CodeGen<'ctx> {
@@ -25,11 +30,22 @@ impl<'ctx> CodeGen<'ctx> {
     let module = self.context.create_module("mod");
     let mut var_scope = Scope::new();
     let mut tag_scope = Scope::new();
+    let mut struct_members = HashMap::new();
+    let mut anon_types = Vec::new();
+    let mut fun_decls = HashMap::new();
     var_scope.push();
     tag_scope.push();
     for toplevel in toplevels {
-      GenTopLevel::new(self.context, &module, &mut var_scope, &mut tag_scope)
-        .gen_toplevel(toplevel)?;
+      GenTopLevel::new(
+        self.context,
+        &module,
+        &mut var_scope,
+        &mut tag_scope,
+        &mut struct_members,
+        &mut anon_types,
+        &mut fun_decls,
+      )
+      .gen_toplevel(toplevel)?;
     }
     var_scope.pop();
     tag_scope.pop();
@@ -37,6 +53,41 @@ impl<'ctx> CodeGen<'ctx> {
   }
 }
 
+/// Members of a struct or union, keyed by the name of the LLVM struct type it is lowered to.
+struct Members<'ctx> {
+  names: Vec<String>,
+  tys: Vec<Type>,
+  /// LLVM types of the members of a union, which share the storage of the LLVM struct type.
+  /// `None` for a struct.
+  union_types: Option<Vec<BasicTypeEnum<'ctx>>>,
+}
+
+/// How a parameter or return value is passed by the System V x86-64 ABI.
+#[derive(Clone)]
+enum PassBy<'ctx> {
+  /// Passed as a value of its LLVM type, as a scalar is.
+  Direct,
+  /// A struct or union passed in registers, as values of the types its eightbytes are coerced
+  /// to.
+  Coerce(StructType<'ctx>, Vec<BasicTypeEnum<'ctx>>),
+  /// A struct or union passed in memory, through a pointer to a copy of the argument (`byval`)
+  /// or to the object to return (`sret`).
+  Memory(StructType<'ctx>),
+}
+
+/// How the return value and each parameter of a function are passed.
+struct FunAbi<'ctx> {
+  ret: PassBy<'ctx>,
+  params: Vec<PassBy<'ctx>>,
+}
+
+/// Blocks of the `case` and `default` labels of a switch statement.
+#[derive(Default)]
+struct SwitchLabels<'ctx> {
+  cases: Vec<(i64, BasicBlock<'ctx>)>,
+  default: Option<BasicBlock<'ctx>>,
+}
+
 enum StmtKind<'ctx> {
   Terminator,
   NoTerminator,
@@ -48,9 +99,26 @@ struct GenTopLevel<'a, 'ctx> {
   module: &'a Module<'ctx>,
   builder: Builder<'ctx>,
   var_scope: &'a mut Scope<PointerValue<'ctx>>,
-  tag_scope: &'a mut Scope<Vec<String>>,
+  // LLVM struct type of each struct or union tag in scope, where the tags of unions are named
+  // by `union_type_name`
+  tag_scope: &'a mut Scope<StructType<'ctx>>,
+  struct_members: &'a mut HashMap<String, Members<'ctx>>,
+  // LLVM struct type of each anonymous struct or union, which is not scoped as it cannot be
+  // referred to by tag
+  anon_types: &'a mut Vec<(Type, StructType<'ctx>)>,
+  // Type and location of the latest definition, or of the first declaration, of each function
+  fun_decls: &'a mut HashMap<String, (Type, Span)>,
   break_label: Vec<BasicBlock<'ctx>>,
   cont_label: Vec<BasicBlock<'ctx>>,
+  switch_labels: Vec<SwitchLabels<'ctx>>,
+  // Block of each label in the function, and the location of a `goto` to it while it is not
+  // defined yet
+  labels: HashMap<String, (BasicBlock<'ctx>, Option<Span>)>,
+  // How the function being generated returns its value
+  ret_pass: PassBy<'ctx>,
+  // Value of the lhs of each enclosing compound assignment or postfix increment or decrement
+  // before the assignment
+  assigned_values: Vec<BasicValueEnum<'ctx>>,
 }
 
 impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
@@ -58,84 +126,225 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     context: &'ctx Context,
     module: &'a Module<'ctx>,
     var_scope: &'a mut Scope<PointerValue<'ctx>>,
-    tag_scope: &'a mut Scope<Vec<String>>,
+    tag_scope: &'a mut Scope<StructType<'ctx>>,
+    struct_members: &'a mut HashMap<String, Members<'ctx>>,
+    anon_types: &'a mut Vec<(Type, StructType<'ctx>)>,
+    fun_decls: &'a mut HashMap<String, (Type, Span)>,
   ) -> GenTopLevel<'a, 'ctx> {
     let builder = context.create_builder();
     let break_label = Vec::new();
     let cont_label = Vec::new();
+    let switch_labels = Vec::new();
+    let labels = HashMap::new();
     GenTopLevel {
       context,
       module,
       builder,
       var_scope,
       tag_scope,
+      struct_members,
+      anon_types,
+      fun_decls,
       break_label,
       cont_label,
+      switch_labels,
+      labels,
+      ret_pass: PassBy::Direct,
+      assigned_values: Vec::new(),
     }
   }
 
   fn into_inkwell_type(&mut self, ty: Type) -> Expected<BasicTypeEnum<'ctx>> {
+    if let Type::Struct(None, Some(_)) | Type::Union(None, Some(_)) = ty {
+      // an anonymous struct or union is lowered once, so that variables declared with it by
+      // several declarators or through a typedef name have the same type
+      let anon = self.anon_types.iter().find(|(anon_ty, _)| *anon_ty == ty);
+      if let Some((_, struct_type)) = anon {
+        return Ok(struct_type.as_basic_type_enum());
+      }
+    }
     match ty {
-      Type::Int => Ok(self.context.i64_type().as_basic_type_enum()),
-      Type::Char => Ok(self.context.i8_type().as_basic_type_enum()),
-      Type::Pointer(ty) => {
+      Type::Void => err!("`void` has no values"),
+      // `_Bool` is stored as a byte holding 0 or 1
+      Type::Bool | Type::Char | Type::UChar => Ok(self.context.i8_type().as_basic_type_enum()),
+      Type::Short | Type::UShort => Ok(self.context.i16_type().as_basic_type_enum()),
+      Type::Int | Type::UInt => Ok(self.context.i32_type().as_basic_type_enum()),
+      Type::Long | Type::ULong | Type::LongLong | Type::ULongLong => {
+        Ok(self.context.i64_type().as_basic_type_enum())
+      }
+      Type::Float => Ok(self.context.f32_type().as_basic_type_enum()),
+      Type::Double => Ok(self.context.f64_type().as_basic_type_enum()),
+      Type::Pointer(ty) if *ty == Type::Void => {
+        // `void *` is lowered to `i8*` as LLVM has no pointer to void
         let res = self
-          .into_inkwell_type(*ty)?
+          .context
+          .i8_type()
           .ptr_type(AddressSpace::default())
           .as_basic_type_enum();
         Ok(res)
       }
-      Type::Array(ty, size) => {
+      Type::Pointer(ty) => match *ty {
+        Type::FunTy(ret_ty, param_tys, _, variadic) => {
+          let fn_type = self.into_inkwell_fn_type(*ret_ty, param_tys, variadic)?;
+          Ok(
+            fn_type
+              .ptr_type(AddressSpace::default())
+              .as_basic_type_enum(),
+          )
+        }
+        ty => {
+          let res = self
+            .into_inkwell_type(ty)?
+            .ptr_type(AddressSpace::default())
+            .as_basic_type_enum();
+          Ok(res)
+        }
+      },
+      Type::Array(elem_ty, Some(size)) => {
         let res = self
-          .into_inkwell_type(*ty)?
+          .into_inkwell_type(*elem_ty)?
           .array_type(size)
           .as_basic_type_enum();
         Ok(res)
       }
-      Type::FunTy(..) => {
-        todo!()
-        // let return_type = self.into_inkwell_type(*ret_ty)?;
-        // let param_types: Vec<_> = param_tys
-        //   .into_iter()
-        //   .map(|(ty, _name)| self.into_inkwell_type(ty)?.into())
-        //   .collect();
-        // return_type
-        //   .fn_type(param_types.as_slice(), false)
-        //   .as_any_type_enum()
-      }
-      Type::Struct(struct_name, mems) => {
-        if let Some((mem_tys, mem_names)) = mems {
-          let mem_types = mem_tys
-            .into_iter()
-            .map(|ty| self.into_inkwell_type(ty).map(|x| x.into()))
-            .collect::<Result<Vec<_>, _>>()?;
-          if let Some(name) = struct_name {
-            if self.tag_scope.get_all(&name).is_some() {
-              return err!("struct already exists");
-            }
-            let struct_type = self.context.opaque_struct_type(&name);
-            struct_type.set_body(mem_types.as_slice(), false);
-            self.tag_scope.insert(name, mem_names);
-            Ok(struct_type.as_basic_type_enum())
-          } else {
-            let struct_type = self.context.opaque_struct_type("struct.anon");
-            struct_type.set_body(mem_types.as_slice(), false);
-            let name = struct_type.get_name().unwrap().to_str().unwrap();
-            self.tag_scope.insert(name.to_string(), mem_names);
-            Ok(struct_type.as_basic_type_enum())
+      Type::Array(_, None) => err!("incomplete type `{}` has no size", ty),
+      // functions are referred to through pointers to them
+      Type::FunTy(..) => err!("function type `{}` has no values", ty),
+      Type::Struct(Some(name), None) => Ok(self.tag_type(&name).as_basic_type_enum()),
+      Type::Struct(None, None) => err!("Both the name and body of the struct are missing"),
+      Type::Struct(struct_name, Some((mem_tys, mem_names))) => {
+        let struct_type = if let Some(name) = struct_name {
+          // the tag is declared before the members, which may point to the struct itself
+          let struct_type = self.declare_tag(&name);
+          if !struct_type.is_opaque() {
+            return err!("struct `{}` already exists", name);
           }
+          struct_type
         } else {
-          if let Some(name) = struct_name {
-            if let Some(struct_type) = self.context.get_struct_type(&name) {
-              Ok(struct_type.as_basic_type_enum())
-            } else {
-              err!("struct does not exist")
-            }
-          } else {
-            err!("Both the name and body of the struct are missing")
+          let struct_type = self.context.opaque_struct_type("struct.anon");
+          let anon_ty = Type::Struct(None, Some((mem_tys.clone(), mem_names.clone())));
+          self.anon_types.push((anon_ty, struct_type));
+          struct_type
+        };
+        let mem_types = mem_tys
+          .iter()
+          .map(|ty| self.into_inkwell_type(ty.clone()).map(|x| x.into()))
+          .collect::<Result<Vec<_>, _>>()?;
+        let tags = |ty: &Type| self.tag_members(ty);
+        if mem_tys.iter().any(|ty| ty.layout(&tags).is_none()) {
+          return err!("struct has a member of incomplete type");
+        }
+        struct_type.set_body(mem_types.as_slice(), false);
+        let members = Members {
+          names: mem_names,
+          tys: mem_tys,
+          union_types: None,
+        };
+        self.insert_members(struct_type, members);
+        Ok(struct_type.as_basic_type_enum())
+      }
+      Type::Union(Some(name), None) => {
+        let union_type = self.tag_type(&union_type_name(&name));
+        Ok(union_type.as_basic_type_enum())
+      }
+      Type::Union(None, None) => err!("Both the name and body of the union are missing"),
+      Type::Union(union_name, Some((mem_tys, mem_names))) => {
+        let union_type = if let Some(name) = union_name {
+          // the tag is declared before the members, which may point to the union itself
+          let union_type = self.declare_tag(&union_type_name(&name));
+          if !union_type.is_opaque() {
+            return err!("union `{}` already exists", name);
           }
+          union_type
+        } else {
+          let union_type = self.context.opaque_struct_type("union.anon");
+          let anon_ty = Type::Union(None, Some((mem_tys.clone(), mem_names.clone())));
+          self.anon_types.push((anon_ty, union_type));
+          union_type
+        };
+        let mem_types = mem_tys
+          .iter()
+          .map(|ty| self.into_inkwell_type(ty.clone()))
+          .collect::<Result<Vec<_>, _>>()?;
+        let body = self.union_body(&mem_tys, &mem_types)?;
+        union_type.set_body(body.as_slice(), false);
+        let members = Members {
+          names: mem_names,
+          tys: mem_tys,
+          union_types: Some(mem_types),
+        };
+        self.insert_members(union_type, members);
+        Ok(union_type.as_basic_type_enum())
+      }
+    }
+  }
+
+  /// Returns the LLVM struct type `tag` refers to. A tag which is not declared yet, as in
+  /// `struct t *p;`, is declared in the current scope as an incomplete type.
+  fn tag_type(&mut self, tag: &str) -> StructType<'ctx> {
+    match self.tag_scope.get_all(tag) {
+      Some(&struct_type) => struct_type,
+      None => self.declare_tag(tag),
+    }
+  }
+
+  /// Declares `tag` in the current scope as an incomplete type, unless it is declared in this
+  /// scope already, and returns its LLVM struct type. The type is named after the tag, with a
+  /// numeric suffix if the tag is reused in another scope, so that each scope has its own type.
+  fn declare_tag(&mut self, tag: &str) -> StructType<'ctx> {
+    if let Some(&struct_type) = self.tag_scope.get(tag) {
+      return struct_type;
+    }
+    let name = unique_name(tag, |name| self.context.get_struct_type(name).is_some());
+    let struct_type = self.context.opaque_struct_type(&name);
+    self.tag_scope.insert(tag.to_string(), struct_type);
+    struct_type
+  }
+
+  /// Returns the body of the LLVM struct type a union with members of `mem_tys` is lowered to:
+  /// its first most strictly aligned member, padded to the size of the union.
+  fn union_body(
+    &self,
+    mem_tys: &[Type],
+    mem_types: &[BasicTypeEnum<'ctx>],
+  ) -> Expected<Vec<BasicTypeEnum<'ctx>>> {
+    let tags = |ty: &Type| self.tag_members(ty);
+    let layouts: Option<Vec<_>> = mem_tys.iter().map(|ty| ty.layout(&tags)).collect();
+    if let (Some(layouts), Some(layout)) = (layouts, ty::union_layout(mem_tys, &tags)) {
+      if let Some(base) = layouts.iter().position(|mem| mem.align == layout.align) {
+        let pad = layout.size - layouts[base].size;
+        if pad == 0 {
+          Ok(vec![mem_types[base]])
+        } else {
+          let pad_type = self.context.i8_type().array_type(pad).as_basic_type_enum();
+          Ok(vec![mem_types[base], pad_type])
         }
+      } else {
+        Ok(Vec::new())
       }
+    } else {
+      err!("union has a member of incomplete type")
+    }
+  }
+
+  /// Returns the member types of the struct or union `ty` refers to by tag, unless it is
+  /// incomplete.
+  fn tag_members(&self, ty: &Type) -> Option<Vec<Type>> {
+    let name = match ty {
+      Type::Struct(Some(name), None) => name.clone(),
+      Type::Union(Some(name), None) => union_type_name(name),
+      _ => return None,
+    };
+    let struct_type = *self.tag_scope.get_all(&name)?;
+    let struct_name = struct_type.get_name()?.to_str().ok()?;
+    let members = self.struct_members.get(struct_name)?;
+    Some(members.tys.clone())
+  }
+
+  fn layout_of(&self, ty: &Type, span: Span) -> Expected<Layout> {
+    match ty.layout(&|ty: &Type| self.tag_members(ty)) {
+      Some(layout) => Ok(layout),
+      None => err!(span, "incomplete type `{}` has no size", ty),
     }
   }
 
@@ -152,6 +361,18 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     &mut self,
     var_type: BasicTypeEnum<'ctx>,
     name: String,
+  ) -> PointerValue<'ctx> {
+    let alloca = self.build_entry_block_alloca(var_type, &name);
+    self.var_scope.insert(name, alloca);
+    alloca
+  }
+
+  // Creates a new stack allocation instruction in the entry block of the function, for a
+  // temporary object which is not a variable
+  fn build_entry_block_alloca(
+    &self,
+    var_type: BasicTypeEnum<'ctx>,
+    name: &str,
   ) -> PointerValue<'ctx> {
     let fn_value = self.get_current_fun();
     let entry_block = fn_value.get_first_basic_block().unwrap();
@@ -160,33 +381,209 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
       Some(inst) => builder.position_before(&inst),
       None => builder.position_at_end(entry_block),
     }
-
-    let alloca = builder.build_alloca(var_type, &name);
-    self.var_scope.insert(name, alloca);
-    alloca
+    builder.build_alloca(var_type, name)
   }
 
   // ----- gen_toplevel -----
 
   fn gen_toplevel(mut self, toplevel: TopLevel) -> Expected<AnyValueEnum<'ctx>> {
+    let span = toplevel.span;
+    self
+      .gen_toplevel_impl(toplevel.kind, span)
+      .map_err(|e| e.or_at(span))
+  }
+
+  fn gen_toplevel_impl(
+    &mut self,
+    toplevel: TopLevelKind,
+    span: Span,
+  ) -> Expected<AnyValueEnum<'ctx>> {
     match toplevel {
-      TopLevel::FunDecl(ret_ty, name, param_tys) => {
-        let fun = self.gen_fun_decl(ret_ty, &name, param_tys)?;
+      TopLevelKind::FunDecl(ret_ty, name, param_tys, variadic, storage) => {
+        let fun = self.gen_fun_decl(ret_ty, &name, param_tys, variadic, storage, span)?;
         Ok(fun.as_any_value_enum())
       }
-      TopLevel::FunDef(ret_ty, name, param_tys, param_names, body) => {
-        let fun = self.gen_fun_def(ret_ty, &name, param_tys, param_names, body)?;
+      TopLevelKind::FunDef(ret_ty, name, param_tys, param_names, variadic, body, storage) => {
+        let fun = self.gen_fun_def(
+          ret_ty,
+          &name,
+          param_tys,
+          param_names,
+          variadic,
+          body,
+          storage,
+          span,
+        )?;
         Ok(fun.as_any_value_enum())
       }
-      TopLevel::VarDef(ty, name, init) => {
-        let var = self.gen_var_def(ty, name, init)?;
+      TopLevelKind::VarDef(ty, name, init, storage) => {
+        let var = self.gen_var_def(ty, name, init, storage, span)?;
         Ok(var.as_any_value_enum())
       }
-      TopLevel::StructDef(ty) => {
-        let struct_type = self.into_inkwell_type(ty)?;
-        Ok(struct_type.const_zero().as_any_value_enum())
+      TopLevelKind::StructDef(ty) => {
+        let struct_type = self.gen_struct_def(ty)?;
+        Ok(struct_type.get_undef().as_any_value_enum())
+      }
+    }
+  }
+
+  /// Generates the definition of a struct or union, or the declaration of its tag by `struct t;`
+  /// which hides the tag of an outer scope until it is defined.
+  fn gen_struct_def(&mut self, ty: Type) -> Expected<StructType<'ctx>> {
+    match ty {
+      Type::Struct(Some(name), None) => Ok(self.declare_tag(&name)),
+      Type::Union(Some(name), None) => Ok(self.declare_tag(&union_type_name(&name))),
+      ty => Ok(self.into_inkwell_type(ty)?.into_struct_type()),
+    }
+  }
+
+  /// Returns the LLVM function type of a function returning `ret_ty`, which may be `void`.
+  /// Structs and unions are passed as `fun_abi` lowers them.
+  fn into_inkwell_fn_type(
+    &mut self,
+    ret_ty: Type,
+    param_tys: Vec<Type>,
+    variadic: bool,
+  ) -> Expected<FunctionType<'ctx>> {
+    let abi = self.fun_abi(&ret_ty, &param_tys)?;
+    let mut param_types: Vec<BasicMetadataTypeEnum<'ctx>> = Vec::new();
+    if let PassBy::Memory(struct_type) = abi.ret {
+      param_types.push(struct_type.ptr_type(AddressSpace::default()).into());
+    }
+    for (ty, pass) in std::iter::zip(param_tys, abi.params) {
+      match pass {
+        PassBy::Direct => param_types.push(self.into_inkwell_type(ty)?.into()),
+        PassBy::Coerce(_, types) => param_types.extend(types.into_iter().map(|ty| ty.into())),
+        PassBy::Memory(struct_type) => {
+          param_types.push(struct_type.ptr_type(AddressSpace::default()).into())
+        }
+      }
+    }
+    match abi.ret {
+      PassBy::Direct if ret_ty != Type::Void => {
+        let return_type = self.into_inkwell_type(ret_ty)?;
+        Ok(return_type.fn_type(param_types.as_slice(), variadic))
+      }
+      PassBy::Coerce(_, types) if types.len() == 1 => {
+        Ok(types[0].fn_type(param_types.as_slice(), variadic))
+      }
+      PassBy::Coerce(_, types) if types.len() == 2 => {
+        let return_type = self.context.struct_type(types.as_slice(), false);
+        Ok(return_type.fn_type(param_types.as_slice(), variadic))
+      }
+      _ => Ok(
+        self
+          .context
+          .void_type()
+          .fn_type(param_types.as_slice(), variadic),
+      ),
+    }
+  }
+
+  /// Returns how the return value and parameters of a function are passed by the System V
+  /// x86-64 ABI. A struct or union passed in registers is passed in memory instead if the
+  /// parameters preceding it leave too few registers for it.
+  fn fun_abi(&mut self, ret_ty: &Type, param_tys: &[Type]) -> Expected<FunAbi<'ctx>> {
+    let mut int_regs: usize = 6;
+    let mut sse_regs: usize = 8;
+    let ret = self.pass_by(ret_ty)?;
+    if let PassBy::Memory(_) = ret {
+      // the address of the object to return is passed as the first argument
+      int_regs -= 1;
+    }
+    let mut params = Vec::with_capacity(param_tys.len());
+    for ty in param_tys {
+      let pass = match self.pass_by(ty)? {
+        PassBy::Direct if ty.is_float() => {
+          sse_regs = sse_regs.saturating_sub(1);
+          PassBy::Direct
+        }
+        PassBy::Direct => {
+          int_regs = int_regs.saturating_sub(1);
+          PassBy::Direct
+        }
+        PassBy::Coerce(struct_type, types) => {
+          let sse = types.iter().filter(|ty| ty.is_float_type()).count();
+          let int = types.len() - sse;
+          if int <= int_regs && sse <= sse_regs {
+            int_regs -= int;
+            sse_regs -= sse;
+            PassBy::Coerce(struct_type, types)
+          } else {
+            PassBy::Memory(struct_type)
+          }
+        }
+        pass @ PassBy::Memory(_) => pass,
+      };
+      params.push(pass);
+    }
+    Ok(FunAbi { ret, params })
+  }
+
+  /// Returns how a value of type `ty` is passed if enough registers are left. A struct or union
+  /// is passed in the registers of the classes of its eightbytes, or in memory if it is larger
+  /// than 16 bytes.
+  fn pass_by(&mut self, ty: &Type) -> Expected<PassBy<'ctx>> {
+    if !matches!(ty, Type::Struct(..) | Type::Union(..)) {
+      return Ok(PassBy::Direct);
+    }
+    let struct_type = self.into_inkwell_type(ty.clone())?.into_struct_type();
+    let tags = |ty: &Type| self.tag_members(ty);
+    let size = match ty.layout(&tags) {
+      Some(layout) => layout.size,
+      None => return err!("incomplete type `{}` has no size", ty),
+    };
+    match ty::classify(ty, &tags) {
+      Some(classes) => {
+        let types = classes
+          .into_iter()
+          .enumerate()
+          .map(|(i, class)| {
+            // the last eightbyte is coerced to a type no larger than the rest of the object
+            let bytes = cmp::min(size - 8 * i as u32, 8);
+            match class {
+              RegClass::Integer => self
+                .context
+                .custom_width_int_type(8 * bytes)
+                .as_basic_type_enum(),
+              RegClass::Sse if bytes <= 4 => self.context.f32_type().as_basic_type_enum(),
+              RegClass::Sse => self.context.f64_type().as_basic_type_enum(),
+            }
+          })
+          .collect();
+        Ok(PassBy::Coerce(struct_type, types))
+      }
+      None => Ok(PassBy::Memory(struct_type)),
+    }
+  }
+
+  /// Returns the `sret` and `byval` attributes of the parameters of a function or a call,
+  /// which tell LLVM the objects passed in memory.
+  fn abi_attributes(&self, abi: &FunAbi<'ctx>) -> Vec<(AttributeLoc, Attribute)> {
+    let type_attribute = |name: &str, struct_type: StructType<'ctx>| {
+      let kind_id = Attribute::get_named_enum_kind_id(name);
+      self
+        .context
+        .create_type_attribute(kind_id, struct_type.as_any_type_enum())
+    };
+    let mut attributes = Vec::new();
+    let mut index = 0;
+    if let PassBy::Memory(struct_type) = abi.ret {
+      attributes.push((AttributeLoc::Param(0), type_attribute("sret", struct_type)));
+      index += 1;
+    }
+    for pass in &abi.params {
+      match pass {
+        PassBy::Direct => index += 1,
+        PassBy::Coerce(_, types) => index += types.len() as u32,
+        PassBy::Memory(struct_type) => {
+          let attribute = type_attribute("byval", *struct_type);
+          attributes.push((AttributeLoc::Param(index), attribute));
+          index += 1;
+        }
       }
     }
+    attributes
   }
 
   fn gen_fun_decl(
@@ -194,29 +591,59 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     ret_ty: Type,
     name: &str,
     param_tys: Vec<Type>,
+    variadic: bool,
+    storage: StorageClass,
+    span: Span,
   ) -> Expected<FunctionValue<'ctx>> {
+    let fun_ty = Type::FunTy(
+      Box::new(ret_ty.clone()),
+      param_tys.clone(),
+      Vec::new(),
+      variadic,
+    );
     if let Some(fn_value) = self.module.get_function(name) {
-      let stored_fn_type = fn_value.get_type();
-      let return_type = self.into_inkwell_type(ret_ty)?;
-      let param_types = param_tys
-        .into_iter()
-        .map(|ty| self.into_inkwell_type(ty))
-        .collect::<Result<Vec<_>, _>>()?;
-      if return_type == stored_fn_type.get_return_type().unwrap()
-        && param_types == stored_fn_type.get_param_types()
-      {
+      if storage == StorageClass::Static && fn_value.get_linkage() != Linkage::Internal {
+        // a function declared without `static` keeps its external linkage
+        let mut diag = Diagnostic::error(format!(
+          "static declaration of `{}` follows non-static declaration",
+          name
+        ))
+        .at(span);
+        if let Some((_, prev_span)) = self.fun_decls.get(name) {
+          diag = diag.with_label(*prev_span, "previous declaration is here");
+        }
+        return Err(diag);
+      }
+      let fn_type = self.into_inkwell_fn_type(ret_ty, param_tys, variadic)?;
+      if fn_type == fn_value.get_type() {
         Ok(fn_value)
       } else {
-        err!("function type differs from the previous declaration")
+        let mut diag = Diagnostic::error(format!(
+          "function type differs from the previous declaration of `{}`",
+          name
+        ))
+        .with_primary(span, format!("`{}` declared here as `{}`", name, fun_ty));
+        if let Some((prev_ty, prev_span)) = self.fun_decls.get(name) {
+          diag = diag.with_label(
+            *prev_span,
+            format!("previous declaration of `{}` as `{}`", name, prev_ty),
+          );
+        }
+        Err(diag)
       }
     } else {
-      let return_type = self.into_inkwell_type(ret_ty)?;
-      let param_types = param_tys
-        .into_iter()
-        .map(|ty| self.into_inkwell_type(ty).map(|x| x.into()))
-        .collect::<Result<Vec<_>, _>>()?;
-      let fn_type = return_type.fn_type(param_types.as_slice(), false);
-      Ok(self.module.add_function(name, fn_type, None))
+      self.fun_decls.insert(name.to_string(), (fun_ty, span));
+      let abi = self.fun_abi(&ret_ty, &param_tys)?;
+      let fn_type = self.into_inkwell_fn_type(ret_ty, param_tys, variadic)?;
+      let linkage = match storage {
+        StorageClass::Static => Some(Linkage::Internal),
+        _ => None,
+      };
+      let fn_value = self.module.add_function(name, fn_type, linkage);
+      for (loc, attribute) in self.abi_attributes(&abi) {
+        fn_value.add_attribute(loc, attribute);
+      }
+      Ok(fn_value)
     }
   }
 
@@ -226,15 +653,24 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     name: &str,
     param_tys: Vec<Type>,
     param_names: Vec<String>,
+    variadic: bool,
     body: Vec<Stmt>,
+    storage: StorageClass,
+    span: Span,
   ) -> Expected<FunctionValue<'ctx>> {
     assert_eq!(param_tys.len(), param_names.len());
+    let abi = self.fun_abi(&ret_ty, &param_tys)?;
     // Check consistency with forward declaration
-    let fn_value = self.gen_fun_decl(ret_ty, &name, param_tys)?;
+    let fn_value = self.gen_fun_decl(ret_ty, &name, param_tys, variadic, storage, span)?;
     // Check function does not exist
     if fn_value.count_basic_blocks() != 0 {
-      return err!("function already exists");
+      let mut diag = Diagnostic::error(format!("redefinition of function `{}`", name)).at(span);
+      if let Some((_, prev_span)) = self.fun_decls.get(name) {
+        diag = diag.with_label(*prev_span, "previous definition is here");
+      }
+      return Err(diag);
     }
+    self.fun_decls.get_mut(name).unwrap().1 = span;
 
     // Create first basic block
     let entry_block = self.context.append_basic_block(fn_value, "entry");
@@ -243,20 +679,40 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     self.var_scope.push();
     self.tag_scope.push();
     // Allocate function parameters
-    for (name, param) in std::iter::zip(param_names, fn_value.get_param_iter()) {
-      if self.var_scope.get(&name).is_none() {
-        let alloca = self.create_entry_block_alloca(param.get_type(), name);
-        self.gen_assign_impl(alloca, param)?;
-      } else {
-        return err!("function parameter already exists");
+    let mut params = fn_value.get_param_iter();
+    if let PassBy::Memory(_) = abi.ret {
+      // skip the address of the object to return
+      params.next();
+    }
+    for (name, pass) in std::iter::zip(param_names, abi.params) {
+      if self.var_scope.get(&name).is_some() {
+        return err!(span, "function parameter `{}` already exists", name);
+      }
+      match pass {
+        PassBy::Direct => {
+          let param = params.next().unwrap();
+          let alloca = self.create_entry_block_alloca(param.get_type(), name);
+          self.gen_assign_impl(alloca, param)?;
+        }
+        PassBy::Coerce(struct_type, types) => {
+          let alloca = self.create_entry_block_alloca(struct_type.as_basic_type_enum(), name);
+          let values: Vec<_> = params.by_ref().take(types.len()).collect();
+          self.store_eightbytes(alloca, &types, values);
+        }
+        PassBy::Memory(_) => {
+          // the parameter lives in the copy the caller passes the address of
+          let ptr = params.next().unwrap().into_pointer_value();
+          self.var_scope.insert(name, ptr);
+        }
       }
     }
+    self.ret_pass = abi.ret;
     // Generate function body
-    let mut stmt_kind = StmtKind::NoTerminator;
-    for stmt in body {
-      stmt_kind = self.gen_stmt(stmt)?;
-      if matches!(stmt_kind, StmtKind::Terminator) {
-        break;
+    let stmt_kind = self.gen_stmts(body)?;
+    // Check labels
+    for (label, (_, undefined_use)) in &self.labels {
+      if let Some(span) = undefined_use {
+        return err!(*span, "label `{}` is used but not defined", label);
       }
     }
     // Destroy first scope
@@ -265,7 +721,13 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
 
     // Check terminator
     if !matches!(stmt_kind, StmtKind::Terminator) {
-      return err!("no terminator in function");
+      if matches!(self.ret_pass, PassBy::Direct) && fn_value.get_type().get_return_type().is_none()
+      {
+        // a void function returns when it reaches the end of its body
+        self.builder.build_return(None);
+      } else {
+        return err!(span, "no terminator in function `{}`", name);
+      }
     }
 
     if fn_value.verify(true) {
@@ -273,7 +735,7 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     } else {
       // TODO: 前方宣言後の定義ならば定義のみ消す。前方宣言なしの定義ならば宣言ごと消す
       // unsafe { fn_value.delete(); }
-      err!("failed to verify function")
+      err!(span, "failed to verify function `{}`", name)
     }
   }
 
@@ -281,27 +743,136 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     &mut self,
     ty: Type,
     name: String,
-    init: Option<AST>,
+    init: Option<Init>,
+    storage: StorageClass,
+    span: Span,
+  ) -> Expected<GlobalValue<'ctx>> {
+    if storage == StorageClass::Extern && init.is_none() {
+      let var = self.gen_global_decl(ty, &name, span)?;
+      self.var_scope.insert(name, var.as_pointer_value());
+      return Ok(var);
+    }
+
+    let var_type = self.into_inkwell_type(ty)?;
+    let var = match self.module.get_global(&name) {
+      Some(prev) if prev.get_initializer().is_some() => {
+        return err!(span, "global variable `{}` already exists", name);
+      }
+      // a variable declared `extern` is defined here
+      Some(prev) => self.define_declared_global(prev, var_type, &name, span)?,
+      None => self.module.add_global(var_type, None, &name),
+    };
+    if storage == StorageClass::Static {
+      var.set_linkage(Linkage::Internal);
+    }
+    self.gen_global_init(var, var_type, init, span)?;
+    self.var_scope.insert(name, var.as_pointer_value());
+    Ok(var)
+  }
+
+  /// Declares a global variable defined elsewhere, as `extern` does, unless a variable of the
+  /// same name is declared already. An array of unknown size is declared as an array of length 0.
+  fn gen_global_decl(&mut self, ty: Type, name: &str, span: Span) -> Expected<GlobalValue<'ctx>> {
+    let var_type = match ty {
+      Type::Array(elem_ty, None) => self
+        .into_inkwell_type(*elem_ty)?
+        .array_type(0)
+        .as_basic_type_enum(),
+      ty => self.into_inkwell_type(ty)?,
+    };
+    match self.module.get_global(name) {
+      Some(prev) => {
+        let prev_type = prev.as_pointer_value().get_type().get_element_type();
+        if is_compatible_global(prev_type, var_type.as_any_type_enum()) {
+          Ok(prev)
+        } else {
+          err!(
+            span,
+            "type of global variable `{}` differs from the previous declaration",
+            name
+          )
+        }
+      }
+      None => Ok(self.module.add_global(var_type, None, name)),
+    }
+  }
+
+  /// Returns the definition of type `var_type` of the global variable declared as `prev`. The
+  /// declaration of an array of unknown size is replaced, as the definition gives its size.
+  fn define_declared_global(
+    &mut self,
+    prev: GlobalValue<'ctx>,
+    var_type: BasicTypeEnum<'ctx>,
+    name: &str,
+    span: Span,
   ) -> Expected<GlobalValue<'ctx>> {
-    if self.var_scope.get(&name).is_some() {
-      return err!("global variable already exists");
+    let prev_ptr = prev.as_pointer_value();
+    let prev_type = prev_ptr.get_type().get_element_type();
+    if prev_type == var_type.as_any_type_enum() {
+      Ok(prev)
+    } else if is_compatible_global(prev_type, var_type.as_any_type_enum()) {
+      let var = self.module.add_global(var_type, None, "");
+      let ptr = var.as_pointer_value().const_cast(prev_ptr.get_type());
+      prev_ptr.replace_all_uses_with(ptr);
+      unsafe { prev.delete() };
+      var.as_pointer_value().set_name(name);
+      Ok(var)
+    } else {
+      err!(
+        span,
+        "type of global variable `{}` differs from the previous declaration",
+        name
+      )
     }
+  }
 
+  /// Defines a `static` local variable, which is a global variable of internal linkage named
+  /// after the function and the variable.
+  fn gen_static_local(
+    &mut self,
+    ty: Type,
+    name: &str,
+    init: Option<Init>,
+    span: Span,
+  ) -> Expected<GlobalValue<'ctx>> {
+    let fun_name = self
+      .get_current_fun()
+      .get_name()
+      .to_str()
+      .unwrap()
+      .to_string();
+    let global_name = unique_name(&format!("{}.{}", fun_name, name), |name| {
+      self.module.get_global(name).is_some()
+    });
     let var_type = self.into_inkwell_type(ty)?;
-    let var = self.module.add_global(var_type.clone(), None, &name);
+    let var = self.module.add_global(var_type, None, &global_name);
+    var.set_linkage(Linkage::Internal);
+    self.gen_global_init(var, var_type, init, span)?;
+    Ok(var)
+  }
 
-    let rhs = if let Some(expr) = init {
-      self.gen_expr(expr)?
+  /// Initializes the global variable `var` with the constant `init`, or with zeros.
+  fn gen_global_init(
+    &mut self,
+    var: GlobalValue<'ctx>,
+    var_type: BasicTypeEnum<'ctx>,
+    init: Option<Init>,
+    span: Span,
+  ) -> Expected<()> {
+    let rhs = if let Some(init) = init {
+      self.gen_const_init(var_type, init, span)?
     } else {
       var_type.const_zero()
     };
 
     if rhs.get_type() == var_type {
       var.set_initializer(&rhs);
-      self.var_scope.insert(name, var.as_pointer_value());
-      Ok(var)
+      Ok(())
     } else {
-      err!("inconsistent types in operands of global variable initialization")
+      err!(
+        span,
+        "inconsistent types in operands of global variable initialization"
+      )
     }
   }
 
@@ -309,58 +880,187 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
 
   // Returns if the last basic block has a terminator
   fn gen_stmt(&mut self, stmt: Stmt) -> Expected<StmtKind<'ctx>> {
+    let span = stmt.span;
+    self
+      .gen_stmt_impl(stmt.kind, span)
+      .map_err(|e| e.or_at(span))
+  }
+
+  fn gen_stmt_impl(&mut self, stmt: parse::StmtKind, span: Span) -> Expected<StmtKind<'ctx>> {
     match stmt {
-      Stmt::VarDef(var_defs) => {
+      parse::StmtKind::VarDef(var_defs, storage) => {
         for (ty, name, init) in var_defs.into_iter() {
           if self.var_scope.get(&name).is_some() {
-            return err!("variable already exists");
+            return err!(span, "variable `{}` already exists", name);
           }
 
-          let var_type = self.into_inkwell_type(ty)?;
-          let rhs = if let Some(expr) = init {
-            self.gen_expr(expr)?
-          } else {
-            var_type.const_zero()
-          };
-
-          let alloca = self.create_entry_block_alloca(var_type.clone(), name);
-          self.gen_assign_impl(alloca, rhs)?;
+          match storage {
+            StorageClass::Default => {
+              let var_type = self.into_inkwell_type(ty)?;
+              let alloca = self.create_entry_block_alloca(var_type.clone(), name);
+              self.gen_init(alloca, init.unwrap_or(Init::Zero))?;
+            }
+            StorageClass::Static => {
+              let var = self.gen_static_local(ty, &name, init, span)?;
+              self.var_scope.insert(name, var.as_pointer_value());
+            }
+            StorageClass::Extern => {
+              if init.is_some() {
+                return err!(span, "`extern` variable `{}` has an initializer", name);
+              }
+              let var = self.gen_global_decl(ty, &name, span)?;
+              self.var_scope.insert(name, var.as_pointer_value());
+            }
+          }
         }
         Ok(StmtKind::NoTerminator)
       }
-      Stmt::StructDef(ty) => {
-        self.into_inkwell_type(ty)?;
+      parse::StmtKind::StructDef(ty) => {
+        self.gen_struct_def(ty)?;
         Ok(StmtKind::NoTerminator)
       }
-      Stmt::IfElse(cond, then, else_) => self.gen_if_else(cond, then, else_),
-      Stmt::For(init, cond, inc, body) => self.gen_for(init, cond, inc, *body),
-      Stmt::Break => {
+      parse::StmtKind::IfElse(cond, then, else_) => self.gen_if_else(cond, then, else_),
+      parse::StmtKind::For(init, cond, inc, body) => self.gen_for(init, cond, inc, *body),
+      parse::StmtKind::DoWhile(body, cond) => self.gen_do_while(*body, cond),
+      parse::StmtKind::Switch(cond, body) => self.gen_switch(cond, *body),
+      parse::StmtKind::Case(n, stmt) => self.gen_switch_label(Some(n), *stmt),
+      parse::StmtKind::Default(stmt) => self.gen_switch_label(None, *stmt),
+      parse::StmtKind::Break => {
         self
           .builder
           .build_unconditional_branch(*self.break_label.last().unwrap());
         Ok(StmtKind::Terminator)
       }
-      Stmt::Cont => {
+      parse::StmtKind::Cont => {
         self
           .builder
           .build_unconditional_branch(*self.cont_label.last().unwrap());
         Ok(StmtKind::Terminator)
       }
-      Stmt::Return(expr) => {
+      parse::StmtKind::Goto(name) => {
+        let block = match self.labels.get(&name) {
+          Some(&(block, _)) => block,
+          None => {
+            let block = self
+              .context
+              .append_basic_block(self.get_current_fun(), "label");
+            self.labels.insert(name, (block, Some(span)));
+            block
+          }
+        };
+        self.builder.build_unconditional_branch(block);
+        Ok(StmtKind::Terminator)
+      }
+      parse::StmtKind::Label(name, stmt) => {
+        let current_block = self.get_current_basic_block();
+        let block = match self.labels.get_mut(&name) {
+          Some((block, undefined_use @ Some(_))) => {
+            *undefined_use = None;
+            block.move_after(current_block).unwrap();
+            *block
+          }
+          Some((_, None)) => return err!(span, "redefinition of label `{}`", name),
+          None => {
+            let block = self
+              .context
+              .insert_basic_block_after(current_block, "label");
+            self.labels.insert(name, (block, None));
+            block
+          }
+        };
+        if current_block.get_terminator().is_none() {
+          self.builder.build_unconditional_branch(block);
+        }
+        self.builder.position_at_end(block);
+        self.gen_stmt(*stmt)
+      }
+      parse::StmtKind::Return(None) => {
+        let return_type = match &self.ret_pass {
+          PassBy::Direct => self.get_current_fun().get_type().get_return_type(),
+          PassBy::Coerce(struct_type, _) | PassBy::Memory(struct_type) => {
+            Some(struct_type.as_basic_type_enum())
+          }
+        };
+        if let Some(return_type) = return_type {
+          err!(
+            span,
+            "non-void function should return a value of type `{}`",
+            type_name(return_type)
+          )
+        } else {
+          self.builder.build_return(None);
+          Ok(StmtKind::Terminator)
+        }
+      }
+      parse::StmtKind::Return(Some(expr)) if !matches!(self.ret_pass, PassBy::Direct) => {
+        self.gen_struct_return(expr)
+      }
+      parse::StmtKind::Return(Some(expr)) => {
+        let expr_span = expr.span;
+        let return_type = self.get_current_fun().get_type().get_return_type();
+        if return_type.is_none() {
+          return err!(expr_span, "void function should not return a value");
+        }
         let ret = self.gen_expr(expr)?;
-        if Some(ret.get_type()) == self.get_current_fun().get_type().get_return_type() {
+        let ret = self.convert_void_pointer(ret, return_type.unwrap());
+        if Some(ret.get_type()) == return_type {
           self.builder.build_return(Some(&ret));
           Ok(StmtKind::Terminator)
         } else {
-          err!("return type differs from the declaration")
+          let expected = return_type.map(type_name).unwrap_or_default();
+          err!(
+            expr_span,
+            "return type differs from the declaration, expecting `{}`, found `{}`",
+            expected,
+            type_name(ret.get_type())
+          )
         }
       }
-      Stmt::Block(stmts) => self.gen_block(stmts),
-      Stmt::Expr(expr) => {
-        let value = self.gen_expr(expr)?;
-        Ok(StmtKind::Expr(value))
+      parse::StmtKind::Block(stmts) => self.gen_block(stmts),
+      parse::StmtKind::Expr(expr) => match self.gen_void_expr(expr)? {
+        Some(value) => Ok(StmtKind::Expr(value)),
+        None => Ok(StmtKind::NoTerminator),
+      },
+    }
+  }
+
+  /// Generates the return of a struct or union, which is stored to the object the caller
+  /// passes the address of, or returned in the registers of its eightbytes.
+  fn gen_struct_return(&mut self, expr: AST) -> Expected<StmtKind<'ctx>> {
+    let span = expr.span;
+    let ret = self.gen_expr(expr)?;
+    let ret_pass = self.ret_pass.clone();
+    let struct_type = match &ret_pass {
+      PassBy::Coerce(struct_type, _) | PassBy::Memory(struct_type) => *struct_type,
+      PassBy::Direct => unreachable!(),
+    };
+    if ret.get_type() != struct_type.as_basic_type_enum() {
+      return err!(
+        span,
+        "return type differs from the declaration, expecting `{}`, found `{}`",
+        type_name(struct_type),
+        type_name(ret.get_type())
+      );
+    }
+    match ret_pass {
+      PassBy::Memory(_) => {
+        let sret = self.get_current_fun().get_first_param().unwrap();
+        self.builder.build_store(sret.into_pointer_value(), ret);
+        self.builder.build_return(None);
+      }
+      PassBy::Coerce(_, types) => {
+        let tmp = self.build_entry_block_alloca(struct_type.as_basic_type_enum(), "");
+        self.builder.build_store(tmp, ret);
+        let values = self.load_eightbytes(tmp, &types);
+        match values.as_slice() {
+          [] => self.builder.build_return(None),
+          [value] => self.builder.build_return(Some(value)),
+          values => self.builder.build_aggregate_return(values),
+        };
       }
+      PassBy::Direct => unreachable!(),
     }
+    Ok(StmtKind::Terminator)
   }
 
   fn gen_if_else(
@@ -389,11 +1089,7 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     };
 
     // cond:
-    let lhs = self.gen_expr_into_int_value(cond)?;
-    let zero = lhs.get_type().const_int(0, false);
-    let comp = self
-      .builder
-      .build_int_compare(IntPredicate::NE, lhs, zero, "cond");
+    let comp = self.gen_cond(cond)?;
     self
       .builder
       .build_conditional_branch(comp, then_block, else_block);
@@ -431,9 +1127,9 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
 
   fn gen_for(
     &mut self,
-    init: Option<AST>,
-    cond: Option<AST>,
-    inc: Option<AST>,
+    init: Option<Box<AST>>,
+    cond: Option<Box<AST>>,
+    inc: Option<Box<AST>>,
     body: Stmt,
   ) -> Expected<StmtKind<'ctx>> {
     /* `for (A; B; C) D`
@@ -459,18 +1155,14 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
 
     // init:
     if let Some(expr) = init {
-      self.gen_expr(expr)?;
+      self.gen_expr(*expr)?;
     }
     self.builder.build_unconditional_branch(cond_block);
 
     // cond:
     self.builder.position_at_end(cond_block);
     if let Some(expr) = cond {
-      let lhs = self.gen_expr_into_int_value(expr)?;
-      let zero = lhs.get_type().const_int(0, false);
-      let comp = self
-        .builder
-        .build_int_compare(IntPredicate::NE, lhs, zero, "cond");
+      let comp = self.gen_cond(*expr)?;
       self
         .builder
         .build_conditional_branch(comp, body_block, cont_block);
@@ -488,7 +1180,7 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     // inc:
     self.builder.position_at_end(inc_block);
     if let Some(expr) = inc {
-      self.gen_expr(expr)?;
+      self.gen_expr(*expr)?;
     }
     self.builder.build_unconditional_branch(cond_block);
 
@@ -508,85 +1200,224 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     }
   }
 
-  fn gen_block(&mut self, stmts: Vec<Stmt>) -> Expected<StmtKind<'ctx>> {
-    self.var_scope.push();
-    self.tag_scope.push();
-    let mut stmt_kind = StmtKind::NoTerminator;
-    for stmt in stmts {
-      stmt_kind = self.gen_stmt(stmt)?;
-      if matches!(stmt_kind, StmtKind::Terminator) {
-        break;
-      }
+  fn gen_do_while(&mut self, body: Stmt, cond: AST) -> Expected<StmtKind<'ctx>> {
+    /* `do A while (B)`
+     *   goto body;
+     * body:
+     *   A;
+     *   goto cond;
+     * cond:
+     *   B != 0 ? goto body : goto cont;
+     * cont:
+     */
+    let current_block = self.get_current_basic_block();
+    let body_block = self.context.insert_basic_block_after(current_block, "body");
+    let cond_block = self.context.insert_basic_block_after(body_block, "cond");
+    let cont_block = self.context.insert_basic_block_after(cond_block, "cont");
+    self.break_label.push(cont_block.clone());
+    self.cont_label.push(cond_block.clone());
+    self.builder.build_unconditional_branch(body_block);
+
+    // body:
+    self.builder.position_at_end(body_block);
+    let stmt_kind_in_body = self.gen_stmt(body)?;
+    if !matches!(stmt_kind_in_body, StmtKind::Terminator) {
+      self.builder.build_unconditional_branch(cond_block);
     }
-    self.var_scope.pop();
-    self.tag_scope.pop();
-    Ok(stmt_kind)
-  }
 
-  // ----- gen_expr -----
+    // cond:
+    self.builder.position_at_end(cond_block);
+    if cond_block.get_first_use().is_none() {
+      self.builder.build_unreachable();
+    } else {
+      let comp = self.gen_cond(cond)?;
+      self
+        .builder
+        .build_conditional_branch(comp, body_block, cont_block);
+    }
 
-  fn gen_expr_into_int_value(&mut self, expr: AST) -> Expected<IntValue<'ctx>> {
-    let value = self.gen_expr(expr)?;
-    if value.is_int_value() {
-      Ok(value.into_int_value())
+    // cont:
+    self.builder.position_at_end(cont_block);
+    let cont_block_is_unreachable = cont_block.get_first_use().is_none();
+    if cont_block_is_unreachable {
+      self.builder.build_unreachable();
+    }
+
+    self.break_label.pop();
+    self.cont_label.pop();
+    if cont_block_is_unreachable {
+      Ok(StmtKind::Terminator)
     } else {
-      err!("unexpected type in expression, expecting int type")
+      Ok(StmtKind::NoTerminator)
     }
   }
 
-  fn gen_expr(&mut self, expr: AST) -> Expected<BasicValueEnum<'ctx>> {
-    let i64_type = self.context.i64_type();
-    match expr {
-      AST::Ternary(cond, then, else_) => self.gen_ternary(*cond, *then, *else_),
-      AST::Eq(n, m) => {
-        let lhs = self.gen_expr_into_int_value(*n)?;
-        let rhs = self.gen_expr_into_int_value(*m)?;
-        let cmp = self
-          .builder
-          .build_int_compare(IntPredicate::EQ, lhs, rhs, "");
-        let zext = self
-          .builder
-          .build_int_z_extend(cmp, i64_type, "")
-          .as_basic_value_enum();
-        Ok(zext)
-      }
-      AST::Ne(n, m) => {
-        let lhs = self.gen_expr_into_int_value(*n)?;
-        let rhs = self.gen_expr_into_int_value(*m)?;
-        let cmp = self
-          .builder
-          .build_int_compare(IntPredicate::NE, lhs, rhs, "");
-        let zext = self
-          .builder
-          .build_int_z_extend(cmp, i64_type, "")
-          .as_basic_value_enum();
-        Ok(zext)
-      }
-      AST::Lt(n, m) => {
-        let lhs = self.gen_expr_into_int_value(*n)?;
-        let rhs = self.gen_expr_into_int_value(*m)?;
-        let cmp = self
-          .builder
-          .build_int_compare(IntPredicate::SLT, lhs, rhs, "");
-        let zext = self
-          .builder
-          .build_int_z_extend(cmp, i64_type, "")
-          .as_basic_value_enum();
-        Ok(zext)
+  fn gen_switch(&mut self, cond: AST, body: Stmt) -> Expected<StmtKind<'ctx>> {
+    /* `switch (A) B`
+     *   switch A, default or cont [case values of B];
+     * body:
+     *   B;
+     *   goto cont;
+     * cont:
+     */
+    let value = self.gen_expr_into_int_value(cond)?;
+    let switch_block = self.get_current_basic_block();
+    let body_block = self.context.insert_basic_block_after(switch_block, "body");
+    let cont_block = self.context.insert_basic_block_after(body_block, "cont");
+    self.break_label.push(cont_block.clone());
+    self.switch_labels.push(SwitchLabels::default());
+
+    // body:
+    self.builder.position_at_end(body_block);
+    let stmt_kind_in_body = self.gen_stmt(body)?;
+    if !matches!(stmt_kind_in_body, StmtKind::Terminator) {
+      self.builder.build_unconditional_branch(cont_block);
+    }
+
+    // switch:
+    let labels = self.switch_labels.pop().unwrap();
+    let cases: Vec<_> = labels
+      .cases
+      .into_iter()
+      .map(|(n, block)| (value.get_type().const_int(n as u64, true), block))
+      .collect();
+    self.builder.position_at_end(switch_block);
+    let default = labels.default.unwrap_or(cont_block);
+    self.builder.build_switch(value, default, cases.as_slice());
+
+    // cont:
+    self.builder.position_at_end(cont_block);
+    let cont_block_is_unreachable = cont_block.get_first_use().is_none();
+    if cont_block_is_unreachable {
+      self.builder.build_unreachable();
+    }
+
+    self.break_label.pop();
+    if cont_block_is_unreachable {
+      Ok(StmtKind::Terminator)
+    } else {
+      Ok(StmtKind::NoTerminator)
+    }
+  }
+
+  /// Generates a `case` label of value `n`, or a `default` label if `n` is `None`, which starts
+  /// a new block that the previous one falls through to.
+  fn gen_switch_label(&mut self, n: Option<i64>, stmt: Stmt) -> Expected<StmtKind<'ctx>> {
+    let current_block = self.get_current_basic_block();
+    let label_block = self.context.insert_basic_block_after(current_block, "case");
+    if current_block.get_terminator().is_none() {
+      self.builder.build_unconditional_branch(label_block);
+    }
+
+    self.builder.position_at_end(label_block);
+    let labels = self.switch_labels.last_mut().unwrap();
+    match n {
+      Some(n) => labels.cases.push((n, label_block)),
+      None => labels.default = Some(label_block),
+    }
+    self.gen_stmt(stmt)
+  }
+
+  fn gen_stmts(&mut self, stmts: Vec<Stmt>) -> Expected<StmtKind<'ctx>> {
+    let mut stmt_kind = StmtKind::NoTerminator;
+    for stmt in stmts {
+      if matches!(stmt_kind, StmtKind::Terminator) {
+        // statements following a terminator are unreachable unless they contain a label, and
+        // the ones before the label are generated in a block without predecessors
+        if !stmt.contains_label() {
+          continue;
+        }
+        if !stmt.is_labeled() {
+          let current_block = self.get_current_basic_block();
+          let block = self
+            .context
+            .insert_basic_block_after(current_block, "unreachable");
+          self.builder.position_at_end(block);
+        }
       }
-      AST::Le(n, m) => {
-        let lhs = self.gen_expr_into_int_value(*n)?;
-        let rhs = self.gen_expr_into_int_value(*m)?;
-        let cmp = self
+      stmt_kind = self.gen_stmt(stmt)?;
+    }
+    Ok(stmt_kind)
+  }
+
+  fn gen_block(&mut self, stmts: Vec<Stmt>) -> Expected<StmtKind<'ctx>> {
+    self.var_scope.push();
+    self.tag_scope.push();
+    let stmt_kind = self.gen_stmts(stmts)?;
+    self.var_scope.pop();
+    self.tag_scope.pop();
+    Ok(stmt_kind)
+  }
+
+  // ----- gen_expr -----
+
+  fn gen_expr_into_int_value(&mut self, expr: AST) -> Expected<IntValue<'ctx>> {
+    let span = expr.span;
+    let value = self.gen_expr(expr)?;
+    if value.is_int_value() {
+      Ok(value.into_int_value())
+    } else {
+      err!(span, "unexpected type in expression, expecting int type")
+    }
+  }
+
+  /// Generates `expr` compared with zero, or with null if it is a pointer, as an `i1` value. A
+  /// NaN compares unequal to zero.
+  fn gen_cond(&mut self, expr: AST) -> Expected<IntValue<'ctx>> {
+    let span = expr.span;
+    match self.gen_expr(expr)? {
+      BasicValueEnum::IntValue(value) => {
+        let zero = value.get_type().const_int(0, false);
+        let comp = self
           .builder
-          .build_int_compare(IntPredicate::SLE, lhs, rhs, "");
-        let zext = self
+          .build_int_compare(IntPredicate::NE, value, zero, "cond");
+        Ok(comp)
+      }
+      BasicValueEnum::FloatValue(value) => {
+        let zero = value.get_type().const_float(0.0);
+        let comp = self
           .builder
-          .build_int_z_extend(cmp, i64_type, "")
-          .as_basic_value_enum();
-        Ok(zext)
+          .build_float_compare(FloatPredicate::UNE, value, zero, "cond");
+        Ok(comp)
+      }
+      BasicValueEnum::PointerValue(ptr) => Ok(self.builder.build_is_not_null(ptr, "cond")),
+      _ => err!(
+        span,
+        "unexpected type in condition, expecting arithmetic or pointer type"
+      ),
+    }
+  }
+
+  fn gen_expr(&mut self, expr: AST) -> Expected<BasicValueEnum<'ctx>> {
+    let i64_type = self.context.i64_type();
+    let span = expr.span;
+    match expr.kind {
+      ASTKind::Ternary(cond, then, else_) => self.gen_ternary(*cond, *then, *else_),
+      ASTKind::PostfixAssign(n, m) => {
+        let (_, old) = self.gen_op_assign(*n, *m, span)?;
+        Ok(old)
       }
-      AST::Add(n, m) => {
+      ASTKind::LogOr(n, m) => self.gen_logical(*n, *m, false),
+      ASTKind::LogAnd(n, m) => self.gen_logical(*n, *m, true),
+      ASTKind::Eq(n, m) => self.gen_compare(*n, *m, IntPredicate::EQ, FloatPredicate::OEQ),
+      ASTKind::Ne(n, m) => self.gen_compare(*n, *m, IntPredicate::NE, FloatPredicate::UNE),
+      ASTKind::Lt(n, m) => {
+        let pred = if n.ty.is_unsigned() {
+          IntPredicate::ULT
+        } else {
+          IntPredicate::SLT
+        };
+        self.gen_compare(*n, *m, pred, FloatPredicate::OLT)
+      }
+      ASTKind::Le(n, m) => {
+        let pred = if n.ty.is_unsigned() {
+          IntPredicate::ULE
+        } else {
+          IntPredicate::SLE
+        };
+        self.gen_compare(*n, *m, pred, FloatPredicate::OLE)
+      }
+      ASTKind::Add(n, m) => {
         let lhs = self.gen_expr(*n)?;
         let rhs = self.gen_expr(*m)?;
         match (lhs, rhs) {
@@ -597,16 +1428,23 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
               .as_basic_value_enum();
             Ok(res)
           }
+          (BasicValueEnum::FloatValue(lhs), BasicValueEnum::FloatValue(rhs)) => {
+            let res = self
+              .builder
+              .build_float_add(lhs, rhs, "")
+              .as_basic_value_enum();
+            Ok(res)
+          }
           (BasicValueEnum::PointerValue(ptr), BasicValueEnum::IntValue(idx)) => {
             Ok(self.gen_pointer_add_impl(ptr, idx))
           }
           (BasicValueEnum::IntValue(idx), BasicValueEnum::PointerValue(ptr)) => {
             Ok(self.gen_pointer_add_impl(ptr, idx))
           }
-          _ => err!("inconsistent types in operands of addition"),
+          _ => err!(span, "inconsistent types in operands of addition"),
         }
       }
-      AST::Sub(n, m) => {
+      ASTKind::Sub(n, m) => {
         let lhs = self.gen_expr(*n)?;
         let rhs = self.gen_expr(*m)?;
         match (lhs, rhs) {
@@ -617,13 +1455,20 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
               .as_basic_value_enum();
             Ok(res)
           }
+          (BasicValueEnum::FloatValue(lhs), BasicValueEnum::FloatValue(rhs)) => {
+            let res = self
+              .builder
+              .build_float_sub(lhs, rhs, "")
+              .as_basic_value_enum();
+            Ok(res)
+          }
           (BasicValueEnum::PointerValue(ptr), BasicValueEnum::IntValue(idx)) => {
             let idx = self.builder.build_int_neg(idx, "");
             Ok(self.gen_pointer_add_impl(ptr, idx))
           }
           (BasicValueEnum::PointerValue(lhs), BasicValueEnum::PointerValue(rhs)) => {
             if lhs.get_type() != rhs.get_type() {
-              return err!("inconsistent types in operands of pointer difference");
+              return err!(span, "inconsistent types in operands of pointer difference");
             }
             let res = self
               .builder
@@ -631,10 +1476,18 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
               .as_basic_value_enum();
             Ok(res)
           }
-          _ => err!("inconsistent types in operands of subtraction"),
+          _ => err!(span, "inconsistent types in operands of subtraction"),
         }
       }
-      AST::Mul(n, m) => {
+      ASTKind::Mul(n, m) if n.ty.is_float() => {
+        let (lhs, rhs) = self.gen_float_operands(*n, *m)?;
+        let res = self
+          .builder
+          .build_float_mul(lhs, rhs, "")
+          .as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::Mul(n, m) => {
         let lhs = self.gen_expr_into_int_value(*n)?;
         let rhs = self.gen_expr_into_int_value(*m)?;
         let res = self
@@ -643,16 +1496,99 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
           .as_basic_value_enum();
         Ok(res)
       }
-      AST::Div(n, m) => {
+      ASTKind::Div(n, m) if n.ty.is_float() => {
+        let (lhs, rhs) = self.gen_float_operands(*n, *m)?;
+        let res = self
+          .builder
+          .build_float_div(lhs, rhs, "")
+          .as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::Div(n, m) => {
+        let is_unsigned = n.ty.is_unsigned();
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = if is_unsigned {
+          self.builder.build_int_unsigned_div(lhs, rhs, "")
+        } else {
+          self.builder.build_int_signed_div(lhs, rhs, "")
+        };
+        Ok(res.as_basic_value_enum())
+      }
+      ASTKind::Mod(n, m) => {
+        let is_unsigned = n.ty.is_unsigned();
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = if is_unsigned {
+          self.builder.build_int_unsigned_rem(lhs, rhs, "")
+        } else {
+          self.builder.build_int_signed_rem(lhs, rhs, "")
+        };
+        Ok(res.as_basic_value_enum())
+      }
+      ASTKind::BitAnd(n, m) => {
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = self.builder.build_and(lhs, rhs, "").as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::BitOr(n, m) => {
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = self.builder.build_or(lhs, rhs, "").as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::BitXor(n, m) => {
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = self.builder.build_xor(lhs, rhs, "").as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::Shl(n, m) => {
         let lhs = self.gen_expr_into_int_value(*n)?;
         let rhs = self.gen_expr_into_int_value(*m)?;
         let res = self
           .builder
-          .build_int_signed_div(lhs, rhs, "")
+          .build_left_shift(lhs, rhs, "")
           .as_basic_value_enum();
         Ok(res)
       }
-      AST::Addr(n) => {
+      ASTKind::Shr(n, m) => {
+        let sign_extend = !n.ty.is_unsigned();
+        let lhs = self.gen_expr_into_int_value(*n)?;
+        let rhs = self.gen_expr_into_int_value(*m)?;
+        let res = self
+          .builder
+          .build_right_shift(lhs, rhs, sign_extend, "")
+          .as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::Not(n) => {
+        let comp = self.gen_cond(*n)?;
+        let not = self.builder.build_not(comp, "");
+        let zext = self
+          .builder
+          .build_int_z_extend(not, self.context.i32_type(), "")
+          .as_basic_value_enum();
+        Ok(zext)
+      }
+      ASTKind::BitNot(n) => {
+        let value = self.gen_expr_into_int_value(*n)?;
+        let res = self.builder.build_not(value, "").as_basic_value_enum();
+        Ok(res)
+      }
+      ASTKind::Neg(n) => match self.gen_expr(*n)? {
+        BasicValueEnum::IntValue(value) => {
+          let res = self.builder.build_int_neg(value, "");
+          Ok(res.as_basic_value_enum())
+        }
+        BasicValueEnum::FloatValue(value) => {
+          let res = self.builder.build_float_neg(value, "");
+          Ok(res.as_basic_value_enum())
+        }
+        _ => err!(span, "wrong type argument to unary minus"),
+      },
+      ASTKind::Addr(n) => {
         let var = self.gen_addr(*n)?;
         if var.get_type().get_element_type().is_array_type() {
           Ok(self.gen_array_addr_impl(var))
@@ -660,17 +1596,30 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
           Ok(var.as_basic_value_enum())
         }
       }
-      AST::Cast(ty, n) => {
-        let cast_type = self.into_inkwell_type(ty)?;
+      ASTKind::Cast(Type::Bool, n) => {
+        // converting to `_Bool` yields whether the value is nonzero
+        let comp = self.gen_cond(*n)?;
+        let zext = self
+          .builder
+          .build_int_z_extend(comp, self.context.i8_type(), "")
+          .as_basic_value_enum();
+        Ok(zext)
+      }
+      ASTKind::Cast(ty, n) => {
+        let to_unsigned = ty.is_unsigned();
+        let cast_type = self.into_inkwell_type(ty.clone())?;
+        let is_unsigned = n.ty.is_unsigned();
         let value = self.gen_expr(*n)?;
         match (cast_type, value) {
           (BasicTypeEnum::IntType(int_type), BasicValueEnum::IntValue(int_value)) => {
             if int_type.get_bit_width() > int_value.get_type().get_bit_width() {
-              let res = self
-                .builder
-                .build_int_s_extend(int_value, int_type, "sext")
-                .as_basic_value_enum();
-              Ok(res)
+              // extend by the signedness of the operand type
+              let res = if is_unsigned {
+                self.builder.build_int_z_extend(int_value, int_type, "zext")
+              } else {
+                self.builder.build_int_s_extend(int_value, int_type, "sext")
+              };
+              Ok(res.as_basic_value_enum())
             } else if int_type.get_bit_width() < int_value.get_type().get_bit_width() {
               let res = self
                 .builder
@@ -681,50 +1630,121 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
               Ok(int_value.as_basic_value_enum())
             }
           }
-          _ => todo!(),
+          (BasicTypeEnum::FloatType(float_type), BasicValueEnum::IntValue(int_value)) => {
+            let res = if is_unsigned {
+              self
+                .builder
+                .build_unsigned_int_to_float(int_value, float_type, "uitofp")
+            } else {
+              self
+                .builder
+                .build_signed_int_to_float(int_value, float_type, "sitofp")
+            };
+            Ok(res.as_basic_value_enum())
+          }
+          (BasicTypeEnum::IntType(int_type), BasicValueEnum::FloatValue(float_value)) => {
+            // the value is truncated toward zero
+            let res = if to_unsigned {
+              self
+                .builder
+                .build_float_to_unsigned_int(float_value, int_type, "fptoui")
+            } else {
+              self
+                .builder
+                .build_float_to_signed_int(float_value, int_type, "fptosi")
+            };
+            Ok(res.as_basic_value_enum())
+          }
+          (BasicTypeEnum::FloatType(float_type), BasicValueEnum::FloatValue(float_value)) => {
+            let f64_type = self.context.f64_type();
+            let res = if float_type == float_value.get_type() {
+              float_value
+            } else if float_type == f64_type {
+              self
+                .builder
+                .build_float_ext(float_value, float_type, "fpext")
+            } else {
+              self
+                .builder
+                .build_float_trunc(float_value, float_type, "fptrunc")
+            };
+            Ok(res.as_basic_value_enum())
+          }
+          (BasicTypeEnum::PointerType(ptr_type), BasicValueEnum::PointerValue(ptr_value)) => {
+            let res = self.builder.build_pointer_cast(ptr_value, ptr_type, "");
+            Ok(res.as_basic_value_enum())
+          }
+          (BasicTypeEnum::PointerType(ptr_type), BasicValueEnum::IntValue(int_value)) => {
+            let res = self
+              .builder
+              .build_int_to_ptr(int_value, ptr_type, "inttoptr");
+            Ok(res.as_basic_value_enum())
+          }
+          (BasicTypeEnum::IntType(int_type), BasicValueEnum::PointerValue(ptr_value)) => {
+            let res = self
+              .builder
+              .build_ptr_to_int(ptr_value, int_type, "ptrtoint");
+            Ok(res.as_basic_value_enum())
+          }
+          _ => err!(span, "invalid cast to `{}`", ty),
         }
       }
-      AST::Block(stmts) => {
+      ASTKind::Block(stmts) => {
         let stmt_kind = self.gen_block(stmts)?;
         match stmt_kind {
           StmtKind::Terminator => todo!(),
           StmtKind::NoTerminator => {
-            err!("GNU statement expression does not end with expression statement")
+            err!(
+              span,
+              "GNU statement expression does not end with expression statement"
+            )
           }
           StmtKind::Expr(value) => Ok(value),
         }
       }
-      AST::Call(name, args) => {
-        if let Some(callee) = self.module.get_function(&name) {
-          let stored_param_types = callee.get_type().get_param_types();
-          let args = args
-            .into_iter()
-            .map(|expr| self.gen_expr(expr))
-            .collect::<Result<Vec<_>, _>>()?;
-          let arg_types: Vec<_> = args.iter().map(|arg| arg.get_type()).collect();
-          if arg_types != stored_param_types {
-            return err!("argument types mismatch function parameter types");
-          }
-
-          let args: Vec<_> = args.into_iter().map(|arg| arg.into()).collect();
-          let res = self
-            .builder
-            .build_call(callee, args.as_slice(), "")
-            .try_as_basic_value()
-            .unwrap_left();
-          Ok(res)
-        } else {
-          err!("function does not exist")
-        }
+      ASTKind::Call(callee, args) => match self.gen_call(*callee, args, span)? {
+        Some(res) => Ok(res),
+        None => err!(span, "void value not ignored as it ought to be"),
+      },
+      ASTKind::VaStart(_) | ASTKind::VaEnd(_) => {
+        err!(span, "void value not ignored as it ought to be")
       }
-      AST::Num(n) => {
-        if n < 0 {
-          todo!();
-        }
-        Ok(i64_type.const_int(n as u64, false).as_basic_value_enum())
+      ASTKind::VaArg(ap, ty) => {
+        let ap = self.gen_va_list(*ap)?;
+        let arg_type = self.into_inkwell_type(ty)?;
+        Ok(self.builder.build_va_arg(ap, arg_type, ""))
       }
-      AST::Str(s) => {
-        let value = self.context.const_string(s.as_bytes(), true);
+      ASTKind::Num(n, ty) => {
+        let int_type = self.into_inkwell_type(ty)?.into_int_type();
+        Ok(int_type.const_int(n as u64, true).as_basic_value_enum())
+      }
+      ASTKind::Float(x, ty) => {
+        let float_type = self.into_inkwell_type(ty)?.into_float_type();
+        Ok(float_type.const_float(x).as_basic_value_enum())
+      }
+      ASTKind::SizeOf(ty) => {
+        let layout = self.layout_of(&ty, span)?;
+        Ok(
+          i64_type
+            .const_int(layout.size.into(), false)
+            .as_basic_value_enum(),
+        )
+      }
+      ASTKind::AlignOf(ty) => {
+        let layout = self.layout_of(&ty, span)?;
+        Ok(
+          i64_type
+            .const_int(layout.align.into(), false)
+            .as_basic_value_enum(),
+        )
+      }
+      ASTKind::AssignedValue(_) => Ok(*self.assigned_values.last().unwrap()),
+      ASTKind::Enumerator(_name, n) => {
+        let int_type = self.context.i32_type();
+        Ok(int_type.const_int(n as u64, true).as_basic_value_enum())
+      }
+      ASTKind::Str(s) => {
+        let value = self.context.const_string(&s, true);
         let global = self.module.add_global(value.get_type(), None, ".str");
         global.set_initializer(&value);
         global.set_linkage(Linkage::Private);
@@ -732,10 +1752,17 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
         let ptr = global.as_pointer_value();
         Ok(self.gen_array_addr_impl(ptr))
       }
-      AST::Assign(..) | AST::Deref(..) | AST::Dot(..) | AST::Ident(..) => {
-        let var = self.gen_addr(expr)?;
+      kind @ (ASTKind::Assign(..)
+      | ASTKind::CompoundAssign(..)
+      | ASTKind::Deref(..)
+      | ASTKind::Dot(..)
+      | ASTKind::Ident(..)) => {
+        let var = self.gen_addr(AST::new(kind, span, expr.ty))?;
         if var.get_type().get_element_type().is_array_type() {
           Ok(self.gen_array_addr_impl(var))
+        } else if var.get_type().get_element_type().is_function_type() {
+          // a function designator is converted to a pointer to the function
+          Ok(var.as_basic_value_enum())
         } else {
           let res = self.builder.build_load(var, "");
           Ok(res)
@@ -744,18 +1771,291 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     }
   }
 
+  /// Generates an expression whose value is discarded, which may be a call to a void
+  /// function. Returns `None` if the expression has no value.
+  fn gen_void_expr(&mut self, expr: AST) -> Expected<Option<BasicValueEnum<'ctx>>> {
+    match expr.kind {
+      ASTKind::Call(callee, args) => self.gen_call(*callee, args, expr.span),
+      ASTKind::VaStart(ap) => {
+        let fn_value = self
+          .builder
+          .get_insert_block()
+          .unwrap()
+          .get_parent()
+          .unwrap();
+        if !fn_value.get_type().is_var_arg() {
+          return err!(
+            expr.span,
+            "`va_start` used in function with fixed parameters"
+          );
+        }
+        self.gen_va_intrinsic("llvm.va_start", *ap)?;
+        Ok(None)
+      }
+      ASTKind::VaEnd(ap) => {
+        self.gen_va_intrinsic("llvm.va_end", *ap)?;
+        Ok(None)
+      }
+      kind => self.gen_expr(AST::new(kind, expr.span, expr.ty)).map(Some),
+    }
+  }
+
+  /// Generates a call to the intrinsic `name` taking the `va_list` `ap` as `i8*`.
+  fn gen_va_intrinsic(&mut self, name: &str, ap: AST) -> Expected<()> {
+    let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+    let intrinsic = self.module.get_function(name).unwrap_or_else(|| {
+      let fn_type = self
+        .context
+        .void_type()
+        .fn_type(&[i8_ptr_type.into()], false);
+      self.module.add_function(name, fn_type, None)
+    });
+    let ap = self.gen_va_list(ap)?;
+    self.builder.build_call(intrinsic, &[ap.into()], "");
+    Ok(())
+  }
+
+  /// Generates the address of the `va_list` `ap` as `i8*`.
+  fn gen_va_list(&mut self, ap: AST) -> Expected<PointerValue<'ctx>> {
+    let span = ap.span;
+    match self.gen_expr(ap)? {
+      BasicValueEnum::PointerValue(ptr) => {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        Ok(self.builder.build_pointer_cast(ptr, i8_ptr_type, ""))
+      }
+      _ => err!(span, "unexpected type in expression, expecting `va_list`"),
+    }
+  }
+
+  /// Generates a call of `callee`. A function named by an identifier is called directly, and
+  /// other callees are evaluated to a pointer to function and called indirectly.
+  fn gen_call(
+    &mut self,
+    callee: AST,
+    args: Vec<AST>,
+    span: Span,
+  ) -> Expected<Option<BasicValueEnum<'ctx>>> {
+    let fun_ty = parse::callee_type(&callee.ty).cloned();
+    let (callee, fn_type, name) = match callee.kind {
+      ASTKind::Ident(name) if self.var_scope.get_all(&name).is_none() => {
+        match self.module.get_function(&name) {
+          Some(fn_value) => (CallableValue::from(fn_value), fn_value.get_type(), name),
+          None => return err!(span, "function `{}` does not exist", name),
+        }
+      }
+      kind => {
+        let callee_span = callee.span;
+        let ptr = match self.gen_expr(AST::new(kind, callee_span, callee.ty))? {
+          BasicValueEnum::PointerValue(ptr) => ptr,
+          _ => {
+            return err!(
+              callee_span,
+              "called object is not a function or function pointer"
+            )
+          }
+        };
+        let fn_type = match ptr.get_type().get_element_type() {
+          AnyTypeEnum::FunctionType(fn_type) => fn_type,
+          _ => {
+            return err!(
+              callee_span,
+              "called object is not a function or function pointer"
+            )
+          }
+        };
+        let callee = CallableValue::try_from(ptr).unwrap();
+        (callee, fn_type, "<function pointer>".to_string())
+      }
+    };
+    let (ret_ty, mut arg_tys) = match fun_ty {
+      Some(Type::FunTy(ret_ty, param_tys, ..)) => (*ret_ty, param_tys),
+      _ => return err!(span, "called object is not a function or function pointer"),
+    };
+    // variadic arguments are passed by their own types
+    let fixed_len = arg_tys.len();
+    arg_tys.extend(args.iter().skip(fixed_len).map(|arg| arg.ty.clone()));
+    let abi = self.fun_abi(&ret_ty, &arg_tys)?;
+    let stored_param_types = fn_type.get_param_types();
+    let sret = match &abi.ret {
+      PassBy::Memory(struct_type) => {
+        Some(self.build_entry_block_alloca(struct_type.as_basic_type_enum(), ""))
+      }
+      _ => None,
+    };
+    let mut lowered_args: Vec<_> = sret.iter().map(|ptr| ptr.as_basic_value_enum()).collect();
+    for (expr, pass) in std::iter::zip(args, &abi.params) {
+      let arg_span = expr.span;
+      let arg = self.gen_expr(expr)?;
+      let struct_type = match pass {
+        PassBy::Direct => {
+          let arg = match stored_param_types.get(lowered_args.len()) {
+            Some(&ty) => self.convert_void_pointer(arg, ty),
+            None => arg,
+          };
+          lowered_args.push(arg);
+          continue;
+        }
+        PassBy::Coerce(struct_type, _) | PassBy::Memory(struct_type) => *struct_type,
+      };
+      if arg.get_type() != struct_type.as_basic_type_enum() {
+        return Err(
+          Diagnostic::error(format!(
+            "argument types mismatch parameter types of function `{}`",
+            name
+          ))
+          .at(arg_span)
+          .with_note(format!(
+            "expecting `{}`, found `{}`",
+            type_name(struct_type),
+            type_name(arg.get_type())
+          )),
+        );
+      }
+      // a struct or union is passed through a copy of it
+      let tmp = self.build_entry_block_alloca(struct_type.as_basic_type_enum(), "");
+      self.builder.build_store(tmp, arg);
+      match pass {
+        PassBy::Coerce(_, types) => lowered_args.extend(self.load_eightbytes(tmp, types)),
+        _ => lowered_args.push(tmp.as_basic_value_enum()),
+      }
+    }
+    let args = lowered_args;
+    let arg_types: Vec<_> = args.iter().map(|arg| arg.get_type()).collect();
+    // a variadic function takes any number of arguments following its parameters
+    let fixed_arg_types = match arg_types.get(..stored_param_types.len()) {
+      Some(fixed) if fn_type.is_var_arg() => fixed,
+      _ => &arg_types,
+    };
+    if fixed_arg_types != stored_param_types {
+      let expected = JoinView::new(stored_param_types.iter().map(|&ty| type_name(ty)), ", ");
+      let found = JoinView::new(arg_types.iter().map(|&ty| type_name(ty)), ", ");
+      return Err(
+        Diagnostic::error(format!(
+          "argument types mismatch parameter types of function `{}`",
+          name
+        ))
+        .at(span)
+        .with_note(format!("expecting `({})`, found `({})`", expected, found)),
+      );
+    }
+
+    let args: Vec<_> = args.into_iter().map(|arg| arg.into()).collect();
+    let call = self.builder.build_call(callee, args.as_slice(), "");
+    for (loc, attribute) in self.abi_attributes(&abi) {
+      call.add_attribute(loc, attribute);
+    }
+    match abi.ret {
+      PassBy::Direct => Ok(call.try_as_basic_value().left()),
+      PassBy::Coerce(struct_type, types) => {
+        let values = match call.try_as_basic_value().left() {
+          None => Vec::new(),
+          Some(BasicValueEnum::StructValue(value)) if types.len() == 2 => (0..2)
+            .map(|i| self.builder.build_extract_value(value, i, "").unwrap())
+            .collect(),
+          Some(value) => vec![value],
+        };
+        let tmp = self.build_entry_block_alloca(struct_type.as_basic_type_enum(), "");
+        self.store_eightbytes(tmp, &types, values);
+        Ok(Some(self.builder.build_load(tmp, "")))
+      }
+      PassBy::Memory(_) => Ok(Some(self.builder.build_load(sret.unwrap(), ""))),
+    }
+  }
+
+  /// Stores the eightbytes `values` of a struct or union passed in registers, which are of
+  /// `types`, into the object `ptr` points to.
+  fn store_eightbytes(
+    &mut self,
+    ptr: PointerValue<'ctx>,
+    types: &[BasicTypeEnum<'ctx>],
+    values: Vec<BasicValueEnum<'ctx>>,
+  ) {
+    let ptr = self.eightbytes_addr(ptr, types);
+    for (i, value) in values.into_iter().enumerate() {
+      let field = self.builder.build_struct_gep(ptr, i as u32, "").unwrap();
+      let store = self.builder.build_store(field, value);
+      // the object may be less aligned than the type of its eightbyte
+      store.set_alignment(1).unwrap();
+    }
+  }
+
+  /// Loads the eightbytes of `types` of the struct or union `ptr` points to, to pass it in
+  /// registers.
+  fn load_eightbytes(
+    &mut self,
+    ptr: PointerValue<'ctx>,
+    types: &[BasicTypeEnum<'ctx>],
+  ) -> Vec<BasicValueEnum<'ctx>> {
+    let ptr = self.eightbytes_addr(ptr, types);
+    (0..types.len())
+      .map(|i| {
+        let field = self.builder.build_struct_gep(ptr, i as u32, "").unwrap();
+        let value = self.builder.build_load(field, "");
+        // the object may be less aligned than the type of its eightbyte
+        let load = value.as_instruction_value().unwrap();
+        load.set_alignment(1).unwrap();
+        value
+      })
+      .collect()
+  }
+
+  /// Casts `ptr` to a pointer to a struct of the eightbytes of `types`.
+  fn eightbytes_addr(
+    &self,
+    ptr: PointerValue<'ctx>,
+    types: &[BasicTypeEnum<'ctx>],
+  ) -> PointerValue<'ctx> {
+    let eightbytes_type = self.context.struct_type(types, false);
+    let ptr_type = eightbytes_type.ptr_type(AddressSpace::default());
+    self.builder.build_pointer_cast(ptr, ptr_type, "")
+  }
+
+  /// Generates the comparison of `n` and `m` by `int_pred`, or by `float_pred` if they are of a
+  /// floating type, as an `i32` value of 0 or 1.
+  fn gen_compare(
+    &mut self,
+    n: AST,
+    m: AST,
+    int_pred: IntPredicate,
+    float_pred: FloatPredicate,
+  ) -> Expected<BasicValueEnum<'ctx>> {
+    let cmp = if n.ty.is_float() {
+      let (lhs, rhs) = self.gen_float_operands(n, m)?;
+      self.builder.build_float_compare(float_pred, lhs, rhs, "")
+    } else {
+      let lhs = self.gen_expr_into_int_value(n)?;
+      let rhs = self.gen_expr_into_int_value(m)?;
+      self.builder.build_int_compare(int_pred, lhs, rhs, "")
+    };
+    let zext = self
+      .builder
+      .build_int_z_extend(cmp, self.context.i32_type(), "")
+      .as_basic_value_enum();
+    Ok(zext)
+  }
+
+  /// Generates the operands `n` and `m` of a floating arithmetic operator.
+  fn gen_float_operands(
+    &mut self,
+    n: AST,
+    m: AST,
+  ) -> Expected<(FloatValue<'ctx>, FloatValue<'ctx>)> {
+    let span = n.span.to(m.span);
+    match (self.gen_expr(n)?, self.gen_expr(m)?) {
+      (BasicValueEnum::FloatValue(lhs), BasicValueEnum::FloatValue(rhs)) => Ok((lhs, rhs)),
+      _ => err!(span, "inconsistent types in operands of floating operation"),
+    }
+  }
+
   fn gen_ternary(&mut self, cond: AST, then: AST, else_: AST) -> Expected<BasicValueEnum<'ctx>> {
+    let span = cond.span.to(else_.span);
     let current_block = self.get_current_basic_block();
     let then_block = self.context.insert_basic_block_after(current_block, "then");
     let else_block = self.context.insert_basic_block_after(then_block, "else");
     let merge_block = self.context.insert_basic_block_after(else_block, "merge");
 
     // cond:
-    let lhs = self.gen_expr_into_int_value(cond)?;
-    let zero = lhs.get_type().const_int(0, false);
-    let comp = self
-      .builder
-      .build_int_compare(IntPredicate::NE, lhs, zero, "cond");
+    let comp = self.gen_cond(cond)?;
     self
       .builder
       .build_conditional_branch(comp, then_block, else_block);
@@ -763,23 +2063,66 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
     // then:
     self.builder.position_at_end(then_block);
     let then_value = self.gen_expr(then)?;
+    let then_end = self.get_current_basic_block();
     self.builder.build_unconditional_branch(merge_block);
 
     // else:
     self.builder.position_at_end(else_block);
     let else_value = self.gen_expr(else_)?;
+    let else_end = self.get_current_basic_block();
     self.builder.build_unconditional_branch(merge_block);
 
     // merge:
     if then_value.get_type() != else_value.get_type() {
-      return err!("inconsistent types in operands of ternary operator");
+      return err!(span, "inconsistent types in operands of ternary operator");
     }
     self.builder.position_at_end(merge_block);
     let phi = self.builder.build_phi(then_value.get_type(), "");
-    phi.add_incoming(&[(&then_value, then_block), (&else_value, else_block)]);
+    phi.add_incoming(&[(&then_value, then_end), (&else_value, else_end)]);
     Ok(phi.as_basic_value())
   }
 
+  /// Generates `n && m` if `is_and`, or `n || m` otherwise. `m` is evaluated only if `n` does
+  /// not determine the result.
+  fn gen_logical(&mut self, n: AST, m: AST, is_and: bool) -> Expected<BasicValueEnum<'ctx>> {
+    // lhs:
+    let lhs = self.gen_cond(n)?;
+    let lhs_end = self.get_current_basic_block();
+    let rhs_block = self.context.insert_basic_block_after(lhs_end, "rhs");
+    let merge_block = self.context.insert_basic_block_after(rhs_block, "merge");
+    if is_and {
+      self
+        .builder
+        .build_conditional_branch(lhs, rhs_block, merge_block);
+    } else {
+      self
+        .builder
+        .build_conditional_branch(lhs, merge_block, rhs_block);
+    }
+
+    // rhs:
+    self.builder.position_at_end(rhs_block);
+    let rhs = self.gen_cond(m)?;
+    let rhs_end = self.get_current_basic_block();
+    self.builder.build_unconditional_branch(merge_block);
+
+    // merge:
+    self.builder.position_at_end(merge_block);
+    let bool_type = self.context.bool_type();
+    let short_circuit = bool_type.const_int((!is_and).into(), false);
+    let phi = self.builder.build_phi(bool_type, "");
+    phi.add_incoming(&[(&short_circuit, lhs_end), (&rhs, rhs_end)]);
+    let zext = self
+      .builder
+      .build_int_z_extend(
+        phi.as_basic_value().into_int_value(),
+        self.context.i32_type(),
+        "",
+      )
+      .as_basic_value_enum();
+    Ok(zext)
+  }
+
   fn gen_pointer_add_impl(
     &mut self,
     ptr: PointerValue<'ctx>,
@@ -806,60 +2149,366 @@ impl<'a, 'ctx> GenTopLevel<'a, 'ctx> {
   // ----- gen_addr -----
 
   fn gen_addr(&mut self, expr: AST) -> Expected<PointerValue<'ctx>> {
-    match expr {
-      AST::Assign(n, m) => {
+    let span = expr.span;
+    let is_lvalue = matches!(
+      expr.kind,
+      ASTKind::Assign(..)
+        | ASTKind::CompoundAssign(..)
+        | ASTKind::Deref(..)
+        | ASTKind::Dot(..)
+        | ASTKind::Ident(..)
+    );
+    if !is_lvalue && matches!(expr.ty, Type::Struct(..) | Type::Union(..)) {
+      // a struct or union rvalue, such as a returned one, is stored in a temporary object so
+      // that its members can be accessed
+      let value = self.gen_expr(expr)?;
+      let tmp = self.build_entry_block_alloca(value.get_type(), "");
+      self.builder.build_store(tmp, value);
+      return Ok(tmp);
+    }
+    match expr.kind {
+      ASTKind::Assign(n, m) => {
         let rhs = self.gen_expr(*m)?;
         let lhs = self.gen_addr(*n)?;
-        self.gen_assign_impl(lhs, rhs)
+        self.gen_assign_impl(lhs, rhs).map_err(|e| e.or_at(span))
       }
-      AST::Deref(n) => {
+      ASTKind::CompoundAssign(n, m) => {
+        let (lhs, _) = self.gen_op_assign(*n, *m, span)?;
+        Ok(lhs)
+      }
+      ASTKind::Deref(n) => {
         let ptr = self.gen_expr(*n)?;
         if ptr.is_pointer_value() {
           Ok(ptr.into_pointer_value())
         } else {
-          err!("cannot dereference int value")
+          err!(span, "cannot dereference int value")
         }
       }
-      AST::Dot(n, name) => {
+      ASTKind::Dot(n, name) => {
+        let lhs_ty = n.ty.clone();
         let lhs = self.gen_addr(*n)?;
         if let AnyTypeEnum::StructType(struct_type) = lhs.get_type().get_element_type() {
-          if let Some(index) = self.get_index_from_struct_type(struct_type, &name) {
-            self.builder.build_struct_gep(lhs, index, "").or(err!(
-              "!!!internal error!!! struct member index is out of range"
-            ))
-          } else {
-            err!("struct member index is out of range")
+          if struct_type.is_opaque() {
+            return err!(span, "incomplete type `{}` has no members", lhs_ty);
+          }
+          let members = self.get_members(struct_type);
+          let index = members.names.iter().position(|mem| mem == &name);
+          match (index, &members.union_types) {
+            (Some(index), None) => self
+              .builder
+              .build_struct_gep(lhs, index.try_into().unwrap(), "")
+              .or(err!(
+                span,
+                "!!!internal error!!! struct member index is out of range"
+              )),
+            (Some(index), Some(mem_types)) => {
+              // every member of a union is stored at the address of the union itself
+              let ptr_type = mem_types[index].ptr_type(AddressSpace::default());
+              let ptr = self.builder.build_bitcast(lhs, ptr_type, "");
+              Ok(ptr.into_pointer_value())
+            }
+            (None, None) => err!(span, "struct has no member named `{}`", name),
+            (None, Some(_)) => err!(span, "union has no member named `{}`", name),
           }
         } else {
-          err!("lhs is not a struct")
+          err!(span, "lhs is not a struct or union")
         }
       }
-      AST::Ident(name) => match self.var_scope.get_all(&name) {
+      ASTKind::Ident(name) => match self.var_scope.get_all(&name) {
         Some(&var) => Ok(var),
-        None => err!("variable should be declared before its first use"),
+        None => match self.module.get_function(&name) {
+          Some(fn_value) => Ok(fn_value.as_global_value().as_pointer_value()),
+          None => err!(
+            span,
+            "variable `{}` should be declared before its first use",
+            name
+          ),
+        },
       },
-      _ => err!("cannot obtain address of rvalue"),
+      _ => err!(span, "cannot obtain address of rvalue"),
+    }
+  }
+
+  // ----- gen_init -----
+
+  /// Generates the initialization of the object `ptr` points to, which is zero-filled unless
+  /// an expression initializes it as a whole.
+  fn gen_init(&mut self, ptr: PointerValue<'ctx>, init: Init) -> Expected<()> {
+    if !matches!(init, Init::Expr(_)) {
+      let ty = BasicTypeEnum::try_from(ptr.get_type().get_element_type()).unwrap();
+      self.builder.build_store(ptr, ty.const_zero());
+    }
+    self.gen_init_impl(ptr, init)
+  }
+
+  fn gen_init_impl(&mut self, ptr: PointerValue<'ctx>, init: Init) -> Expected<()> {
+    match init {
+      Init::Zero => Ok(()),
+      Init::Expr(expr) => {
+        let span = expr.span;
+        let value = self.gen_expr(expr)?;
+        self
+          .gen_assign_impl(ptr, value)
+          .map_err(|e| e.or_at(span))?;
+        Ok(())
+      }
+      Init::List(inits) => {
+        for (index, init) in inits.into_iter().enumerate() {
+          if !matches!(init, Init::Zero) {
+            let elem_ptr = self.gen_element_addr(ptr, index);
+            self.gen_init_impl(elem_ptr, init)?;
+          }
+        }
+        Ok(())
+      }
     }
   }
 
-  fn get_index_from_struct_type(&self, struct_type: StructType<'ctx>, mem: &str) -> Option<u32> {
+  /// Returns the address of the element at `index` of the array, or of the member of the struct
+  /// or union, `ptr` points to.
+  fn gen_element_addr(&mut self, ptr: PointerValue<'ctx>, index: usize) -> PointerValue<'ctx> {
+    match ptr.get_type().get_element_type() {
+      AnyTypeEnum::ArrayType(_) => {
+        let i64_type = self.context.i64_type();
+        let indices = [
+          i64_type.const_zero(),
+          i64_type.const_int(index as u64, false),
+        ];
+        unsafe { self.builder.build_in_bounds_gep(ptr, &indices, "") }
+      }
+      AnyTypeEnum::StructType(struct_type) => {
+        let members = self.get_members(struct_type);
+        match members
+          .union_types
+          .as_ref()
+          .map(|mem_types| mem_types[index])
+        {
+          // every member of a union is stored at the address of the union itself
+          Some(mem_type) => {
+            let ptr_type = mem_type.ptr_type(AddressSpace::default());
+            self
+              .builder
+              .build_bitcast(ptr, ptr_type, "")
+              .into_pointer_value()
+          }
+          None => self
+            .builder
+            .build_struct_gep(ptr, index.try_into().unwrap(), "")
+            .unwrap(),
+        }
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  /// Returns the constant of type `ty` a global variable is initialized with.
+  fn gen_const_init(
+    &mut self,
+    ty: BasicTypeEnum<'ctx>,
+    init: Init,
+    span: Span,
+  ) -> Expected<BasicValueEnum<'ctx>> {
+    match (init, ty) {
+      (Init::Zero, ty) => Ok(ty.const_zero()),
+      (Init::Expr(expr), ty) => {
+        let expr_span = expr.span;
+        let value = self.gen_expr(expr)?;
+        if value.as_instruction_value().is_some() {
+          return err!(expr_span, "initializer element is not constant");
+        }
+        Ok(self.convert_void_pointer(value, ty))
+      }
+      (Init::List(inits), BasicTypeEnum::ArrayType(array_type)) => {
+        let elem_type = array_type.get_element_type();
+        let values = inits
+          .into_iter()
+          .map(|init| self.gen_const_init(elem_type, init, span))
+          .collect::<Result<Vec<_>, _>>()?;
+        Ok(const_array(elem_type, &values))
+      }
+      (Init::List(inits), BasicTypeEnum::StructType(struct_type)) => {
+        let members = self.get_members(struct_type);
+        let union_types = members.union_types.clone();
+        let names = members.names.clone();
+        let field_types = struct_type.get_field_types();
+        let values = if let Some(mem_types) = union_types {
+          match inits
+            .into_iter()
+            .enumerate()
+            .find(|(_, init)| !matches!(init, Init::Zero))
+          {
+            None => return Ok(ty.const_zero()),
+            // a constant of a union is one of the member it is lowered to, followed by padding
+            Some((index, init)) if field_types.first() == Some(&mem_types[index]) => {
+              let mut values = vec![self.gen_const_init(mem_types[index], init, span)?];
+              values.extend(field_types[1..].iter().map(|ty| ty.const_zero()));
+              values
+            }
+            Some((index, _)) => {
+              return err!(
+                span,
+                "initializing member `{}` of a global union is not supported",
+                names[index]
+              )
+            }
+          }
+        } else {
+          std::iter::zip(inits, field_types)
+            .map(|(init, ty)| self.gen_const_init(ty, init, span))
+            .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok(
+          struct_type
+            .const_named_struct(&values)
+            .as_basic_value_enum(),
+        )
+      }
+      (Init::List(_), ty) => err!(
+        span,
+        "initializer list for scalar of type `{}`",
+        type_name(ty)
+      ),
+    }
+  }
+
+  fn get_members(&self, struct_type: StructType<'ctx>) -> &Members<'ctx> {
+    let struct_name = struct_type.get_name().unwrap().to_str().unwrap();
+    &self.struct_members[struct_name]
+  }
+
+  /// Records the members of a struct or union lowered to `struct_type`, whose name is unique in
+  /// the context even if its tag is reused in another scope.
+  fn insert_members(&mut self, struct_type: StructType<'ctx>, members: Members<'ctx>) {
     let struct_name = struct_type.get_name().unwrap().to_str().unwrap();
-    let v = self.tag_scope.get_all(struct_name).unwrap();
-    v.iter()
-      .position(|name| name == mem)
-      .map(|index| index.try_into().unwrap())
+    self.struct_members.insert(struct_name.to_string(), members);
+  }
+
+  /// Converts a pointer to or from `void *` into a pointer of type `ty`, as `void *` is lowered
+  /// to `i8*` and needs a bitcast. Other values are returned as they are.
+  fn convert_void_pointer(
+    &self,
+    value: BasicValueEnum<'ctx>,
+    ty: BasicTypeEnum<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    let void_ptr_type = self
+      .context
+      .i8_type()
+      .ptr_type(AddressSpace::default())
+      .as_basic_type_enum();
+    if value.is_pointer_value()
+      && ty.is_pointer_type()
+      && (value.get_type() == void_ptr_type || ty == void_ptr_type)
+    {
+      self.builder.build_bitcast(value, ty, "")
+    } else {
+      value
+    }
+  }
+
+  /// Generates the assignment of `m` to the lvalue `n`, which is evaluated once. `m` refers to
+  /// the value of `n` before the assignment by `AssignedValue`. Returns the address of `n` and
+  /// its old value.
+  fn gen_op_assign(
+    &mut self,
+    n: AST,
+    m: AST,
+    span: Span,
+  ) -> Expected<(PointerValue<'ctx>, BasicValueEnum<'ctx>)> {
+    let lhs = self.gen_addr(n)?;
+    let old = self.builder.build_load(lhs, "");
+    self.assigned_values.push(old);
+    let rhs = self.gen_expr(m);
+    self.assigned_values.pop();
+    self.gen_assign_impl(lhs, rhs?).map_err(|e| e.or_at(span))?;
+    Ok((lhs, old))
   }
 
   fn gen_assign_impl(
     &mut self,
     lhs: PointerValue<'ctx>,
-    rhs: BasicValueEnum<'ctx>,
+    mut rhs: BasicValueEnum<'ctx>,
   ) -> Expected<PointerValue<'ctx>> {
+    if let Ok(ty) = BasicTypeEnum::try_from(lhs.get_type().get_element_type()) {
+      rhs = self.convert_void_pointer(rhs, ty);
+    }
     if lhs.get_type().get_element_type() == rhs.get_type().as_any_type_enum() {
       self.builder.build_store(lhs, rhs);
       Ok(lhs)
     } else {
-      err!("inconsistent types in operands of assignment")
+      err!(
+        "inconsistent types in operands of assignment, `{}` and `{}`",
+        type_name(lhs.get_type().get_element_type()),
+        type_name(rhs.get_type())
+      )
     }
   }
 }
+
+/// Returns a constant array of `values`, which are constants of type `elem_type`.
+fn const_array<'ctx>(
+  elem_type: BasicTypeEnum<'ctx>,
+  values: &[BasicValueEnum<'ctx>],
+) -> BasicValueEnum<'ctx> {
+  let res = match elem_type {
+    BasicTypeEnum::ArrayType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_array_value()).collect();
+      ty.const_array(&values)
+    }
+    BasicTypeEnum::FloatType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_float_value()).collect();
+      ty.const_array(&values)
+    }
+    BasicTypeEnum::IntType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_int_value()).collect();
+      ty.const_array(&values)
+    }
+    BasicTypeEnum::PointerType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_pointer_value()).collect();
+      ty.const_array(&values)
+    }
+    BasicTypeEnum::StructType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_struct_value()).collect();
+      ty.const_array(&values)
+    }
+    BasicTypeEnum::VectorType(ty) => {
+      let values: Vec<_> = values.iter().map(|v| v.into_vector_value()).collect();
+      ty.const_array(&values)
+    }
+  };
+  res.as_basic_value_enum()
+}
+
+fn type_name<'ctx, T: AnyType<'ctx>>(ty: T) -> String {
+  ty.print_to_string().to_string()
+}
+
+/// Returns `name`, followed by the smallest numeric suffix needed if `is_taken` rejects it.
+fn unique_name(name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+  let mut unique = name.to_string();
+  let mut n = 0;
+  while is_taken(&unique) {
+    n += 1;
+    unique = format!("{}.{}", name, n);
+  }
+  unique
+}
+
+/// Returns whether a global variable declared of type `prev_type` may be redeclared of type
+/// `var_type`. An array of unknown size, which is declared as an array of length 0, may be
+/// redeclared with any size.
+fn is_compatible_global<'ctx>(prev_type: AnyTypeEnum<'ctx>, var_type: AnyTypeEnum<'ctx>) -> bool {
+  match (prev_type, var_type) {
+    (AnyTypeEnum::ArrayType(prev), AnyTypeEnum::ArrayType(var))
+      if prev.len() == 0 || var.len() == 0 =>
+    {
+      prev.get_element_type() == var.get_element_type()
+    }
+    (prev_type, var_type) => prev_type == var_type,
+  }
+}
+
+/// Returns the name of the LLVM struct type the union tagged `name` is lowered to, which keeps
+/// it apart from a struct of the same name.
+fn union_type_name(name: &str) -> String {
+  format!("union.{}", name)
+}
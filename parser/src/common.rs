@@ -1,3 +1,5 @@
+use crate::diagnostic::Diagnostic;
+use crate::ty::Type;
 use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
@@ -5,21 +7,41 @@ use std::ops;
 
 #[cfg(debug_assertions)]
 #[macro_export]
-macro_rules! err {
-  ($x:expr) => {
-    Err(concat!($x, " [", file!(), ":", line!(), "]"))
+macro_rules! diag {
+  ($fmt:literal $(, $arg:expr)* $(,)?) => {
+    $crate::diagnostic::Diagnostic::error(format!(
+      concat!($fmt, " [", file!(), ":", line!(), "]"),
+      $($arg),*
+    ))
+  };
+  ($span:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+    $crate::diagnostic::Diagnostic::error(format!(
+      concat!($fmt, " [", file!(), ":", line!(), "]"),
+      $($arg),*
+    ))
+    .at($span)
   };
 }
 
 #[cfg(not(debug_assertions))]
+#[macro_export]
+macro_rules! diag {
+  ($fmt:literal $(, $arg:expr)* $(,)?) => {
+    $crate::diagnostic::Diagnostic::error(format!($fmt, $($arg),*))
+  };
+  ($span:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+    $crate::diagnostic::Diagnostic::error(format!($fmt, $($arg),*)).at($span)
+  };
+}
+
 #[macro_export]
 macro_rules! err {
-  ($x:expr) => {
-    Err($x)
+  ($($arg:tt)*) => {
+    Err($crate::diag!($($arg)*))
   };
 }
 
-pub type Expected<T> = Result<T, &'static str>;
+pub type Expected<T> = Result<T, Diagnostic>;
 
 // ----- StringRef -----
 
@@ -140,8 +162,25 @@ where
 
 // ----- Scope -----
 
+/// An entry in the namespace of ordinary identifiers. Variables, typedef names and enumeration
+/// constants share this namespace, so that a declaration of one hides an outer declaration of
+/// another.
+#[derive(Clone, Debug)]
+pub enum Ordinary<V> {
+  Var(V),
+  Typedef(Type),
+  Enumerator(i64),
+}
+
+#[derive(Clone)]
 pub struct Scope<V> {
-  vars: Vec<HashMap<String, V>>,
+  vars: Vec<HashMap<String, Ordinary<V>>>,
+}
+
+impl<V> Default for Scope<V> {
+  fn default() -> Scope<V> {
+    Scope::new()
+  }
 }
 
 impl<V> Scope<V> {
@@ -158,21 +197,59 @@ impl<V> Scope<V> {
     self.vars.pop();
   }
 
-  pub fn insert(&mut self, k: String, v: V) -> Option<V> {
-    self.vars.last_mut().unwrap().insert(k, v)
+  pub fn insert(&mut self, k: String, v: V) -> Option<Ordinary<V>> {
+    self.vars.last_mut().unwrap().insert(k, Ordinary::Var(v))
+  }
+
+  pub fn insert_typedef(&mut self, k: String, ty: Type) -> Option<Ordinary<V>> {
+    self
+      .vars
+      .last_mut()
+      .unwrap()
+      .insert(k, Ordinary::Typedef(ty))
+  }
+
+  pub fn insert_enumerator(&mut self, k: String, n: i64) -> Option<Ordinary<V>> {
+    self
+      .vars
+      .last_mut()
+      .unwrap()
+      .insert(k, Ordinary::Enumerator(n))
   }
 
+  /// Looks up a variable in the innermost scope.
   pub fn get(&self, k: &str) -> Option<&V> {
-    self.vars.last().unwrap().get(k)
+    match self.vars.last().unwrap().get(k) {
+      Some(Ordinary::Var(v)) => Some(v),
+      _ => None,
+    }
   }
 
+  /// Looks up a variable, unless another ordinary identifier hides it.
   pub fn get_all(&self, k: &str) -> Option<&V> {
-    for vars in self.vars.iter().rev() {
-      let var = vars.get(k);
-      if var.is_some() {
-        return var;
-      }
+    match self.lookup(k) {
+      Some(Ordinary::Var(v)) => Some(v),
+      _ => None,
     }
-    None
+  }
+
+  /// Looks up a typedef name, unless another ordinary identifier hides it.
+  pub fn get_typedef(&self, k: &str) -> Option<&Type> {
+    match self.lookup(k) {
+      Some(Ordinary::Typedef(ty)) => Some(ty),
+      _ => None,
+    }
+  }
+
+  /// Looks up the value of an enumeration constant, unless another ordinary identifier hides it.
+  pub fn get_enumerator(&self, k: &str) -> Option<i64> {
+    match self.lookup(k) {
+      Some(&Ordinary::Enumerator(n)) => Some(n),
+      _ => None,
+    }
+  }
+
+  fn lookup(&self, k: &str) -> Option<&Ordinary<V>> {
+    self.vars.iter().rev().find_map(|vars| vars.get(k))
   }
 }
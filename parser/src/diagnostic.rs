@@ -0,0 +1,124 @@
+use crate::source::Span;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning,
+  Note,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+      Severity::Note => write!(f, "note"),
+    }
+  }
+}
+
+/// A span annotated with a message. The primary label points at the cause of the
+/// diagnostic, secondary labels point at related code such as a previous declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+  pub span: Span,
+  pub message: String,
+  pub primary: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub message: String,
+  pub labels: Vec<Label>,
+  pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+  pub fn new(severity: Severity, message: String) -> Diagnostic {
+    Diagnostic {
+      severity,
+      message,
+      labels: Vec::new(),
+      notes: Vec::new(),
+    }
+  }
+
+  pub fn error<S: Into<String>>(message: S) -> Diagnostic {
+    Diagnostic::new(Severity::Error, message.into())
+  }
+
+  pub fn warning<S: Into<String>>(message: S) -> Diagnostic {
+    Diagnostic::new(Severity::Warning, message.into())
+  }
+
+  /// Returns the span of the primary label.
+  pub fn span(&self) -> Option<Span> {
+    self
+      .labels
+      .iter()
+      .find(|label| label.primary)
+      .map(|label| label.span)
+  }
+
+  /// Points the diagnostic at `span`, replacing the previous primary label.
+  pub fn at(self, span: Span) -> Diagnostic {
+    self.with_primary(span, "")
+  }
+
+  /// Points the diagnostic at `span` unless it already points somewhere more precise.
+  pub fn or_at(self, span: Span) -> Diagnostic {
+    if self.span().is_some() {
+      self
+    } else {
+      self.at(span)
+    }
+  }
+
+  pub fn with_primary<S: Into<String>>(mut self, span: Span, message: S) -> Diagnostic {
+    self.labels.retain(|label| !label.primary);
+    self.labels.insert(
+      0,
+      Label {
+        span,
+        message: message.into(),
+        primary: true,
+      },
+    );
+    self
+  }
+
+  pub fn with_label<S: Into<String>>(mut self, span: Span, message: S) -> Diagnostic {
+    self.labels.push(Label {
+      span,
+      message: message.into(),
+      primary: false,
+    });
+    self
+  }
+
+  pub fn with_note<S: Into<String>>(mut self, note: S) -> Diagnostic {
+    self.notes.push(note.into());
+    self
+  }
+}
+
+/// Lets `?` turn a single diagnostic into the list of diagnostics of a whole run.
+impl From<Diagnostic> for Vec<Diagnostic> {
+  fn from(diag: Diagnostic) -> Vec<Diagnostic> {
+    vec![diag]
+  }
+}
+
+#[test]
+fn test_diagnostic() {
+  let span1 = Span::new(0, 0, 3);
+  let span2 = Span::new(0, 4, 8);
+  let diag = Diagnostic::error("oops").or_at(span1).or_at(span2);
+  assert_eq!(diag.span(), Some(span1));
+  let diag = diag.with_label(span2, "here").at(span2);
+  assert_eq!(diag.span(), Some(span2));
+  assert_eq!(diag.labels.len(), 2);
+  assert!(!diag.labels[1].primary);
+}
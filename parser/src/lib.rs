@@ -1,4 +1,7 @@
 pub mod common;
+pub mod diagnostic;
 pub mod parse;
+pub mod preprocess;
+pub mod source;
 pub mod tokenize;
 pub mod ty;
@@ -1,32 +1,114 @@
-use crate::tokenize::{Token, Tokenizer};
-use crate::ty::Type;
-use crate::{common::Expected, err};
+use crate::common::Scope;
+use crate::diagnostic::Diagnostic;
+use crate::source::Span;
+use crate::tokenize::{Position, Token, TokenKind, Tokenizer};
+use crate::ty::{struct_layout, union_layout, Layout, Type};
+use crate::{common::Expected, diag, err};
 
 #[derive(Clone, Debug)]
-pub enum TopLevel {
-  FunDecl(Type, String, Vec<Type>),
-  FunDef(Type, String, Vec<Type>, Vec<String>, Vec<Stmt>),
-  VarDef(Type, String, Option<AST>),
+pub struct TopLevel {
+  pub kind: TopLevelKind,
+  pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub enum TopLevelKind {
+  /// A function declaration, which is variadic if the field following the parameter types is
+  /// `true`.
+  FunDecl(Type, String, Vec<Type>, bool, StorageClass),
+  /// A function definition, which is variadic if the field following the parameter names is
+  /// `true`.
+  FunDef(
+    Type,
+    String,
+    Vec<Type>,
+    Vec<String>,
+    bool,
+    Vec<Stmt>,
+    StorageClass,
+  ),
+  VarDef(Type, String, Option<Init>, StorageClass),
   StructDef(Type),
 }
 
+/// Storage class specifier of a declaration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageClass {
+  /// No storage class is specified. Functions and global variables have external linkage, and
+  /// local variables live until their block is left.
+  Default,
+  /// `static` gives functions and global variables internal linkage, and makes local variables
+  /// live throughout the program.
+  Static,
+  /// `extern` declares a function or variable which may be defined in another translation
+  /// unit.
+  Extern,
+}
+
 #[derive(Clone, Debug)]
-pub enum Stmt {
-  VarDef(Vec<(Type, String, Option<AST>)>),
+pub struct Stmt {
+  pub kind: StmtKind,
+  pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub enum StmtKind {
+  VarDef(Vec<(Type, String, Option<Init>)>, StorageClass),
   StructDef(Type),
   IfElse(AST, Box<Stmt>, Option<Box<Stmt>>),
-  For(Option<AST>, Option<AST>, Option<AST>, Box<Stmt>),
+  For(
+    Option<Box<AST>>,
+    Option<Box<AST>>,
+    Option<Box<AST>>,
+    Box<Stmt>,
+  ),
+  DoWhile(Box<Stmt>, AST),
+  Switch(AST, Box<Stmt>),
+  Case(i64, Box<Stmt>),
+  Default(Box<Stmt>),
   Break,
   Cont,
-  Return(AST),
+  Goto(String),
+  Label(String, Box<Stmt>),
+  Return(Option<AST>),
   Block(Vec<Stmt>),
   Expr(AST),
 }
 
+/// An initializer of a variable, laid out by the type of the variable.
 #[derive(Clone, Debug)]
-pub enum AST {
+pub enum Init {
+  /// Fills the object with zeros.
+  Zero,
+  /// Initializes a scalar, or copies a struct or union, with an expression converted to its
+  /// type.
+  Expr(AST),
+  /// Initializes each element of an array or member of a struct. Only one member of a union is
+  /// initialized while the others are `Zero`.
+  List(Vec<Init>),
+}
+
+#[derive(Clone, Debug)]
+pub struct AST {
+  pub kind: ASTKind,
+  pub span: Span,
+  /// Type of the expression. The implicit conversions of its operands are made explicit as
+  /// casts, so that operands of arithmetic operators always have the same type.
+  pub ty: Type,
+}
+
+#[derive(Clone, Debug)]
+pub enum ASTKind {
   Ternary(Box<AST>, Box<AST>, Box<AST>),
   Assign(Box<AST>, Box<AST>),
+  /// `n op= m`, which evaluates the lvalue `n` once and assigns the rhs to it. The rhs refers
+  /// to the value of `n` before the assignment by `AssignedValue`.
+  CompoundAssign(Box<AST>, Box<AST>),
+  /// `n++` or `n--`, which is like `CompoundAssign` but yields the value of `n` before the
+  /// assignment.
+  PostfixAssign(Box<AST>, Box<AST>),
+  LogOr(Box<AST>, Box<AST>),
+  LogAnd(Box<AST>, Box<AST>),
   Eq(Box<AST>, Box<AST>),
   Ne(Box<AST>, Box<AST>),
   Lt(Box<AST>, Box<AST>),
@@ -35,30 +117,192 @@ pub enum AST {
   Sub(Box<AST>, Box<AST>),
   Mul(Box<AST>, Box<AST>),
   Div(Box<AST>, Box<AST>),
+  Mod(Box<AST>, Box<AST>),
+  BitAnd(Box<AST>, Box<AST>),
+  BitOr(Box<AST>, Box<AST>),
+  BitXor(Box<AST>, Box<AST>),
+  Shl(Box<AST>, Box<AST>),
+  Shr(Box<AST>, Box<AST>),
   // vvv unary
+  Not(Box<AST>),
+  BitNot(Box<AST>),
+  /// `-n`, which negates a floating value without subtracting it from zero so that the sign
+  /// of a zero is flipped.
+  Neg(Box<AST>),
   Addr(Box<AST>),
   Deref(Box<AST>),
   Cast(Type, Box<AST>),
+  SizeOf(Type),
+  AlignOf(Type),
   // vvv postfix
   Dot(Box<AST>, String),
   // vvv primary
   Block(Vec<Stmt>),
-  Call(String, Vec<AST>),
+  /// A call of a function or a pointer to function, which the callee is converted to.
+  Call(Box<AST>, Vec<AST>),
+  /// `__builtin_va_start(ap, last)`, which initializes the `va_list` `ap` of a variadic function.
+  VaStart(Box<AST>),
+  /// `__builtin_va_arg(ap, ty)`, which takes the next variadic argument of type `ty` from `ap`.
+  VaArg(Box<AST>, Type),
+  /// `__builtin_va_end(ap)`.
+  VaEnd(Box<AST>),
   Ident(String),
-  Num(i64),
-  Str(String),
+  /// The value of type `ty` of the lhs of the enclosing `CompoundAssign` or `PostfixAssign`
+  /// before the assignment.
+  AssignedValue(Type),
+  Enumerator(String, i64),
+  /// An integer constant and its type. Unsigned values are stored as their bit pattern.
+  Num(i64, Type),
+  /// A floating constant of type `float` or `double`.
+  Float(f64, Type),
+  Str(Vec<u8>),
+}
+
+impl TopLevel {
+  pub fn new(kind: TopLevelKind, span: Span) -> TopLevel {
+    TopLevel { kind, span }
+  }
+}
+
+impl Stmt {
+  pub fn new(kind: StmtKind, span: Span) -> Stmt {
+    Stmt { kind, span }
+  }
+
+  /// Returns whether the statement is labeled, so that it can be reached by a jump even if it
+  /// follows a statement that never completes.
+  pub fn is_labeled(&self) -> bool {
+    matches!(
+      self.kind,
+      StmtKind::Case(..) | StmtKind::Default(_) | StmtKind::Label(..)
+    )
+  }
+
+  /// Returns whether the statement is or contains a labeled statement, so that it may be
+  /// reached by a jump even if it follows a statement that never completes.
+  pub fn contains_label(&self) -> bool {
+    match &self.kind {
+      StmtKind::Case(..) | StmtKind::Default(_) | StmtKind::Label(..) => true,
+      StmtKind::IfElse(_, then, else_) => {
+        then.contains_label() || else_.as_ref().is_some_and(|else_| else_.contains_label())
+      }
+      StmtKind::For(.., body) | StmtKind::DoWhile(body, _) | StmtKind::Switch(_, body) => {
+        body.contains_label()
+      }
+      StmtKind::Block(stmts) => stmts.iter().any(Stmt::contains_label),
+      _ => false,
+    }
+  }
 }
 
-fn consume_eof(it: &mut Tokenizer) -> Expected<bool> {
-  if it.current()? == Token::Eof {
+impl AST {
+  pub fn new(kind: ASTKind, span: Span, ty: Type) -> AST {
+    AST { kind, span, ty }
+  }
+}
+
+/// The state of the parser: a cursor over the tokens, and what is known about the
+/// declarations parsed so far.
+pub struct Parser {
+  tokens: Tokenizer,
+  diags: Vec<Diagnostic>,
+  /// Ordinary identifiers declared so far, which tell typedef names from other identifiers
+  /// and give the types of variables.
+  scope: Scope<Type>,
+  /// Definitions of the structs and unions declared so far, by tag.
+  tags: Scope<Type>,
+  /// Labels of the enclosing switch statements, innermost last. A `default` label has no value.
+  switches: Vec<Vec<(Option<i64>, Span)>>,
+  /// Return type of the function being parsed, which return values are converted to.
+  ret_ty: Option<Type>,
+}
+
+/// A saved state of the parser to backtrack to. Declarations are not saved, so backtracking
+/// is cheap but only fit for parsing the same tokens again.
+#[derive(Clone, Copy)]
+struct Checkpoint {
+  pos: Position,
+  diags: usize,
+}
+
+impl Parser {
+  pub fn new(mut tokens: Tokenizer) -> Parser {
+    let diags = tokens.take_diagnostics();
+    let mut scope = Scope::new();
+    let mut tags = Scope::new();
+    scope.push();
+    tags.push();
+    // `va_list` of x86-64 is a 24-byte `__va_list_tag[1]`, which is only accessed through the
+    // `va_*` builtins
+    scope.insert_typedef(
+      "__builtin_va_list".to_string(),
+      Type::Array(Box::new(Type::ULong), Some(3)),
+    );
+    Parser {
+      tokens,
+      diags,
+      scope,
+      tags,
+      switches: Vec::new(),
+      ret_ty: None,
+    }
+  }
+
+  fn current(&mut self) -> Expected<Token> {
+    self.tokens.current()
+  }
+
+  fn advance(&mut self) {
+    self.tokens.advance()
+  }
+
+  fn peek(&self, n: usize) -> Expected<Token> {
+    self.tokens.peek(n)
+  }
+
+  fn span(&self) -> Span {
+    self.tokens.span()
+  }
+
+  fn prev_span(&self) -> Span {
+    self.tokens.prev_span()
+  }
+
+  fn checkpoint(&self) -> Checkpoint {
+    Checkpoint {
+      pos: self.tokens.position(),
+      diags: self.diags.len(),
+    }
+  }
+
+  /// Goes back to `checkpoint`, discarding the diagnostics reported since.
+  fn backtrack(&mut self, checkpoint: Checkpoint) {
+    self.tokens.rewind(checkpoint.pos);
+    self.diags.truncate(checkpoint.diags);
+  }
+
+  /// Opens a block scope for ordinary identifiers and tags.
+  fn push_scope(&mut self) {
+    self.scope.push();
+    self.tags.push();
+  }
+
+  fn pop_scope(&mut self) {
+    self.scope.pop();
+    self.tags.pop();
+  }
+}
+
+fn consume_eof(it: &mut Parser) -> Expected<bool> {
+  if it.current()?.kind == TokenKind::Eof {
     Ok(true)
   } else {
     Ok(false)
   }
 }
 
-fn consume_keyword(it: &mut Tokenizer, keyword: &str) -> Expected<bool> {
-  if it.current()? == Token::Keyword(keyword) {
+fn consume_keyword(it: &mut Parser, keyword: &'static str) -> Expected<bool> {
+  if it.current()?.kind == TokenKind::Keyword(keyword) {
     it.advance();
     Ok(true)
   } else {
@@ -66,8 +310,8 @@ fn consume_keyword(it: &mut Tokenizer, keyword: &str) -> Expected<bool> {
   }
 }
 
-fn consume_ident(it: &mut Tokenizer) -> Expected<Option<String>> {
-  if let Token::Ident(name) = it.current()? {
+fn consume_ident(it: &mut Parser) -> Expected<Option<String>> {
+  if let TokenKind::Ident(name) = it.current()?.kind {
     it.advance();
     Ok(Some(name))
   } else {
@@ -75,17 +319,26 @@ fn consume_ident(it: &mut Tokenizer) -> Expected<Option<String>> {
   }
 }
 
-fn consume_num(it: &mut Tokenizer) -> Expected<Option<i64>> {
-  if let Token::Num(n) = it.current()? {
+fn consume_num(it: &mut Parser) -> Expected<Option<(i64, Type)>> {
+  if let TokenKind::Num(n, ty) = it.current()?.kind {
     it.advance();
-    Ok(Some(n))
+    Ok(Some((n, ty)))
   } else {
     Ok(None)
   }
 }
 
-fn consume_str(it: &mut Tokenizer) -> Expected<Option<String>> {
-  if let Token::Str(s) = it.current()? {
+fn consume_float(it: &mut Parser) -> Expected<Option<(f64, Type)>> {
+  if let TokenKind::Float(x, ty) = it.current()?.kind {
+    it.advance();
+    Ok(Some((x, ty)))
+  } else {
+    Ok(None)
+  }
+}
+
+fn consume_str(it: &mut Parser) -> Expected<Option<Vec<u8>>> {
+  if let TokenKind::Str(s) = it.current()?.kind {
     it.advance();
     Ok(Some(s))
   } else {
@@ -93,8 +346,8 @@ fn consume_str(it: &mut Tokenizer) -> Expected<Option<String>> {
   }
 }
 
-fn consume(it: &mut Tokenizer, op: &str) -> Expected<bool> {
-  if it.current()? == Token::Punct(op) {
+fn consume(it: &mut Parser, op: &'static str) -> Expected<bool> {
+  if it.current()?.kind == TokenKind::Punct(op) {
     it.advance();
     Ok(true)
   } else {
@@ -102,70 +355,263 @@ fn consume(it: &mut Tokenizer, op: &str) -> Expected<bool> {
   }
 }
 
-fn expect_ident(it: &mut Tokenizer) -> Expected<String> {
-  if let Token::Ident(name) = it.current()? {
+fn expect_ident(it: &mut Parser) -> Expected<String> {
+  let tok = it.current()?;
+  if let TokenKind::Ident(name) = tok.kind {
     it.advance();
     Ok(name)
   } else {
-    err!("unexpected token, expecting identifier")
+    err!(tok.span, "unexpected {}, expecting identifier", tok.kind)
   }
 }
 
-fn expect_num(it: &mut Tokenizer) -> Expected<i64> {
-  if let Token::Num(n) = it.current()? {
+fn expect_keyword(it: &mut Parser, keyword: &'static str) -> Expected<()> {
+  let tok = it.current()?;
+  if tok.kind == TokenKind::Keyword(keyword) {
     it.advance();
-    Ok(n)
+    Ok(())
   } else {
-    err!("unexpected token, expecting number")
+    err!(
+      tok.span,
+      "unexpected {}, expecting keyword `{}`",
+      tok.kind,
+      keyword
+    )
   }
 }
 
-fn expect(it: &mut Tokenizer, op: &str) -> Expected<()> {
-  if it.current()? == Token::Punct(op) {
+fn expect(it: &mut Parser, op: &'static str) -> Expected<()> {
+  let tok = it.current()?;
+  if tok.kind == TokenKind::Punct(op) {
     it.advance();
     Ok(())
   } else {
-    err!("unexpected token, expecting punctuator")
+    err!(tok.span, "unexpected {}, expecting `{}`", tok.kind, op)
   }
 }
 
-fn try_parse<F, T>(it: &mut Tokenizer, f: F) -> Option<T>
-where
-  F: FnOnce(&mut Tokenizer) -> Expected<T>,
-{
-  let backup = it.clone();
-  match f(it) {
-    Ok(value) => Some(value),
-    Err(_) => {
-      *it = backup;
-      None
+fn peek(it: &mut Parser, op: &'static str) -> bool {
+  matches!(it.current(), Ok(tok) if tok.kind == TokenKind::Punct(op))
+}
+
+/// Returns whether `item` starts a declaration specifier, which is either a keyword or a
+/// typedef name not shadowed by an ordinary identifier.
+fn starts_declspec(it: &Parser, item: &Expected<Token>) -> bool {
+  match item {
+    Ok(Token {
+      kind: TokenKind::Keyword("struct" | "union" | "enum" | "typedef" | "static" | "extern"),
+      ..
+    }) => true,
+    item if is_basic_type(item) => true,
+    Ok(Token {
+      kind: TokenKind::Ident(name),
+      ..
+    }) => it.scope.get_typedef(name).is_some(),
+    _ => false,
+  }
+}
+
+/// Returns whether the current token starts a declaration specifier.
+fn is_declspec(it: &mut Parser) -> bool {
+  let item = it.current();
+  starts_declspec(it, &item)
+}
+
+/// Looks ahead to check whether a cast follows, without consuming any token.
+fn is_cast(it: &mut Parser) -> bool {
+  peek(it, "(") && starts_declspec(it, &it.peek(1))
+}
+
+/// Reports `diag`, pointing at the macro definition if the offending token comes from one.
+fn report(it: &mut Parser, diag: Diagnostic) {
+  let diag = match it.current() {
+    Ok(Token {
+      span,
+      expansion: Some(expansion),
+      ..
+    }) if diag.span() == Some(span) => diag.with_label(
+      expansion.def,
+      format!("in expansion of macro `{}`", expansion.name),
+    ),
+    _ => diag,
+  };
+  it.diags.push(diag);
+}
+
+/// Skips tokens after a syntax error until parsing can resume: just past a `;` or a
+/// brace-enclosed block, or before an unmatched `}`. At the top level, where `toplevel` is
+/// the start of the failed declaration, an unmatched `}` is skipped and a later declaration
+/// specifier starts a new declaration.
+fn synchronize(it: &mut Parser, toplevel: Option<Span>) {
+  let mut depth = 0;
+  loop {
+    let kind = match it.current() {
+      Ok(tok) => tok.kind,
+      Err(_) => {
+        it.advance();
+        continue;
+      }
+    };
+    match kind {
+      TokenKind::Eof => return,
+      TokenKind::Punct(";") if depth == 0 => {
+        it.advance();
+        return;
+      }
+      TokenKind::Punct("{") => depth += 1,
+      TokenKind::Punct("}") if depth == 0 => {
+        if toplevel.is_none() {
+          return;
+        }
+      }
+      TokenKind::Punct("}") => {
+        depth -= 1;
+        if depth == 0 {
+          it.advance();
+          return;
+        }
+      }
+      _ => {
+        if let Some(start) = toplevel {
+          if depth == 0 && it.span().start > start.start && is_declspec(it) {
+            return;
+          }
+        }
+      }
     }
+    it.advance();
+  }
+}
+
+/// Returns the span from `start` to the end of the most recently consumed token.
+fn span_from(it: &Parser, start: Span) -> Span {
+  start.to(it.prev_span())
+}
+
+fn binary(it: &Parser, kind: fn(Box<AST>, Box<AST>) -> ASTKind, n: AST, m: AST) -> AST {
+  let span = n.span.to(m.span);
+  let kind = convert_operands(kind(Box::new(n), Box::new(m)));
+  new_ast(it, kind, span)
+}
+
+/// Creates a node of `kind`, whose type is determined from the types of its operands. If the
+/// type cannot be determined, e.g. for an undeclared identifier, the node is typed as `int` and
+/// the error is left to code generation.
+fn new_ast(it: &Parser, kind: ASTKind, span: Span) -> AST {
+  let ty = type_of(it, &kind, span).unwrap_or(Type::Int);
+  AST::new(kind, span, ty)
+}
+
+/// Converts `n` to `ty` by an implicit cast if both are of arithmetic types.
+fn convert(n: AST, ty: &Type) -> AST {
+  if n.ty != *ty && n.ty.is_arithmetic() && ty.is_arithmetic() {
+    let span = n.span;
+    AST::new(ASTKind::Cast(ty.clone(), Box::new(n)), span, ty.clone())
+  } else {
+    n
+  }
+}
+
+/// Applies the integer promotions to `n`.
+fn promote(n: AST) -> AST {
+  let ty = n.ty.promote();
+  convert(n, &ty)
+}
+
+/// Applies the default argument promotions to `n`, which are the integer promotions and the
+/// conversion of `float` to `double`.
+fn promote_arg(n: AST) -> AST {
+  if n.ty == Type::Float {
+    convert(n, &Type::Double)
+  } else {
+    promote(n)
+  }
+}
+
+/// Converts the operands `n` and `m` to their common type by the usual arithmetic conversions.
+/// In pointer arithmetic, the integer operand is converted to `long`, which has the width of a
+/// pointer, and other operands that are not arithmetic are only promoted.
+fn arith_operands(kind: fn(Box<AST>, Box<AST>) -> ASTKind, n: AST, m: AST) -> ASTKind {
+  let is_pointer = |n: &AST| matches!(decay(n.ty.clone()), Type::Pointer(_));
+  let (n, m) = match Type::common_type(&n.ty, &m.ty) {
+    Some(ty) => (convert(n, &ty), convert(m, &ty)),
+    None if is_pointer(&n) && m.ty.is_integer() => (n, convert(m, &Type::Long)),
+    None if is_pointer(&m) && n.ty.is_integer() => (convert(n, &Type::Long), m),
+    None => (promote(n), promote(m)),
+  };
+  kind(Box::new(n), Box::new(m))
+}
+
+/// Makes the implicit conversions of the operands of a binary operator explicit.
+fn convert_operands(kind: ASTKind) -> ASTKind {
+  match kind {
+    ASTKind::Assign(n, m) => {
+      let m = convert(*m, &n.ty);
+      ASTKind::Assign(n, Box::new(m))
+    }
+    ASTKind::Eq(n, m) => arith_operands(ASTKind::Eq, *n, *m),
+    ASTKind::Ne(n, m) => arith_operands(ASTKind::Ne, *n, *m),
+    ASTKind::Lt(n, m) => arith_operands(ASTKind::Lt, *n, *m),
+    ASTKind::Le(n, m) => arith_operands(ASTKind::Le, *n, *m),
+    ASTKind::Add(n, m) => arith_operands(ASTKind::Add, *n, *m),
+    ASTKind::Sub(n, m) => arith_operands(ASTKind::Sub, *n, *m),
+    ASTKind::Mul(n, m) => arith_operands(ASTKind::Mul, *n, *m),
+    ASTKind::Div(n, m) => arith_operands(ASTKind::Div, *n, *m),
+    ASTKind::Mod(n, m) => arith_operands(ASTKind::Mod, *n, *m),
+    ASTKind::BitAnd(n, m) => arith_operands(ASTKind::BitAnd, *n, *m),
+    ASTKind::BitOr(n, m) => arith_operands(ASTKind::BitOr, *n, *m),
+    ASTKind::BitXor(n, m) => arith_operands(ASTKind::BitXor, *n, *m),
+    // the operands of a shift are promoted separately
+    ASTKind::Shl(n, m) => ASTKind::Shl(Box::new(promote(*n)), Box::new(promote(*m))),
+    ASTKind::Shr(n, m) => ASTKind::Shr(Box::new(promote(*n)), Box::new(promote(*m))),
+    kind => kind,
   }
 }
 
 //' program     = toplevel* eof
-//' toplevel    = declspec fun_body
-//'             | declspec decllist
-//' fun_body    = declarator "{" compound_stmt
+//' toplevel    = "typedef" declspec typedef
+//'             | storage? declspec declarator fun_body
+//'             | storage? declspec decllist
+//' storage     = "static" | "extern"
+//' typedef     = declarator ("," declarator)* ";"
+//' fun_body    = "{" compound_stmt
 //' decllist    = (declitem ("," declitem)*)? ";"
-//' declitem    = declarator ("=" expr)?
-//' declspec = "char" | "int" | "struct" ident? struct_decl?
+//' declitem    = declarator ("=" initializer)?
+//' initializer = "{" (init_item ("," init_item)* ","?)? "}"
+//'             | str
+//'             | expr
+//' init_item   = (designator+ "=")? initializer
+//' designator  = "[" ternary "]" | "." ident
+//' declspec    = basic_type+ | ("struct" | "union") ident? struct_decl?
+//'             | "enum" ident? enum_decl? | typedef_name
+//' basic_type  = "void" | "_Bool" | "char" | "short" | "int" | "long" | "float" | "double"
+//'             | "signed" | "unsigned"
 //' struct_decl = "{" struct_mem* "}"
 //' struct_mem  = declspec declarator ("," declarator)* ";"
-//' declarator  = "*"* ident type_suffix
-//' type_suffix = "[" num "]"
+//' enum_decl   = "{" enumerator ("," enumerator)* ","? "}"
+//' enumerator  = ident ("=" ternary)?
+//' declarator  = "*"* ("(" declarator ")" | ident) type_suffix
+//' abstract_declarator = "*"* ("(" abstract_declarator ")")? type_suffix
+//' type_suffix = "[" ternary? "]" type_suffix
 //'             | "(" fun_params
 //'             | ε
-//' fun_params  = param (("," param)*)? ")"
-//' param       = declspec declarator
+//' fun_params  = ("void" | param ("," param)* ("," "...")?)? ")"
+//' param       = declspec (declarator | abstract_declarator)
 //'
-//' stmt        = declspec decllist
+//' stmt        = ident ":" stmt
+//'             | "typedef" declspec typedef
+//'             | storage? declspec decllist
 //'             | "if" "(" expr ")" stmt ("else" stmt)?
 //'             | "for" "(" expr? ";" expr? ";" expr? ")" stmt
 //'             | "while" "(" expr ")" stmt
+//'             | "do" stmt "while" "(" expr ")" ";"
+//'             | "switch" "(" expr ")" stmt
+//'             | "case" ternary ":" stmt
+//'             | "default" ":" stmt
 //'             | "break" ";"
 //'             | "continue" ";"
-//'             | "return" expr ";"
+//'             | "goto" ident ";"
+//'             | "return" expr? ";"
 //'             | "{" compound_stmt
 //'             | ";"
 //'             | expr ";"
@@ -173,213 +619,1281 @@ where
 //'
 //' expr        = ternary
 //' ternary     = assign ("?" expr ":" ternary)?
-//' assign      = equality ("=" assign | "+=" assign | "-=" assign | "*=" assign | "/=" assign)?
+type BinaryOp = fn(Box<AST>, Box<AST>) -> ASTKind;
+
+//' assign      = logor (assign_op assign)?
+//' assign_op   = "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>="
+//' logor       = logand ("||" logand)*
+//' logand      = bitor ("&&" bitor)*
+//' bitor       = bitxor ("|" bitxor)*
+//' bitxor      = bitand ("^" bitand)*
+//' bitand      = equality ("&" equality)*
 //' equality    = relational ("==" relational | "!=" relational)*
-//' relational  = add ("<" add | "<=" add | ">" add | ">=" add)*
+//' relational  = shift ("<" shift | "<=" shift | ">" shift | ">=" shift)*
+//' shift       = add ("<<" add | ">>" add)*
 //' add         = mul ("+" mul | "-" mul)*
-//' mul         = unary ("*" unary | "/" unary)*
-//' unary       = ("+" | "-" | "&" | "*" | "++" | "--") unary
+//' mul         = unary ("*" unary | "/" unary | "%" unary)*
+//' unary       = ("+" | "-" | "&" | "*" | "!" | "~" | "++" | "--") unary
+//'             | "sizeof" "(" type_name ")"
+//'             | "sizeof" unary
+//'             | "_Alignof" "(" type_name ")"
 //'             | cast
 //'             | postfix
-//' cast        = "(" declspec ")" unary
-//' postfix     = primary ("[" expr "]" | "++" | "--" | "." ident)*
+//' cast        = "(" type_name ")" unary
+//' type_name   = declspec abstract_declarator
+//' postfix     = primary ("(" fun_args | "[" expr "]" | "++" | "--" | "." ident | "->" ident)*
 //' primary     = "(" "{" compound_stmt ")"
 //'             | "(" expr ")"
-//'             | ident "(" fun_args
+//'             | builtin
 //'             | ident
 //'             | num
-//'             | str
+//'             | str+
 //' fun_args    = (expr ("," expr)*)? ")"
+//' builtin     = "__builtin_va_start" "(" expr "," ident ")"
+//'             | "__builtin_va_arg" "(" expr "," type_name ")"
+//'             | "__builtin_va_end" "(" expr ")"
 
-//' program     = toplevel* eof
-pub fn parse(mut it: Tokenizer) -> Expected<Vec<TopLevel>> {
+/// Parses a translation unit and fails with every syntax error found in it.
+pub fn parse(it: Tokenizer) -> Result<Vec<TopLevel>, Vec<Diagnostic>> {
+  let (toplevels, diags) = parse_with_recovery(it);
+  if diags.is_empty() {
+    Ok(toplevels)
+  } else {
+    Err(diags)
+  }
+}
+
+/// Parses a translation unit, recovering from syntax errors. Returns the top-level
+/// declarations that could be parsed together with the diagnostics reported on the way.
+pub fn parse_with_recovery(tokens: Tokenizer) -> (Vec<TopLevel>, Vec<Diagnostic>) {
+  let mut it = Parser::new(tokens);
   let mut toplevels = Vec::new();
-  while !consume_eof(&mut it)? {
-    let mut toplevel = parse_toplevel(&mut it)?;
-    toplevels.append(&mut toplevel);
+  loop {
+    match consume_eof(&mut it) {
+      Ok(true) => break,
+      Ok(false) => (),
+      Err(e) => {
+        report(&mut it, e);
+        it.advance();
+        continue;
+      }
+    }
+    let start = it.span();
+    match parse_toplevel(&mut it) {
+      Ok(mut toplevel) => toplevels.append(&mut toplevel),
+      Err(e) => {
+        report(&mut it, e);
+        synchronize(&mut it, Some(start));
+      }
+    }
   }
-  Ok(toplevels)
+  (toplevels, it.diags)
 }
 
-//' toplevel    = declspec fun_body
-//'             | declspec decllist
-fn parse_toplevel(it: &mut Tokenizer) -> Expected<Vec<TopLevel>> {
+//' toplevel    = "typedef" declspec typedef
+//'             | storage? declspec declarator fun_body
+//'             | storage? declspec decllist
+fn parse_toplevel(it: &mut Parser) -> Expected<Vec<TopLevel>> {
+  let start = it.span();
+  if consume_keyword(it, "typedef")? {
+    let ty = parse_declspec(it)?;
+    let (def, ty) = split_struct_def(ty);
+    parse_typedef(it, ty)?;
+    let span = span_from(it, start);
+    return Ok(
+      def
+        .map(|def| TopLevel::new(TopLevelKind::StructDef(def), span))
+        .into_iter()
+        .collect(),
+    );
+  }
+  let storage = parse_storage(it)?;
   let ty = parse_declspec(it)?;
-  if let Some(fun_def) = try_parse(it, |it| parse_fun_body(it, ty.clone())) {
-    Ok(vec![fun_def])
+  let decllist = if consume(it, ";")? {
+    Vec::new()
   } else {
-    let decllist = parse_decllist(it, ty.clone())?;
-    if decllist.is_empty() && matches!(ty, Type::Struct(..)) {
-      Ok(vec![TopLevel::StructDef(ty)])
-    } else {
-      let res: Vec<_> = decllist
-        .into_iter()
-        .map(|(ty, name, init)| match ty {
-          Type::FunTy(ret_ty, param_tys, _param_names) => {
-            TopLevel::FunDecl(*ret_ty, name, param_tys)
-          }
-          _ => TopLevel::VarDef(ty, name, init),
-        })
-        .collect();
-      Ok(res)
+    // the first declarator tells a function definition from a declaration
+    let decl_start = it.span();
+    let (decl_ty, name) = parse_declarator(it, ty.clone())?;
+    if matches!(decl_ty, Type::FunTy(..)) && peek(it, "{") {
+      return Ok(vec![parse_fun_body(it, decl_ty, name, storage, start)?]);
     }
+    parse_decllist_rest(it, ty.clone(), (decl_ty, name), decl_start, storage)?
+  };
+  let span = span_from(it, start);
+  if decllist.is_empty() && matches!(ty, Type::Struct(..) | Type::Union(..)) {
+    forward_declare_tag(it, &ty);
+    Ok(vec![TopLevel::new(TopLevelKind::StructDef(ty), span)])
+  } else {
+    let res: Vec<_> = decllist
+      .into_iter()
+      .map(|(ty, name, init)| match ty {
+        Type::FunTy(ret_ty, param_tys, _param_names, variadic) => TopLevel::new(
+          TopLevelKind::FunDecl(*ret_ty, name, param_tys, variadic, storage),
+          span,
+        ),
+        _ => TopLevel::new(TopLevelKind::VarDef(ty, name, init, storage), span),
+      })
+      .collect();
+    Ok(res)
+  }
+}
+
+//' storage     = "static" | "extern"
+fn parse_storage(it: &mut Parser) -> Expected<StorageClass> {
+  if consume_keyword(it, "static")? {
+    Ok(StorageClass::Static)
+  } else if consume_keyword(it, "extern")? {
+    Ok(StorageClass::Extern)
+  } else {
+    Ok(StorageClass::Default)
+  }
+}
+
+/// Separates the definition of a named struct or union from a declaration specifier, so that
+/// it is defined once while the remaining type only refers to it by name.
+fn split_struct_def(ty: Type) -> (Option<Type>, Type) {
+  match ty {
+    Type::Struct(Some(name), Some(mems)) => (
+      Some(Type::Struct(Some(name.clone()), Some(mems))),
+      Type::Struct(Some(name), None),
+    ),
+    Type::Union(Some(name), Some(mems)) => (
+      Some(Type::Union(Some(name.clone()), Some(mems))),
+      Type::Union(Some(name), None),
+    ),
+    ty => (None, ty),
   }
 }
 
-//' fun_body    = declarator "{" compound_stmt
-fn parse_fun_body(it: &mut Tokenizer, ty: Type) -> Expected<TopLevel> {
-  let (ty, name) = parse_declarator(it, ty)?;
-  if let Type::FunTy(ret_ty, param_tys, param_names) = ty {
+//' typedef     = declarator ("," declarator)* ";"
+fn parse_typedef(it: &mut Parser, ty: Type) -> Expected<()> {
+  loop {
+    let (ty, name) = parse_declarator(it, ty.clone())?;
+    it.scope.insert_typedef(name, ty);
+    if consume(it, ";")? {
+      break Ok(());
+    }
+    expect(it, ",")?;
+  }
+}
+
+//' fun_body    = "{" compound_stmt
+/// Parses the body of a function declared by the declarator of type `ty` and name `name`.
+fn parse_fun_body(
+  it: &mut Parser,
+  ty: Type,
+  name: String,
+  storage: StorageClass,
+  start: Span,
+) -> Expected<TopLevel> {
+  it.scope.insert(name.clone(), ty.clone());
+  if let Type::FunTy(ret_ty, param_tys, param_names, variadic) = ty {
+    if param_names.iter().any(String::is_empty) {
+      return err!(
+        span_from(it, start),
+        "parameter name omitted in function definition"
+      );
+    }
     expect(it, "{")?;
-    let body = parse_compound_stmt(it)?;
-    Ok(TopLevel::FunDef(
-      *ret_ty,
-      name,
-      param_tys,
-      param_names,
-      body,
+    it.push_scope();
+    for (param_ty, param_name) in param_tys.iter().zip(&param_names) {
+      it.scope.insert(param_name.clone(), param_ty.clone());
+    }
+    it.ret_ty = Some(*ret_ty.clone());
+    let body = parse_compound_stmt(it);
+    it.ret_ty = None;
+    it.pop_scope();
+    let body = body?;
+    Ok(TopLevel::new(
+      TopLevelKind::FunDef(
+        *ret_ty,
+        name,
+        param_tys,
+        param_names,
+        variadic,
+        body,
+        storage,
+      ),
+      span_from(it, start),
     ))
   } else {
-    err!("declarator is not function")
+    err!(span_from(it, start), "declarator is not function")
+  }
+}
+
+//' decllist    = (declitem ("," declitem)*)? ";"
+fn parse_decllist(
+  it: &mut Parser,
+  ty: Type,
+  storage: StorageClass,
+) -> Expected<Vec<(Type, String, Option<Init>)>> {
+  if consume(it, ";")? {
+    return Ok(Vec::new());
+  }
+  let start = it.span();
+  let decl = parse_declarator(it, ty.clone())?;
+  parse_decllist_rest(it, ty, decl, start, storage)
+}
+
+/// Parses the rest of a non-empty `decllist` whose first declarator `decl` is already parsed
+/// from `start`.
+fn parse_decllist_rest(
+  it: &mut Parser,
+  ty: Type,
+  decl: (Type, String),
+  start: Span,
+  storage: StorageClass,
+) -> Expected<Vec<(Type, String, Option<Init>)>> {
+  let mut decls = vec![parse_declitem(it, decl, start, storage)?];
+  // a named struct or union is defined by the first declarator and referred to by the others
+  let (_, ty) = split_struct_def(ty);
+  while !consume(it, ";")? {
+    expect(it, ",")?;
+    let start = it.span();
+    let decl = parse_declarator(it, ty.clone())?;
+    decls.push(parse_declitem(it, decl, start, storage)?);
+  }
+  Ok(decls)
+}
+
+//' declitem    = declarator ("=" initializer)?
+/// Parses the initializer following the declarator `(ty, name)` parsed from `start`.
+fn parse_declitem(
+  it: &mut Parser,
+  (mut ty, name): (Type, String),
+  start: Span,
+  storage: StorageClass,
+) -> Expected<(Type, String, Option<Init>)> {
+  if ty == Type::Void {
+    return err!(span_from(it, start), "variable `{}` declared void", name);
+  }
+  it.scope.insert(name.clone(), ty.clone());
+  if let Type::FunTy(..) = ty {
+    // parsing function declaration
+    Ok((ty, name, None))
+  } else {
+    // parsing variable definition
+    let init = if consume(it, "=")? {
+      let init = parse_var_init(it, &ty)?;
+      // the size of an array of unknown size is given by the number of its initializers
+      if let (Type::Array(elem_ty, None), Init::List(inits)) = (&ty, &init) {
+        let len = inits
+          .len()
+          .try_into()
+          .map_err(|_| diag!(span_from(it, start), "array `{}` is too large", name))?;
+        ty = Type::Array(elem_ty.clone(), Some(len));
+        it.scope.insert(name.clone(), ty.clone());
+      }
+      Some(init)
+    } else {
+      None
+    };
+    if storage == StorageClass::Extern && init.is_none() {
+      // the object is defined elsewhere, so its type may be incomplete
+      return Ok((ty, name, init));
+    }
+    if let Type::Array(_, None) = ty {
+      return err!(span_from(it, start), "array size missing in `{}`", name);
+    }
+    if matches!(ty, Type::Struct(..) | Type::Union(..)) && members_of(it, &ty).is_none() {
+      return err!(
+        span_from(it, start),
+        "variable `{}` has incomplete type `{}`",
+        name,
+        ty
+      );
+    }
+    Ok((ty, name, init))
+  }
+}
+
+/// Parses the initializer of a variable of type `ty`. Unlike a nested initializer, it is
+/// enclosed in braces unless it is an expression or a string literal.
+fn parse_var_init(it: &mut Parser, ty: &Type) -> Expected<Init> {
+  let mut init = Init::Zero;
+  match ty {
+    _ if peek(it, "{") || is_string_init(it, ty) => parse_initializer(it, ty, &mut init)?,
+    Type::Array(..) => {
+      return err!(
+        it.span(),
+        "array must be initialized with a brace-enclosed initializer or a string literal"
+      )
+    }
+    Type::Struct(..) | Type::Union(..) => init = Init::Expr(parse_expr(it)?),
+    _ => parse_initializer(it, ty, &mut init)?,
+  }
+  Ok(init)
+}
+
+/// Parses an initializer of an object of type `ty` into `init`, which holds the parts of the
+/// object initialized so far.
+fn parse_initializer(it: &mut Parser, ty: &Type, init: &mut Init) -> Expected<()> {
+  match ty {
+    _ if is_string_init(it, ty) => parse_string_init(it, ty, init),
+    Type::Array(..) | Type::Struct(..) | Type::Union(..) if peek(it, "{") => {
+      parse_init_list(it, ty, init)
+    }
+    Type::Struct(..) | Type::Union(..) => {
+      // a struct or union is copied from an expression of such a type, or otherwise its
+      // members take the initializers without braces
+      let checkpoint = it.checkpoint();
+      match parse_expr(it) {
+        Ok(expr) if matches!(expr.ty, Type::Struct(..) | Type::Union(..)) => {
+          *init = Init::Expr(expr);
+          Ok(())
+        }
+        _ => {
+          it.backtrack(checkpoint);
+          parse_elided_init_list(it, ty, init)
+        }
+      }
+    }
+    Type::Array(..) => parse_elided_init_list(it, ty, init),
+    _ if consume(it, "{")? => {
+      // a scalar may be enclosed in braces
+      parse_initializer(it, ty, init)?;
+      consume(it, ",")?;
+      expect(it, "}")
+    }
+    _ => {
+      *init = Init::Expr(convert(parse_expr(it)?, ty));
+      Ok(())
+    }
+  }
+}
+
+/// Returns whether a string literal initializing a character array of type `ty` follows.
+fn is_string_init(it: &mut Parser, ty: &Type) -> bool {
+  matches!(ty, Type::Array(elem_ty, _) if matches!(**elem_ty, Type::Char | Type::UChar))
+    && matches!(
+      it.current(),
+      Ok(Token {
+        kind: TokenKind::Str(_),
+        ..
+      })
+    )
+}
+
+/// Parses a string literal initializing a character array, whose terminating null character is
+/// dropped if the array has no room for it.
+fn parse_string_init(it: &mut Parser, ty: &Type, init: &mut Init) -> Expected<()> {
+  let start = it.span();
+  let mut s = Vec::new();
+  while let Some(t) = consume_str(it)? {
+    s.extend(t);
+  }
+  s.push(0);
+  let span = span_from(it, start);
+  let (elem_ty, len) = match ty {
+    Type::Array(elem_ty, len) => (elem_ty, len),
+    _ => unreachable!(),
+  };
+  let mut inits: Vec<_> = s
+    .into_iter()
+    .map(|c| {
+      let n = AST::new(ASTKind::Num(c as i8 as i64, Type::Int), span, Type::Int);
+      Init::Expr(convert(n, elem_ty))
+    })
+    .collect();
+  if let Some(len) = *len {
+    let len = len as usize;
+    if inits.len() > len + 1 {
+      return err!(span, "initializer-string for `{}` is too long", ty);
+    }
+    inits.resize(len, Init::Zero);
+  }
+  *init = Init::List(inits);
+  Ok(())
+}
+
+/// Parses a brace-enclosed initializer list of an array, struct or union.
+fn parse_init_list(it: &mut Parser, ty: &Type, init: &mut Init) -> Expected<()> {
+  expect(it, "{")?;
+  expand_init(it, ty, init);
+  let mut index = 0;
+  if !consume(it, "}")? {
+    index = parse_init_item(it, ty, init, index)?;
+    while !consume(it, "}")? {
+      expect(it, ",")?;
+      if consume(it, "}")? {
+        break;
+      }
+      index = parse_init_item(it, ty, init, index)?;
+    }
+  }
+  Ok(())
+}
+
+//' init_item   = (designator+ "=")? initializer
+/// Parses an initializer of the element or member at `index` unless a designator specifies
+/// another one, and returns the index of the element or member following it.
+fn parse_init_item(it: &mut Parser, ty: &Type, init: &mut Init, index: usize) -> Expected<usize> {
+  if peek(it, "[") || peek(it, ".") {
+    return Ok(parse_designation(it, ty, init)? + 1);
+  }
+  let start = it.span();
+  match init_element(it, ty, init, index) {
+    Some((elem_ty, elem)) => parse_initializer(it, &elem_ty, elem)?,
+    None => return err!(start, "excess elements in initializer of `{}`", ty),
+  }
+  Ok(index + 1)
+}
+
+//' designator  = "[" ternary "]" | "." ident
+/// Parses designators followed by an initializer of the element or member they designate, and
+/// returns the index of the element or member the first designator designates.
+fn parse_designation(it: &mut Parser, ty: &Type, init: &mut Init) -> Expected<usize> {
+  let start = it.span();
+  let index = if consume(it, "[")? {
+    if !matches!(ty, Type::Array(..)) {
+      return err!(
+        start,
+        "array index in initializer of non-array type `{}`",
+        ty
+      );
+    }
+    let expr = parse_ternary(it)?;
+    let n = eval_const(it, &expr)?;
+    expect(it, "]")?;
+    n.try_into()
+      .map_err(|_| diag!(expr.span, "array index `{}` in initializer is negative", n))?
+  } else {
+    expect(it, ".")?;
+    let name = expect_ident(it)?;
+    let names = match (ty, members_of(it, ty)) {
+      (Type::Struct(..) | Type::Union(..), Some((_, names))) => names,
+      _ => return err!(start, "field name not in struct or union initializer"),
+    };
+    match names.iter().position(|mem| mem == &name) {
+      Some(index) => index,
+      None => {
+        return err!(
+          span_from(it, start),
+          "`{}` has no member named `{}`",
+          ty,
+          name
+        )
+      }
+    }
+  };
+  let (elem_ty, elem) = match init_element(it, ty, init, index) {
+    Some(res) => res,
+    None => {
+      return err!(
+        span_from(it, start),
+        "array index `{}` exceeds bounds of `{}`",
+        index,
+        ty
+      )
+    }
+  };
+  if peek(it, "[") || peek(it, ".") {
+    parse_designation(it, &elem_ty, elem)?;
+  } else {
+    expect(it, "=")?;
+    parse_initializer(it, &elem_ty, elem)?;
+  }
+  Ok(index)
+}
+
+/// Parses initializers of the elements or members of `ty` without enclosing braces, which take
+/// as many initializers of the enclosing list as they need.
+fn parse_elided_init_list(it: &mut Parser, ty: &Type, init: &mut Init) -> Expected<()> {
+  let mut index = 0;
+  while let Some((elem_ty, elem)) = init_element(it, ty, init, index) {
+    if index > 0 {
+      // a designator or the end of the list belongs to the enclosing list
+      let next = it.peek(1);
+      let is_end = |op| matches!(&next, Ok(tok) if tok.kind == TokenKind::Punct(op));
+      if !peek(it, ",") || is_end("}") || is_end("[") || is_end(".") {
+        break;
+      }
+      it.advance();
+    }
+    parse_initializer(it, &elem_ty, elem)?;
+    index += 1;
+  }
+  Ok(())
+}
+
+/// Replaces the initializer of an array, struct or union of type `ty` with a list of those of
+/// its elements or members, unless it already is, and returns the list.
+fn expand_init<'a>(it: &Parser, ty: &Type, init: &'a mut Init) -> &'a mut Vec<Init> {
+  if !matches!(init, Init::List(_)) {
+    let len = match ty {
+      Type::Array(_, len) => len.unwrap_or(0) as usize,
+      _ => members_of(it, ty).map_or(0, |(tys, _)| tys.len()),
+    };
+    *init = Init::List(vec![Init::Zero; len]);
+  }
+  match init {
+    Init::List(inits) => inits,
+    _ => unreachable!(),
+  }
+}
+
+/// Returns the type and initializer of the element or member at `index` of an object of type
+/// `ty`, or `None` if there is no such element or member. An array of unknown size grows to
+/// have the element, and initializing a member of a union zero-fills the other members.
+fn init_element<'a>(
+  it: &Parser,
+  ty: &Type,
+  init: &'a mut Init,
+  index: usize,
+) -> Option<(Type, &'a mut Init)> {
+  let mem_tys = match ty {
+    Type::Struct(..) | Type::Union(..) => members_of(it, ty).map_or(Vec::new(), |(tys, _)| tys),
+    _ => Vec::new(),
+  };
+  let inits = expand_init(it, ty, init);
+  match ty {
+    Type::Array(elem_ty, len) => {
+      if len.is_none() && index >= inits.len() {
+        inits.resize(index + 1, Init::Zero);
+      }
+      Some(((**elem_ty).clone(), inits.get_mut(index)?))
+    }
+    Type::Struct(..) => Some((mem_tys.get(index)?.clone(), inits.get_mut(index)?)),
+    Type::Union(..) => {
+      let elem_ty = mem_tys.get(index)?.clone();
+      for (i, init) in inits.iter_mut().enumerate() {
+        if i != index {
+          *init = Init::Zero;
+        }
+      }
+      Some((elem_ty, inits.get_mut(index)?))
+    }
+    _ => None,
+  }
+}
+
+//' declspec    = basic_type+ | ("struct" | "union") ident? struct_decl?
+//'             | "enum" ident? enum_decl? | typedef_name
+//' basic_type  = "void" | "_Bool" | "char" | "short" | "int" | "long" | "float" | "double"
+//'             | "signed" | "unsigned"
+fn parse_declspec(it: &mut Parser) -> Expected<Type> {
+  let start = it.span();
+  if is_basic_type(&it.current()) {
+    parse_basic_types(it)
+  } else if consume_keyword(it, "struct")? {
+    let (name, mems) = parse_tag(it, "struct", start)?;
+    Ok(declare_tag(it, Type::Struct(name, mems)))
+  } else if consume_keyword(it, "union")? {
+    let (name, mems) = parse_tag(it, "union", start)?;
+    Ok(declare_tag(it, Type::Union(name, mems)))
+  } else if consume_keyword(it, "enum")? {
+    // enumerated types are compatible with `int`, which an enum tag is declared as. Misused
+    // tags are reported, but the syntax is fine, so carry on parsing
+    let name = consume_ident(it)?;
+    match name {
+      _ if peek(it, "{") => {
+        let redefined = matches!(&name, Some(name) if it.tags.get(name).is_some());
+        if let (Some(name), true) = (&name, redefined) {
+          let diag = diag!(span_from(it, start), "redefinition of `enum {}`", name);
+          report(it, diag);
+        }
+        parse_enum_decl(it)?;
+        if let (Some(name), false) = (name, redefined) {
+          it.tags.insert(name, Type::Int);
+        }
+      }
+      Some(name) => match it.tags.get_all(&name) {
+        Some(Type::Int) => (),
+        Some(_) => {
+          let diag = diag!(
+            span_from(it, start),
+            "`{}` is not declared as an enum tag",
+            name
+          );
+          report(it, diag);
+        }
+        None => {
+          let diag = diag!(span_from(it, start), "undefined enum `{}`", name);
+          report(it, diag);
+        }
+      },
+      None => {
+        return err!(
+          span_from(it, start),
+          "Both the name and body of the enum are missing"
+        )
+      }
+    }
+    Ok(Type::Int)
+  } else {
+    let tok = it.current()?;
+    if let TokenKind::Ident(name) = &tok.kind {
+      if let Some(ty) = it.scope.get_typedef(name).cloned() {
+        it.advance();
+        return Ok(ty);
+      }
+    }
+    err!(
+      tok.span,
+      "unexpected {}, expecting type specifier, `struct`, `union`, `enum` or type name",
+      tok.kind
+    )
+  }
+}
+
+static BASIC_TYPES: [&str; 10] = [
+  "void", "_Bool", "char", "short", "int", "long", "float", "double", "signed", "unsigned",
+];
+
+fn is_basic_type(item: &Expected<Token>) -> bool {
+  matches!(item, Ok(Token { kind: TokenKind::Keyword(kw), .. }) if BASIC_TYPES.contains(kw))
+}
+
+/// Parses a sequence of basic type keywords, which may come in any order as in `long unsigned
+/// int`, and returns the type they specify.
+fn parse_basic_types(it: &mut Parser) -> Expected<Type> {
+  let start = it.span();
+  let mut counts = [0; BASIC_TYPES.len()];
+  while let Ok(Token {
+    kind: TokenKind::Keyword(kw),
+    ..
+  }) = it.current()
+  {
+    match BASIC_TYPES.iter().position(|&ty| ty == kw) {
+      Some(i) => counts[i] += 1,
+      None => break,
+    }
+    it.advance();
+  }
+  let [void, bool_, char_, short, int, long, float, double, signed, unsigned] = counts;
+  let ty = match (
+    void,
+    bool_,
+    char_,
+    short,
+    int,
+    long,
+    float,
+    double,
+    signed + unsigned,
+  ) {
+    (1, 0, 0, 0, 0, 0, 0, 0, 0) => Some(Type::Void),
+    (0, 1, 0, 0, 0, 0, 0, 0, 0) => Some(Type::Bool),
+    (0, 0, 1, 0, 0, 0, 0, 0, _) => Some(Type::Char),
+    (0, 0, 0, 1, 0 | 1, 0, 0, 0, _) => Some(Type::Short),
+    (0, 0, 0, 0, 0 | 1, 0, 0, 0, _) => Some(Type::Int),
+    (0, 0, 0, 0, 0 | 1, 1, 0, 0, _) => Some(Type::Long),
+    (0, 0, 0, 0, 0 | 1, 2, 0, 0, _) => Some(Type::LongLong),
+    (0, 0, 0, 0, 0, 0, 1, 0, 0) => Some(Type::Float),
+    // `long double` is the same as `double`
+    (0, 0, 0, 0, 0, 0 | 1, 0, 1, 0) => Some(Type::Double),
+    _ => None,
+  };
+  match ty {
+    Some(ty) if signed + unsigned <= 1 => {
+      if unsigned == 1 {
+        Ok(ty.to_unsigned())
+      } else {
+        Ok(ty)
+      }
+    }
+    _ => err!(
+      span_from(it, start),
+      "invalid combination of type specifiers"
+    ),
+  }
+}
+
+/// The member types and names of a struct or union, in declaration order.
+type Members = (Vec<Type>, Vec<String>);
+
+/// Parses the optional tag and member list following `struct` or `union`.
+fn parse_tag(
+  it: &mut Parser,
+  keyword: &str,
+  start: Span,
+) -> Expected<(Option<String>, Option<Members>)> {
+  let name = consume_ident(it)?;
+  let mems = if peek(it, "{") {
+    let decl_start = it.span();
+    let mems = parse_struct_decl(it)?;
+    let tags = |ty: &Type| members_of(it, ty).map(|(tys, _)| tys);
+    let is_complete = mems.0.iter().all(|ty| ty.layout(&tags).is_some());
+    let layout = if keyword == "union" {
+      union_layout(&mems.0, &tags)
+    } else {
+      struct_layout(&mems.0, &tags).map(|(layout, _)| layout)
+    };
+    if is_complete && layout.is_none() {
+      return err!(span_from(it, decl_start), "{} is too large", keyword);
+    }
+    Some(mems)
+  } else {
+    None
+  };
+  if name.is_none() && mems.is_none() {
+    err!(
+      span_from(it, start),
+      "Both the name and body of the {} are missing",
+      keyword
+    )
+  } else {
+    Ok((name, mems))
+  }
+}
+
+/// Records the definition of a named struct or union, so that the members of a type referring
+/// to it by tag can be looked up.
+fn declare_tag(it: &mut Parser, ty: Type) -> Type {
+  if let Type::Struct(Some(name), Some(_)) | Type::Union(Some(name), Some(_)) = &ty {
+    it.tags.insert(name.clone(), ty.clone());
+  }
+  ty
+}
+
+/// Declares the tag of `struct t;` or `union t;` in the current scope as an incomplete type,
+/// which hides the tag of an outer scope until it is defined.
+fn forward_declare_tag(it: &mut Parser, ty: &Type) {
+  if let Type::Struct(Some(name), None) | Type::Union(Some(name), None) = ty {
+    if it.tags.get(name).is_none() {
+      it.tags.insert(name.clone(), ty.clone());
+    }
+  }
+}
+
+/// Returns the member types and names of a struct or union, which is looked up by tag if `ty`
+/// only refers to it.
+fn members_of(it: &Parser, ty: &Type) -> Option<Members> {
+  match (ty, ty_tag(it, ty)) {
+    (Type::Struct(_, Some(mems)) | Type::Union(_, Some(mems)), _) => Some(mems.clone()),
+    (Type::Struct(_, None), Some(Type::Struct(_, Some(mems)))) => Some(mems.clone()),
+    (Type::Union(_, None), Some(Type::Union(_, Some(mems)))) => Some(mems.clone()),
+    _ => None,
+  }
+}
+
+fn ty_tag<'a>(it: &'a Parser, ty: &Type) -> Option<&'a Type> {
+  match ty {
+    Type::Struct(Some(name), None) | Type::Union(Some(name), None) => it.tags.get_all(name),
+    _ => None,
+  }
+}
+
+/// Returns the layout of `ty`, or fails if `ty` is incomplete.
+fn layout_of(it: &Parser, ty: &Type, span: Span) -> Expected<Layout> {
+  let tags = |ty: &Type| members_of(it, ty).map(|(tys, _)| tys);
+  match ty.layout(&tags) {
+    Some(layout) => Ok(layout),
+    None => err!(span, "incomplete type `{}` has no size", ty),
+  }
+}
+
+//' struct_decl = "{" struct_mem* "}"
+fn parse_struct_decl(it: &mut Parser) -> Expected<Members> {
+  expect(it, "{")?;
+  let mut mems = Vec::new();
+  while !consume(it, "}")? {
+    let mut mem = parse_struct_mem(it)?;
+    mems.append(&mut mem);
+  }
+  Ok(mems.into_iter().unzip())
+}
+
+//' struct_mem  = declspec declarator ("," declarator)* ";"
+fn parse_struct_mem(it: &mut Parser) -> Expected<Vec<(Type, String)>> {
+  let ty = parse_declspec(it)?;
+  let mut mem = Vec::new();
+  mem.push(parse_declarator(it, ty.clone())?);
+  while !consume(it, ";")? {
+    expect(it, ",")?;
+    mem.push(parse_declarator(it, ty.clone())?);
+  }
+  Ok(mem)
+}
+
+//' enum_decl   = "{" enumerator ("," enumerator)* ","? "}"
+fn parse_enum_decl(it: &mut Parser) -> Expected<()> {
+  expect(it, "{")?;
+  let mut n = 0;
+  loop {
+    n = parse_enumerator(it, n)?.wrapping_add(1);
+    if consume(it, "}")? {
+      break Ok(());
+    }
+    expect(it, ",")?;
+    if consume(it, "}")? {
+      break Ok(());
+    }
+  }
+}
+
+//' enumerator  = ident ("=" ternary)?
+/// Parses an enumerator, which takes the value `n` unless it is given explicitly, and returns
+/// its value.
+fn parse_enumerator(it: &mut Parser, n: i64) -> Expected<i64> {
+  let start = it.span();
+  let name = expect_ident(it)?;
+  let n = if consume(it, "=")? {
+    let expr = parse_ternary(it)?;
+    eval_const(it, &expr).unwrap_or_else(|e| {
+      // the syntax is fine, so carry on parsing
+      report(it, e);
+      n
+    })
+  } else {
+    n
+  };
+  if i32::try_from(n).is_err() {
+    let span = span_from(it, start);
+    report(
+      it,
+      diag!(
+        span,
+        "value of enumerator `{}` is not representable as `int`",
+        name
+      ),
+    );
+  }
+  it.scope.insert_enumerator(name, n);
+  Ok(n)
+}
+
+/// Evaluates an integer constant expression.
+fn eval_const(it: &Parser, expr: &AST) -> Expected<i64> {
+  let binary = |n: &AST, m: &AST, op: fn(i64, i64) -> Option<i64>| {
+    let (n, m) = (eval_const(it, n)?, eval_const(it, m)?);
+    op(n, m).ok_or_else(|| {
+      Diagnostic::error("overflow or division by zero in constant expression").at(expr.span)
+    })
+  };
+  let n = match &expr.kind {
+    ASTKind::Ternary(cond, then, else_) => {
+      if eval_const(it, cond)? != 0 {
+        eval_const(it, then)
+      } else {
+        eval_const(it, else_)
+      }
+    }
+    ASTKind::LogOr(n, m) => Ok((eval_const(it, n)? != 0 || eval_const(it, m)? != 0) as i64),
+    ASTKind::LogAnd(n, m) => Ok((eval_const(it, n)? != 0 && eval_const(it, m)? != 0) as i64),
+    ASTKind::Eq(n, m) if n.ty.is_float() => Ok((eval_float(it, n)? == eval_float(it, m)?) as i64),
+    ASTKind::Ne(n, m) if n.ty.is_float() => Ok((eval_float(it, n)? != eval_float(it, m)?) as i64),
+    ASTKind::Lt(n, m) if n.ty.is_float() => Ok((eval_float(it, n)? < eval_float(it, m)?) as i64),
+    ASTKind::Le(n, m) if n.ty.is_float() => Ok((eval_float(it, n)? <= eval_float(it, m)?) as i64),
+    ASTKind::Eq(n, m) => binary(n, m, |n, m| Some((n == m) as i64)),
+    ASTKind::Ne(n, m) => binary(n, m, |n, m| Some((n != m) as i64)),
+    // unsigned operands are compared and divided as `u64`, which holds the values of all
+    // unsigned types, and arithmetic on them wraps around
+    ASTKind::Lt(n, m) if n.ty.is_unsigned() => {
+      binary(n, m, |n, m| Some(((n as u64) < m as u64) as i64))
+    }
+    ASTKind::Le(n, m) if n.ty.is_unsigned() => {
+      binary(n, m, |n, m| Some((n as u64 <= m as u64) as i64))
+    }
+    ASTKind::Add(n, m) if n.ty.is_unsigned() => binary(n, m, |n, m| Some(n.wrapping_add(m))),
+    ASTKind::Sub(n, m) if n.ty.is_unsigned() => binary(n, m, |n, m| Some(n.wrapping_sub(m))),
+    ASTKind::Mul(n, m) if n.ty.is_unsigned() => binary(n, m, |n, m| Some(n.wrapping_mul(m))),
+    ASTKind::Div(n, m) if n.ty.is_unsigned() => {
+      binary(n, m, |n, m| Some((n as u64).checked_div(m as u64)? as i64))
+    }
+    ASTKind::Mod(n, m) if n.ty.is_unsigned() => {
+      binary(n, m, |n, m| Some((n as u64).checked_rem(m as u64)? as i64))
+    }
+    ASTKind::Lt(n, m) => binary(n, m, |n, m| Some((n < m) as i64)),
+    ASTKind::Le(n, m) => binary(n, m, |n, m| Some((n <= m) as i64)),
+    ASTKind::Add(n, m) => binary(n, m, i64::checked_add),
+    ASTKind::Sub(n, m) => binary(n, m, i64::checked_sub),
+    ASTKind::Mul(n, m) => binary(n, m, i64::checked_mul),
+    ASTKind::Div(n, m) => binary(n, m, i64::checked_div),
+    ASTKind::Mod(n, m) => binary(n, m, i64::checked_rem),
+    ASTKind::BitAnd(n, m) => binary(n, m, |n, m| Some(n & m)),
+    ASTKind::BitOr(n, m) => binary(n, m, |n, m| Some(n | m)),
+    ASTKind::BitXor(n, m) => binary(n, m, |n, m| Some(n ^ m)),
+    ASTKind::Shl(n, m) => binary(n, m, |n, m| n.checked_shl(m.try_into().ok()?)),
+    ASTKind::Shr(n, m) if n.ty.is_unsigned() => binary(n, m, |n, m| {
+      Some((n as u64).checked_shr(m.try_into().ok()?)? as i64)
+    }),
+    ASTKind::Shr(n, m) => binary(n, m, |n, m| n.checked_shr(m.try_into().ok()?)),
+    ASTKind::Not(n) => Ok((eval_const(it, n)? == 0) as i64),
+    ASTKind::BitNot(n) => Ok(!eval_const(it, n)?),
+    ASTKind::Neg(n) if n.ty.is_unsigned() => Ok(eval_const(it, n)?.wrapping_neg()),
+    ASTKind::Neg(n) => eval_const(it, n)?.checked_neg().ok_or_else(|| {
+      Diagnostic::error("overflow or division by zero in constant expression").at(expr.span)
+    }),
+    ASTKind::Cast(ty, n) if ty.is_integer() => {
+      let n = if n.ty.is_float() {
+        // the value is truncated toward zero, and saturated if it is out of range
+        eval_float(it, n)? as i64
+      } else {
+        eval_const(it, n)?
+      };
+      match ty {
+        Type::Bool => Ok((n != 0) as i64),
+        Type::Char => Ok(n as i8 as i64),
+        Type::UChar => Ok(n as u8 as i64),
+        Type::Short => Ok(n as i16 as i64),
+        Type::UShort => Ok(n as u16 as i64),
+        Type::Int => Ok(n as i32 as i64),
+        Type::UInt => Ok(n as u32 as i64),
+        _ => Ok(n),
+      }
+    }
+    ASTKind::SizeOf(ty) => Ok(layout_of(it, ty, expr.span)?.size.into()),
+    ASTKind::AlignOf(ty) => Ok(layout_of(it, ty, expr.span)?.align.into()),
+    ASTKind::Enumerator(_, n) | ASTKind::Num(n, _) => Ok(*n),
+    _ => err!(expr.span, "expression is not an integer constant"),
+  }?;
+  // the arithmetic above is done in 64 bits, so a result of a narrower type wraps around if it
+  // is unsigned, and overflows if it is signed and out of range
+  match expr.ty {
+    Type::UInt => Ok(n as u32 as i64),
+    Type::Int if i32::try_from(n).is_err() => err!(expr.span, "overflow in constant expression"),
+    _ => Ok(n),
+  }
+}
+
+/// Evaluates an arithmetic constant expression of a floating type, which may appear as an
+/// operand of an integer constant expression converted to an integer type.
+fn eval_float(it: &Parser, expr: &AST) -> Expected<f64> {
+  let round = |x: f64| match expr.ty {
+    Type::Float => x as f32 as f64,
+    _ => x,
+  };
+  match &expr.kind {
+    ASTKind::Ternary(cond, then, else_) => {
+      let cond = if cond.ty.is_float() {
+        eval_float(it, cond)? != 0.0
+      } else {
+        eval_const(it, cond)? != 0
+      };
+      if cond {
+        eval_float(it, then)
+      } else {
+        eval_float(it, else_)
+      }
+    }
+    ASTKind::Add(n, m) => Ok(round(eval_float(it, n)? + eval_float(it, m)?)),
+    ASTKind::Sub(n, m) => Ok(round(eval_float(it, n)? - eval_float(it, m)?)),
+    ASTKind::Mul(n, m) => Ok(round(eval_float(it, n)? * eval_float(it, m)?)),
+    ASTKind::Div(n, m) => Ok(round(eval_float(it, n)? / eval_float(it, m)?)),
+    ASTKind::Neg(n) => Ok(-eval_float(it, n)?),
+    ASTKind::Cast(_, n) if n.ty.is_float() => Ok(round(eval_float(it, n)?)),
+    ASTKind::Cast(_, n) if n.ty.is_unsigned() => Ok(round(eval_const(it, n)? as u64 as f64)),
+    ASTKind::Cast(_, n) => Ok(round(eval_const(it, n)? as f64)),
+    ASTKind::Float(x, _) => Ok(*x),
+    _ => err!(expr.span, "expression is not an arithmetic constant"),
   }
 }
 
-//' decllist    = (declitem ("," declitem)*)? ";"
-fn parse_decllist(it: &mut Tokenizer, ty: Type) -> Expected<Vec<(Type, String, Option<AST>)>> {
-  let mut decls = Vec::new();
-  if !consume(it, ";")? {
-    decls.push(parse_declitem(it, ty.clone())?);
-    while !consume(it, ";")? {
-      expect(it, ",")?;
-      decls.push(parse_declitem(it, ty.clone())?);
-    }
+/// Returns the type an array or function designated by an expression of type `ty` decays to.
+fn decay(ty: Type) -> Type {
+  match ty {
+    Type::Array(ty, _) => Type::Pointer(ty),
+    ty @ Type::FunTy(..) => Type::Pointer(Box::new(ty)),
+    ty => ty,
   }
-  Ok(decls)
 }
 
-//' declitem    = declarator ("=" expr)?
-fn parse_declitem(it: &mut Tokenizer, ty: Type) -> Expected<(Type, String, Option<AST>)> {
-  let (ty, name) = parse_declarator(it, ty)?;
-  if let Type::FunTy(..) = ty {
-    // parsing function declaration
-    Ok((ty, name, None))
-  } else {
-    // parsing variable definition
-    let init = if consume(it, "=")? {
-      Some(parse_expr(it)?)
-    } else {
-      None
-    };
-    Ok((ty, name, init))
+/// Returns the function type of a callee of type `ty`, which is a function or a pointer to
+/// function.
+pub fn callee_type(ty: &Type) -> Option<&Type> {
+  match ty {
+    Type::FunTy(..) => Some(ty),
+    Type::Pointer(ty) if matches!(**ty, Type::FunTy(..)) => Some(ty),
+    _ => None,
   }
 }
 
-//' declspec = "char" | "int" | "struct" ident? struct_decl?
-fn parse_declspec(it: &mut Tokenizer) -> Expected<Type> {
-  if consume_keyword(it, "int")? {
-    Ok(Type::Int)
-  } else if consume_keyword(it, "char")? {
-    Ok(Type::Char)
-  } else if consume_keyword(it, "struct")? {
-    let name = consume_ident(it)?;
-    let mems = try_parse(it, parse_struct_decl);
-    if name.is_none() && mems.is_none() {
-      err!("Both the name and body of the struct are missing")
-    } else {
-      Ok(Type::Struct(name, mems))
+/// Returns the type of an expression of `kind` from the types of its operands, looking up
+/// identifiers in the current scope.
+fn type_of(it: &Parser, kind: &ASTKind, span: Span) -> Expected<Type> {
+  match kind {
+    ASTKind::Ternary(_, then, _) => Ok(decay(then.ty.clone())),
+    ASTKind::Assign(n, _) | ASTKind::CompoundAssign(n, _) | ASTKind::PostfixAssign(n, _) => {
+      Ok(n.ty.clone())
+    }
+    ASTKind::LogOr(..)
+    | ASTKind::LogAnd(..)
+    | ASTKind::Eq(..)
+    | ASTKind::Ne(..)
+    | ASTKind::Lt(..)
+    | ASTKind::Le(..)
+    | ASTKind::Not(_) => Ok(Type::Int),
+    ASTKind::Add(n, m) => match (decay(n.ty.clone()), decay(m.ty.clone())) {
+      (ty @ Type::Pointer(_), _) | (_, ty @ Type::Pointer(_)) => Ok(ty),
+      (ty, _) => Ok(ty),
+    },
+    ASTKind::Sub(n, m) => match (decay(n.ty.clone()), decay(m.ty.clone())) {
+      (Type::Pointer(_), Type::Pointer(_)) => Ok(Type::Long),
+      (ty, _) => Ok(ty),
+    },
+    ASTKind::Mul(n, _)
+    | ASTKind::Div(n, _)
+    | ASTKind::Mod(n, _)
+    | ASTKind::BitAnd(n, _)
+    | ASTKind::BitOr(n, _)
+    | ASTKind::BitXor(n, _)
+    | ASTKind::Shl(n, _)
+    | ASTKind::Shr(n, _)
+    | ASTKind::BitNot(n)
+    | ASTKind::Neg(n) => Ok(n.ty.clone()),
+    ASTKind::Addr(n) => Ok(Type::Pointer(Box::new(n.ty.clone()))),
+    ASTKind::Deref(n) => match decay(n.ty.clone()) {
+      Type::Pointer(ty) => Ok(*ty),
+      _ => err!(span, "cannot dereference int value"),
+    },
+    ASTKind::Cast(ty, _) => Ok(ty.clone()),
+    ASTKind::SizeOf(_) | ASTKind::AlignOf(_) => Ok(Type::ULong),
+    ASTKind::Dot(n, name) => {
+      if let Some((mem_tys, mem_names)) = members_of(it, &n.ty) {
+        match mem_names.iter().position(|mem| mem == name) {
+          Some(index) => Ok(mem_tys[index].clone()),
+          None => err!(span, "`{}` has no member named `{}`", n.ty, name),
+        }
+      } else {
+        err!(span, "lhs is not a struct or union")
+      }
+    }
+    ASTKind::Block(stmts) => match stmts.last() {
+      Some(Stmt {
+        kind: StmtKind::Expr(n),
+        ..
+      }) => Ok(n.ty.clone()),
+      _ => Ok(Type::Void),
+    },
+    ASTKind::VaStart(_) | ASTKind::VaEnd(_) => Ok(Type::Void),
+    ASTKind::VaArg(_, ty) => Ok(ty.clone()),
+    ASTKind::Call(callee, _) => match callee_type(&callee.ty) {
+      Some(Type::FunTy(ret_ty, ..)) => Ok(*ret_ty.clone()),
+      _ => err!(span, "called object is not a function or function pointer"),
+    },
+    ASTKind::Ident(name) => match it.scope.get_all(name) {
+      Some(ty) => Ok(ty.clone()),
+      None => err!(
+        span,
+        "variable `{}` should be declared before its first use",
+        name
+      ),
+    },
+    ASTKind::Enumerator(..) => Ok(Type::Int),
+    ASTKind::AssignedValue(ty) | ASTKind::Num(_, ty) => Ok(ty.clone()),
+    ASTKind::Float(_, ty) => Ok(ty.clone()),
+    ASTKind::Str(s) => {
+      let len = s.len() + 1;
+      let len = len
+        .try_into()
+        .map_err(|_| diag!(span, "string literal is too long"))?;
+      Ok(Type::Array(Box::new(Type::Char), Some(len)))
     }
-  } else {
-    err!("unexpected token, expecting `int`, `char` or `struct`")
   }
 }
 
-//' struct_decl = "{" struct_mem* "}"
-fn parse_struct_decl(it: &mut Tokenizer) -> Expected<(Vec<Type>, Vec<String>)> {
-  expect(it, "{")?;
-  let mut mems = Vec::new();
-  while !consume(it, "}")? {
-    let mut mem = parse_struct_mem(it)?;
-    mems.append(&mut mem);
-  }
-  Ok(mems.into_iter().unzip())
+/// Whether a declarator names an identifier, which it must in a declaration and must not in a
+/// type name, while a parameter may be unnamed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Naming {
+  Named,
+  Abstract,
+  Optional,
 }
 
-//' struct_mem  = declspec declarator ("," declarator)* ";"
-fn parse_struct_mem(it: &mut Tokenizer) -> Expected<Vec<(Type, String)>> {
-  let ty = parse_declspec(it)?;
-  let mut mem = Vec::new();
-  mem.push(parse_declarator(it, ty.clone())?);
-  while !consume(it, ";")? {
-    expect(it, ",")?;
-    mem.push(parse_declarator(it, ty.clone())?);
-  }
-  Ok(mem)
+//' declarator  = "*"* ("(" declarator ")" | ident) type_suffix
+fn parse_declarator(it: &mut Parser, ty: Type) -> Expected<(Type, String)> {
+  let (ty, name) = parse_declarator_impl(it, ty, Naming::Named)?;
+  Ok((ty, name.unwrap()))
+}
+
+//' abstract_declarator = "*"* ("(" abstract_declarator ")")? type_suffix
+fn parse_abstract_declarator(it: &mut Parser, ty: Type) -> Expected<Type> {
+  let (ty, _) = parse_declarator_impl(it, ty, Naming::Abstract)?;
+  Ok(ty)
 }
 
-//' declarator  = "*"* ident type_suffix
-fn parse_declarator(it: &mut Tokenizer, mut ty: Type) -> Expected<(Type, String)> {
+fn parse_declarator_impl(
+  it: &mut Parser,
+  mut ty: Type,
+  naming: Naming,
+) -> Expected<(Type, Option<String>)> {
   while consume(it, "*")? {
     ty = Type::Pointer(Box::new(ty));
   }
-  let name = expect_ident(it)?;
-  ty = parse_type_suffix(it, ty)?;
-  Ok((ty, name))
+  if is_nested_declarator(it, naming) {
+    // the type suffix following the parentheses applies before the nested declarator, as in
+    // `int (*fp)(int)`, so the nested declarator is skipped and parsed again with that type
+    it.advance();
+    let nested = it.tokens.position();
+    parse_declarator_impl(it, Type::Int, naming)?;
+    expect(it, ")")?;
+    let ty = parse_type_suffix(it, ty)?;
+    let end = it.checkpoint();
+    it.tokens.rewind(nested);
+    let res = parse_declarator_impl(it, ty, naming)?;
+    // the diagnostics were reported when the nested declarator was first parsed
+    it.backtrack(end);
+    Ok(res)
+  } else {
+    let name = match naming {
+      Naming::Named => Some(expect_ident(it)?),
+      Naming::Abstract => None,
+      Naming::Optional => consume_ident(it)?,
+    };
+    ty = parse_type_suffix(it, ty)?;
+    Ok((ty, name))
+  }
+}
+
+/// Looks ahead to check whether a parenthesized declarator follows. Parentheses in a declarator
+/// without a name may instead enclose the parameters of a function, as in `int (int)`.
+fn is_nested_declarator(it: &mut Parser, naming: Naming) -> bool {
+  if !peek(it, "(") {
+    return false;
+  }
+  let next = it.peek(1);
+  naming == Naming::Named
+    || !(starts_declspec(it, &next)
+      || matches!(&next, Ok(tok) if tok.kind == TokenKind::Punct(")")))
 }
 
-//' type_suffix = "[" num "]"
+//' type_suffix = "[" ternary? "]" type_suffix
 //'             | "(" fun_params
 //'             | ε
-fn parse_type_suffix(it: &mut Tokenizer, ty: Type) -> Expected<Type> {
+fn parse_type_suffix(it: &mut Parser, ty: Type) -> Expected<Type> {
+  let start = it.span();
   if consume(it, "[")? {
-    let n = expect_num(it)?;
-    let n = n.try_into().or(err!("failed to convert integer"))?;
-    expect(it, "]")?;
-    Ok(Type::Array(Box::new(ty), n))
+    let n = if consume(it, "]")? {
+      None
+    } else {
+      let expr = parse_ternary(it)?;
+      let n = eval_const(it, &expr)?;
+      let n = n
+        .try_into()
+        .map_err(|_| diag!(expr.span, "array size `{}` is out of range", n))?;
+      expect(it, "]")?;
+      Some(n)
+    };
+    // `int a[2][3]` is an array of 2 arrays of 3 ints
+    let ty = parse_type_suffix(it, ty)?;
+    if let Type::Array(_, None) = ty {
+      return err!(
+        span_from(it, start),
+        "array has incomplete element type `{}`",
+        ty
+      );
+    }
+    let tags = |ty: &Type| members_of(it, ty).map(|(tys, _)| tys);
+    let is_complete = n.is_some() && ty.layout(&tags).is_some();
+    let ty = Type::Array(Box::new(ty), n);
+    if is_complete && ty.layout(&tags).is_none() {
+      return err!(span_from(it, start), "array is too large");
+    }
+    Ok(ty)
   } else if consume(it, "(")? {
-    let params = parse_fun_params(it)?;
+    let (params, variadic) = parse_fun_params(it)?;
     let (param_tys, param_names) = params.into_iter().unzip();
-    Ok(Type::FunTy(Box::new(ty), param_tys, param_names))
+    Ok(Type::FunTy(Box::new(ty), param_tys, param_names, variadic))
   } else {
     Ok(ty)
   }
 }
 
-//' fun_params  = param (("," param)*)? ")"
-fn parse_fun_params(it: &mut Tokenizer) -> Expected<Vec<(Type, String)>> {
+//' fun_params  = ("void" | param ("," param)* ("," "...")?)? ")"
+/// Parses the parameters of a function, and returns them with whether the function is variadic.
+fn parse_fun_params(it: &mut Parser) -> Expected<(Vec<(Type, String)>, bool)> {
   let mut params = Vec::new();
-  if !consume(it, ")")? {
+  let mut variadic = false;
+  if matches!(it.current(), Ok(tok) if tok.kind == TokenKind::Keyword("void"))
+    && matches!(it.peek(1), Ok(tok) if tok.kind == TokenKind::Punct(")"))
+  {
+    // `(void)` declares that the function takes no parameters
+    it.advance();
+    it.advance();
+  } else if !consume(it, ")")? {
     params.push(parse_param(it)?);
     while !consume(it, ")")? {
       expect(it, ",")?;
+      if consume(it, "...")? {
+        variadic = true;
+        expect(it, ")")?;
+        break;
+      }
       params.push(parse_param(it)?);
     }
   }
-  Ok(params)
+  Ok((params, variadic))
 }
 
-//' param       = declspec declarator
-fn parse_param(it: &mut Tokenizer) -> Expected<(Type, String)> {
+//' param       = declspec (declarator | abstract_declarator)
+/// Parses a parameter, whose name is empty if it is unnamed.
+fn parse_param(it: &mut Parser) -> Expected<(Type, String)> {
+  let start = it.span();
   let ty = parse_declspec(it)?;
-  parse_declarator(it, ty)
+  let (ty, name) = parse_declarator_impl(it, ty, Naming::Optional)?;
+  let name = name.unwrap_or_default();
+  match without_param_names(ty) {
+    Type::Void if name.is_empty() => err!(span_from(it, start), "parameter declared void"),
+    Type::Void => err!(span_from(it, start), "parameter `{}` declared void", name),
+    // a parameter of array type is adjusted to a pointer to its element, and a parameter of
+    // function type to a pointer to function
+    Type::Array(ty, _) => Ok((Type::Pointer(ty), name)),
+    ty @ Type::FunTy(..) => Ok((Type::Pointer(Box::new(ty)), name)),
+    ty => Ok((ty, name)),
+  }
+}
+
+/// Drops the parameter names of the function types `ty` is derived from, so that the type of a
+/// parameter or a type name does not depend on them.
+fn without_param_names(ty: Type) -> Type {
+  match ty {
+    Type::Pointer(ty) => Type::Pointer(Box::new(without_param_names(*ty))),
+    Type::Array(ty, n) => Type::Array(Box::new(without_param_names(*ty)), n),
+    Type::FunTy(ret_ty, param_tys, _, variadic) => Type::FunTy(
+      Box::new(without_param_names(*ret_ty)),
+      param_tys,
+      Vec::new(),
+      variadic,
+    ),
+    ty => ty,
+  }
 }
 
-//' stmt        = declspec decllist
+//' stmt        = ident ":" stmt
+//'             | "typedef" declspec typedef
+//'             | storage? declspec decllist
 //'             | "if" "(" expr ")" stmt ("else" stmt)?
 //'             | "for" "(" expr? ";" expr? ";" expr? ")" stmt
 //'             | "while" "(" expr ")" stmt
+//'             | "do" stmt "while" "(" expr ")" ";"
+//'             | "switch" "(" expr ")" stmt
+//'             | "case" ternary ":" stmt
+//'             | "default" ":" stmt
 //'             | "break" ";"
 //'             | "continue" ";"
-//'             | "return" expr ";"
+//'             | "goto" ident ";"
+//'             | "return" expr? ";"
 //'             | "{" compound_stmt
 //'             | ";"
 //'             | expr ";"
-fn parse_stmt(it: &mut Tokenizer) -> Expected<Stmt> {
-  if let Some(ty) = try_parse(it, parse_declspec) {
-    let decllist = parse_decllist(it, ty.clone())?;
-    if decllist.is_empty() && matches!(ty, Type::Struct(..)) {
-      Ok(Stmt::StructDef(ty))
+fn parse_stmt(it: &mut Parser) -> Expected<Stmt> {
+  let start = it.span();
+  let is_label = matches!(it.peek(1), Ok(tok) if tok.kind == TokenKind::Punct(":"));
+  let kind = if is_label && matches!(it.current()?.kind, TokenKind::Ident(_)) {
+    let name = expect_ident(it)?;
+    expect(it, ":")?;
+    let stmt = Box::new(parse_stmt(it)?);
+    StmtKind::Label(name, stmt)
+  } else if consume_keyword(it, "typedef")? {
+    let ty = parse_declspec(it)?;
+    let (def, ty) = split_struct_def(ty);
+    parse_typedef(it, ty)?;
+    match def {
+      Some(def) => StmtKind::StructDef(def),
+      None => StmtKind::Block(Vec::new()),
+    }
+  } else if is_declspec(it) {
+    let storage = parse_storage(it)?;
+    let ty = parse_declspec(it)?;
+    let decllist = parse_decllist(it, ty.clone(), storage)?;
+    if decllist.is_empty() && matches!(ty, Type::Struct(..) | Type::Union(..)) {
+      forward_declare_tag(it, &ty);
+      StmtKind::StructDef(ty)
     } else {
-      Ok(Stmt::VarDef(decllist))
+      StmtKind::VarDef(decllist, storage)
     }
   } else if consume_keyword(it, "if")? {
     expect(it, "(")?;
@@ -391,300 +1905,581 @@ fn parse_stmt(it: &mut Tokenizer) -> Expected<Stmt> {
     } else {
       None
     };
-    Ok(Stmt::IfElse(cond, then_stmt, else_stmt))
+    StmtKind::IfElse(cond, then_stmt, else_stmt)
   } else if consume_keyword(it, "for")? {
     expect(it, "(")?;
-    let n1 = parse_expr(it).ok();
+    let n1 = parse_opt_expr(it, ";")?.map(Box::new);
     expect(it, ";")?;
-    let n2 = parse_expr(it).ok();
+    let n2 = parse_opt_expr(it, ";")?.map(Box::new);
     expect(it, ";")?;
-    let n3 = parse_expr(it).ok();
+    let n3 = parse_opt_expr(it, ")")?.map(Box::new);
     expect(it, ")")?;
     let stmt = Box::new(parse_stmt(it)?);
-    Ok(Stmt::For(n1, n2, n3, stmt))
+    StmtKind::For(n1, n2, n3, stmt)
   } else if consume_keyword(it, "while")? {
     expect(it, "(")?;
-    let n2 = parse_expr(it).ok();
+    let n2 = parse_expr(it)?;
+    expect(it, ")")?;
+    let stmt = Box::new(parse_stmt(it)?);
+    StmtKind::For(None, Some(Box::new(n2)), None, stmt)
+  } else if consume_keyword(it, "do")? {
+    let stmt = Box::new(parse_stmt(it)?);
+    expect_keyword(it, "while")?;
+    expect(it, "(")?;
+    let cond = parse_expr(it)?;
+    expect(it, ")")?;
+    expect(it, ";")?;
+    StmtKind::DoWhile(stmt, cond)
+  } else if consume_keyword(it, "switch")? {
+    expect(it, "(")?;
+    let cond = promote(parse_expr(it)?);
     expect(it, ")")?;
+    it.switches.push(Vec::new());
+    let stmt = parse_stmt(it);
+    it.switches.pop();
+    StmtKind::Switch(cond, Box::new(stmt?))
+  } else if consume_keyword(it, "case")? {
+    let expr = parse_ternary(it)?;
+    let n = eval_const(it, &expr)?;
+    expect(it, ":")?;
+    if let Err(e) = add_switch_label(it, Some(n), span_from(it, start)) {
+      report(it, e);
+    }
+    let stmt = Box::new(parse_stmt(it)?);
+    StmtKind::Case(n, stmt)
+  } else if consume_keyword(it, "default")? {
+    expect(it, ":")?;
+    if let Err(e) = add_switch_label(it, None, span_from(it, start)) {
+      report(it, e);
+    }
     let stmt = Box::new(parse_stmt(it)?);
-    Ok(Stmt::For(None, n2, None, stmt))
+    StmtKind::Default(stmt)
   } else if consume_keyword(it, "break")? {
     expect(it, ";")?;
-    Ok(Stmt::Break)
+    StmtKind::Break
   } else if consume_keyword(it, "continue")? {
     expect(it, ";")?;
-    Ok(Stmt::Cont)
-  } else if consume_keyword(it, "return")? {
-    let n = parse_expr(it)?;
+    StmtKind::Cont
+  } else if consume_keyword(it, "goto")? {
+    let name = expect_ident(it)?;
     expect(it, ";")?;
-    Ok(Stmt::Return(n))
+    StmtKind::Goto(name)
+  } else if consume_keyword(it, "return")? {
+    if consume(it, ";")? {
+      StmtKind::Return(None)
+    } else {
+      let mut n = parse_expr(it)?;
+      if let Some(ret_ty) = &it.ret_ty {
+        n = convert(n, ret_ty);
+      }
+      expect(it, ";")?;
+      StmtKind::Return(Some(n))
+    }
   } else if consume(it, "{")? {
     let stmts = parse_compound_stmt(it)?;
-    Ok(Stmt::Block(stmts))
+    StmtKind::Block(stmts)
   } else if consume(it, ";")? {
-    Ok(Stmt::Block(Vec::new()))
+    StmtKind::Block(Vec::new())
   } else {
     let n = parse_expr(it)?;
     expect(it, ";")?;
-    Ok(Stmt::Expr(n))
+    StmtKind::Expr(n)
+  };
+  Ok(Stmt::new(kind, span_from(it, start)))
+}
+
+/// Records a `case` label of value `n`, or a `default` label if `n` is `None`, in the innermost
+/// switch statement.
+fn add_switch_label(it: &mut Parser, n: Option<i64>, span: Span) -> Expected<()> {
+  let keyword = if n.is_some() { "case" } else { "default" };
+  let labels = match it.switches.last_mut() {
+    Some(labels) => labels,
+    None => return err!(span, "`{}` label not within a switch statement", keyword),
+  };
+  if let Some(&(_, prev_span)) = labels.iter().find(|(m, _)| *m == n) {
+    let message = match n {
+      Some(n) => format!("duplicate case value `{}`", n),
+      None => "multiple default labels in one switch".to_string(),
+    };
+    Err(
+      Diagnostic::error(message)
+        .at(span)
+        .with_label(prev_span, "previous label is here"),
+    )
+  } else {
+    labels.push((n, span));
+    Ok(())
   }
 }
 
 //' compound_stmt = stmt* "}"
-fn parse_compound_stmt(it: &mut Tokenizer) -> Expected<Vec<Stmt>> {
+fn parse_compound_stmt(it: &mut Parser) -> Expected<Vec<Stmt>> {
+  it.push_scope();
+  let stmts = parse_compound_stmt_impl(it);
+  it.pop_scope();
+  stmts
+}
+
+fn parse_compound_stmt_impl(it: &mut Parser) -> Expected<Vec<Stmt>> {
   let mut stmts = Vec::new();
   while !consume(it, "}")? {
-    stmts.push(parse_stmt(it)?);
+    if consume_eof(it)? {
+      let tok = it.current()?;
+      return err!(tok.span, "unexpected {}, expecting `}}`", tok.kind);
+    }
+    match parse_stmt(it) {
+      Ok(stmt) => stmts.push(stmt),
+      Err(e) => {
+        report(it, e);
+        synchronize(it, None);
+      }
+    }
   }
   Ok(stmts)
 }
 
 //' expr        = ternary
-fn parse_expr(it: &mut Tokenizer) -> Expected<AST> {
+fn parse_expr(it: &mut Parser) -> Expected<AST> {
   parse_ternary(it)
 }
 
+/// Parses an expression unless the current token is `end`, which is left unconsumed.
+fn parse_opt_expr(it: &mut Parser, end: &'static str) -> Expected<Option<AST>> {
+  if peek(it, end) {
+    Ok(None)
+  } else {
+    Ok(Some(parse_expr(it)?))
+  }
+}
+
 //' ternary     = assign ("?" expr ":" ternary)?
-fn parse_ternary(it: &mut Tokenizer) -> Expected<AST> {
+fn parse_ternary(it: &mut Parser) -> Expected<AST> {
   let cond = parse_assign(it)?;
   if consume(it, "?")? {
     let then = parse_expr(it)?;
     expect(it, ":")?;
     let else_ = parse_ternary(it)?;
-    Ok(AST::Ternary(
-      Box::new(cond),
-      Box::new(then),
-      Box::new(else_),
+    let span = cond.span.to(else_.span);
+    let (then, else_) = match Type::common_type(&then.ty, &else_.ty) {
+      Some(ty) => (convert(then, &ty), convert(else_, &ty)),
+      None => (then, else_),
+    };
+    Ok(new_ast(
+      it,
+      ASTKind::Ternary(Box::new(cond), Box::new(then), Box::new(else_)),
+      span,
     ))
   } else {
     Ok(cond)
   }
 }
 
-//' assign      = equality ("=" assign | "+=" assign | "-=" assign | "*=" assign | "/=" assign)?
-fn parse_assign(it: &mut Tokenizer) -> Expected<AST> {
-  let n = parse_equality(it)?;
+//' assign      = logor (assign_op assign)?
+//' assign_op   = "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>="
+fn parse_assign(it: &mut Parser) -> Expected<AST> {
+  static COMPOUND_OPS: [(&str, BinaryOp); 10] = [
+    ("+=", ASTKind::Add),
+    ("-=", ASTKind::Sub),
+    ("*=", ASTKind::Mul),
+    ("/=", ASTKind::Div),
+    ("%=", ASTKind::Mod),
+    ("&=", ASTKind::BitAnd),
+    ("|=", ASTKind::BitOr),
+    ("^=", ASTKind::BitXor),
+    ("<<=", ASTKind::Shl),
+    (">>=", ASTKind::Shr),
+  ];
+  let n = parse_logor(it)?;
   if consume(it, "=")? {
     let m = parse_assign(it)?;
-    Ok(AST::Assign(Box::new(n), Box::new(m)))
-  } else if consume(it, "+=")? {
-    // convert x+=y to x=x+y
-    let m = parse_assign(it)?;
-    let add = AST::Add(Box::new(n.clone()), Box::new(m));
-    Ok(AST::Assign(Box::new(n), Box::new(add)))
-  } else if consume(it, "-=")? {
-    // convert x-=y to x=x-y
-    let m = parse_assign(it)?;
-    let sub = AST::Sub(Box::new(n.clone()), Box::new(m));
-    Ok(AST::Assign(Box::new(n), Box::new(sub)))
-  } else if consume(it, "*=")? {
-    // convert x*=y to x=x*y
-    let m = parse_assign(it)?;
-    let mul = AST::Mul(Box::new(n.clone()), Box::new(m));
-    Ok(AST::Assign(Box::new(n), Box::new(mul)))
-  } else if consume(it, "/=")? {
-    // convert x/=y to x=x/y
-    let m = parse_assign(it)?;
-    let div = AST::Div(Box::new(n.clone()), Box::new(m));
-    Ok(AST::Assign(Box::new(n), Box::new(div)))
-  } else {
-    Ok(n)
+    return Ok(binary(it, ASTKind::Assign, n, m));
+  }
+  for (op, kind) in COMPOUND_OPS {
+    if consume(it, op)? {
+      let m = parse_assign(it)?;
+      let span = n.span.to(m.span);
+      return Ok(compound_assign(it, kind, n, m, span));
+    }
+  }
+  Ok(n)
+}
+
+/// Creates `n op= m`, where `op` is the operator of `kind`. The old value of `n` and `m` are
+/// converted as for `n op m`, and the result is converted back to the type of `n` and assigned
+/// to it.
+fn compound_assign(it: &Parser, kind: BinaryOp, n: AST, m: AST, span: Span) -> AST {
+  let value = AST::new(ASTKind::AssignedValue(n.ty.clone()), n.span, n.ty.clone());
+  let rhs = convert(binary(it, kind, value, m), &n.ty);
+  new_ast(
+    it,
+    ASTKind::CompoundAssign(Box::new(n), Box::new(rhs)),
+    span,
+  )
+}
+
+//' logor       = logand ("||" logand)*
+fn parse_logor(it: &mut Parser) -> Expected<AST> {
+  let mut n = parse_logand(it)?;
+  while consume(it, "||")? {
+    let m = parse_logand(it)?;
+    n = binary(it, ASTKind::LogOr, n, m);
+  }
+  Ok(n)
+}
+
+//' logand      = bitor ("&&" bitor)*
+fn parse_logand(it: &mut Parser) -> Expected<AST> {
+  let mut n = parse_bitor(it)?;
+  while consume(it, "&&")? {
+    let m = parse_bitor(it)?;
+    n = binary(it, ASTKind::LogAnd, n, m);
+  }
+  Ok(n)
+}
+
+//' bitor       = bitxor ("|" bitxor)*
+fn parse_bitor(it: &mut Parser) -> Expected<AST> {
+  let mut n = parse_bitxor(it)?;
+  while consume(it, "|")? {
+    let m = parse_bitxor(it)?;
+    n = binary(it, ASTKind::BitOr, n, m);
+  }
+  Ok(n)
+}
+
+//' bitxor      = bitand ("^" bitand)*
+fn parse_bitxor(it: &mut Parser) -> Expected<AST> {
+  let mut n = parse_bitand(it)?;
+  while consume(it, "^")? {
+    let m = parse_bitand(it)?;
+    n = binary(it, ASTKind::BitXor, n, m);
+  }
+  Ok(n)
+}
+
+//' bitand      = equality ("&" equality)*
+fn parse_bitand(it: &mut Parser) -> Expected<AST> {
+  let mut n = parse_equality(it)?;
+  while consume(it, "&")? {
+    let m = parse_equality(it)?;
+    n = binary(it, ASTKind::BitAnd, n, m);
   }
+  Ok(n)
 }
 
 //' equality    = relational ("==" relational | "!=" relational)*
-fn parse_equality(it: &mut Tokenizer) -> Expected<AST> {
+fn parse_equality(it: &mut Parser) -> Expected<AST> {
   let n = parse_relational(it)?;
   parse_equality_impl(it, n)
 }
 
-fn parse_equality_impl(it: &mut Tokenizer, n: AST) -> Expected<AST> {
+fn parse_equality_impl(it: &mut Parser, n: AST) -> Expected<AST> {
   if consume(it, "==")? {
     let m = parse_relational(it)?;
-    parse_equality_impl(it, AST::Eq(Box::new(n), Box::new(m)))
+    parse_equality_impl(it, binary(it, ASTKind::Eq, n, m))
   } else if consume(it, "!=")? {
     let m = parse_relational(it)?;
-    parse_equality_impl(it, AST::Ne(Box::new(n), Box::new(m)))
+    parse_equality_impl(it, binary(it, ASTKind::Ne, n, m))
   } else {
     Ok(n)
   }
 }
 
-//' relational  = add ("<" add | "<=" add | ">" add | ">=" add)*
-fn parse_relational(it: &mut Tokenizer) -> Expected<AST> {
-  let n = parse_add(it)?;
+//' relational  = shift ("<" shift | "<=" shift | ">" shift | ">=" shift)*
+fn parse_relational(it: &mut Parser) -> Expected<AST> {
+  let n = parse_shift(it)?;
   parse_relational_impl(it, n)
 }
 
-fn parse_relational_impl(it: &mut Tokenizer, n: AST) -> Expected<AST> {
+fn parse_relational_impl(it: &mut Parser, n: AST) -> Expected<AST> {
   if consume(it, "<")? {
-    let m = parse_add(it)?;
-    parse_relational_impl(it, AST::Lt(Box::new(n), Box::new(m)))
+    let m = parse_shift(it)?;
+    parse_relational_impl(it, binary(it, ASTKind::Lt, n, m))
   } else if consume(it, "<=")? {
-    let m = parse_add(it)?;
-    parse_relational_impl(it, AST::Le(Box::new(n), Box::new(m)))
+    let m = parse_shift(it)?;
+    parse_relational_impl(it, binary(it, ASTKind::Le, n, m))
   } else if consume(it, ">")? {
-    let m = parse_add(it)?;
-    parse_relational_impl(it, AST::Lt(Box::new(m), Box::new(n)))
+    let m = parse_shift(it)?;
+    parse_relational_impl(it, binary(it, ASTKind::Lt, m, n))
   } else if consume(it, ">=")? {
+    let m = parse_shift(it)?;
+    parse_relational_impl(it, binary(it, ASTKind::Le, m, n))
+  } else {
+    Ok(n)
+  }
+}
+
+//' shift       = add ("<<" add | ">>" add)*
+fn parse_shift(it: &mut Parser) -> Expected<AST> {
+  let n = parse_add(it)?;
+  parse_shift_impl(it, n)
+}
+
+fn parse_shift_impl(it: &mut Parser, n: AST) -> Expected<AST> {
+  if consume(it, "<<")? {
+    let m = parse_add(it)?;
+    parse_shift_impl(it, binary(it, ASTKind::Shl, n, m))
+  } else if consume(it, ">>")? {
     let m = parse_add(it)?;
-    parse_relational_impl(it, AST::Le(Box::new(m), Box::new(n)))
+    parse_shift_impl(it, binary(it, ASTKind::Shr, n, m))
   } else {
     Ok(n)
   }
 }
 
 //' add         = mul ("+" mul | "-" mul)*
-fn parse_add(it: &mut Tokenizer) -> Expected<AST> {
+fn parse_add(it: &mut Parser) -> Expected<AST> {
   let n = parse_mul(it)?;
   parse_add_impl(it, n)
 }
 
-fn parse_add_impl(it: &mut Tokenizer, n: AST) -> Expected<AST> {
+fn parse_add_impl(it: &mut Parser, n: AST) -> Expected<AST> {
   if consume(it, "+")? {
     let m = parse_mul(it)?;
-    parse_add_impl(it, AST::Add(Box::new(n), Box::new(m)))
+    parse_add_impl(it, binary(it, ASTKind::Add, n, m))
   } else if consume(it, "-")? {
     let m = parse_mul(it)?;
-    parse_add_impl(it, AST::Sub(Box::new(n), Box::new(m)))
+    parse_add_impl(it, binary(it, ASTKind::Sub, n, m))
   } else {
     Ok(n)
   }
 }
 
-//' mul         = unary ("*" unary | "/" unary)*
-fn parse_mul(it: &mut Tokenizer) -> Expected<AST> {
+//' mul         = unary ("*" unary | "/" unary | "%" unary)*
+fn parse_mul(it: &mut Parser) -> Expected<AST> {
   let n = parse_unary(it)?;
   parse_mul_impl(it, n)
 }
 
-fn parse_mul_impl(it: &mut Tokenizer, n: AST) -> Expected<AST> {
+fn parse_mul_impl(it: &mut Parser, n: AST) -> Expected<AST> {
   if consume(it, "*")? {
     let m = parse_unary(it)?;
-    parse_mul_impl(it, AST::Mul(Box::new(n), Box::new(m)))
+    parse_mul_impl(it, binary(it, ASTKind::Mul, n, m))
   } else if consume(it, "/")? {
     let m = parse_unary(it)?;
-    parse_mul_impl(it, AST::Div(Box::new(n), Box::new(m)))
+    parse_mul_impl(it, binary(it, ASTKind::Div, n, m))
+  } else if consume(it, "%")? {
+    let m = parse_unary(it)?;
+    parse_mul_impl(it, binary(it, ASTKind::Mod, n, m))
   } else {
     Ok(n)
   }
 }
 
-//' unary       = ("+" | "-" | "&" | "*" | "++" | "--") unary
+//' unary       = ("+" | "-" | "&" | "*" | "!" | "~" | "++" | "--") unary
+//'             | "sizeof" "(" type_name ")"
+//'             | "sizeof" unary
+//'             | "_Alignof" "(" type_name ")"
 //'             | cast
 //'             | postfix
-fn parse_unary(it: &mut Tokenizer) -> Expected<AST> {
+fn parse_unary(it: &mut Parser) -> Expected<AST> {
+  let start = it.span();
   if consume(it, "+")? {
     parse_unary(it)
   } else if consume(it, "-")? {
-    let n = AST::Num(0);
-    let m = parse_unary(it)?;
-    Ok(AST::Sub(Box::new(n), Box::new(m)))
+    let n = promote(parse_unary(it)?);
+    Ok(new_ast(it, ASTKind::Neg(Box::new(n)), span_from(it, start)))
   } else if consume(it, "&")? {
     let n = parse_unary(it)?;
-    Ok(AST::Addr(Box::new(n)))
+    Ok(new_ast(
+      it,
+      ASTKind::Addr(Box::new(n)),
+      span_from(it, start),
+    ))
   } else if consume(it, "*")? {
     let n = parse_unary(it)?;
-    Ok(AST::Deref(Box::new(n)))
+    Ok(new_ast(
+      it,
+      ASTKind::Deref(Box::new(n)),
+      span_from(it, start),
+    ))
+  } else if consume(it, "!")? {
+    let n = parse_unary(it)?;
+    Ok(new_ast(it, ASTKind::Not(Box::new(n)), span_from(it, start)))
+  } else if consume(it, "~")? {
+    let n = promote(parse_unary(it)?);
+    Ok(new_ast(
+      it,
+      ASTKind::BitNot(Box::new(n)),
+      span_from(it, start),
+    ))
   } else if consume(it, "++")? {
-    // convert ++i to i=i+1
+    // convert ++i to i+=1
     let n = parse_unary(it)?;
-    let one = AST::Num(1);
-    let add = AST::Add(Box::new(n.clone()), Box::new(one));
-    Ok(AST::Assign(Box::new(n), Box::new(add)))
+    let one = new_ast(it, ASTKind::Num(1, Type::Int), start);
+    let span = span_from(it, start);
+    Ok(compound_assign(it, ASTKind::Add, n, one, span))
   } else if consume(it, "--")? {
-    // convert --i to i=i-1
+    // convert --i to i-=1
     let n = parse_unary(it)?;
-    let one = AST::Num(1);
-    let sub = AST::Sub(Box::new(n.clone()), Box::new(one));
-    Ok(AST::Assign(Box::new(n), Box::new(sub)))
-  } else if let Some(n) = try_parse(it, parse_cast) {
-    Ok(n)
+    let one = new_ast(it, ASTKind::Num(1, Type::Int), start);
+    let span = span_from(it, start);
+    Ok(compound_assign(it, ASTKind::Sub, n, one, span))
+  } else if consume_keyword(it, "sizeof")? {
+    let ty = if is_cast(it) {
+      expect(it, "(")?;
+      let ty = parse_type_name(it)?;
+      expect(it, ")")?;
+      ty
+    } else {
+      let n = parse_unary(it)?;
+      type_of(it, &n.kind, n.span)?
+    };
+    let span = span_from(it, start);
+    layout_of(it, &ty, span)?;
+    Ok(new_ast(it, ASTKind::SizeOf(ty), span))
+  } else if consume_keyword(it, "_Alignof")? {
+    expect(it, "(")?;
+    let ty = parse_type_name(it)?;
+    expect(it, ")")?;
+    let span = span_from(it, start);
+    layout_of(it, &ty, span)?;
+    Ok(new_ast(it, ASTKind::AlignOf(ty), span))
+  } else if is_cast(it) {
+    parse_cast(it)
   } else {
     parse_postfix(it)
   }
 }
 
-//' cast        = "(" declspec ")" unary
-fn parse_cast(it: &mut Tokenizer) -> Expected<AST> {
+//' cast        = "(" type_name ")" unary
+fn parse_cast(it: &mut Parser) -> Expected<AST> {
+  let start = it.span();
   expect(it, "(")?;
-  let ty = parse_declspec(it)?;
+  let ty = parse_type_name(it)?;
   expect(it, ")")?;
   let n = parse_unary(it)?;
-  Ok(AST::Cast(ty, Box::new(n)))
+  Ok(new_ast(
+    it,
+    ASTKind::Cast(ty, Box::new(n)),
+    span_from(it, start),
+  ))
+}
+
+//' type_name   = declspec abstract_declarator
+/// Parses a type name, as in casts and `sizeof`, such as `int (*)[3]`.
+pub fn parse_type_name(it: &mut Parser) -> Expected<Type> {
+  let ty = parse_declspec(it)?;
+  let ty = parse_abstract_declarator(it, ty)?;
+  Ok(without_param_names(ty))
 }
 
-//' postfix     = primary ("[" expr "]" | "++" | "--" | "." ident)*
-fn parse_postfix(it: &mut Tokenizer) -> Expected<AST> {
+//' postfix     = primary ("(" fun_args | "[" expr "]" | "++" | "--" | "." ident | "->" ident)*
+fn parse_postfix(it: &mut Parser) -> Expected<AST> {
   let mut n = parse_primary(it)?;
   loop {
-    if consume(it, "[")? {
+    let op = it.span();
+    if consume(it, "(")? {
+      let args = parse_fun_args(it)?;
+      let args = match callee_type(&n.ty) {
+        Some(Type::FunTy(_, param_tys, _, variadic))
+          if param_tys.len() == args.len() || *variadic && param_tys.len() < args.len() =>
+        {
+          // arguments without parameters undergo the default argument promotions
+          args
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| match param_tys.get(i) {
+              Some(ty) => convert(arg, ty),
+              None => promote_arg(arg),
+            })
+            .collect()
+        }
+        _ => args,
+      };
+      let span = span_from(it, n.span);
+      n = new_ast(it, ASTKind::Call(Box::new(n), args), span);
+    } else if consume(it, "[")? {
       // convert a[i] to *(a+i)
       let m = parse_expr(it)?;
       expect(it, "]")?;
-      let add = AST::Add(Box::new(n), Box::new(m));
-      n = AST::Deref(Box::new(add));
+      let span = span_from(it, n.span);
+      let add = binary(it, ASTKind::Add, n, m);
+      n = new_ast(it, ASTKind::Deref(Box::new(add)), span);
     } else if consume(it, "++")? {
-      // convert i++ to (i=i+1)-1
-      let one = AST::Num(1);
-      let add = AST::Add(Box::new(n.clone()), Box::new(one.clone()));
-      let assign = AST::Assign(Box::new(n), Box::new(add));
-      n = AST::Sub(Box::new(assign), Box::new(one));
+      n = postfix_assign(it, ASTKind::Add, n, op);
     } else if consume(it, "--")? {
-      // convert i-- to (i=i-1)+1
-      let one = AST::Num(1);
-      let sub = AST::Sub(Box::new(n.clone()), Box::new(one.clone()));
-      let assign = AST::Assign(Box::new(n), Box::new(sub));
-      n = AST::Add(Box::new(assign), Box::new(one));
+      n = postfix_assign(it, ASTKind::Sub, n, op);
     } else if consume(it, ".")? {
       let name = expect_ident(it)?;
-      n = AST::Dot(Box::new(n), name);
+      let span = span_from(it, n.span);
+      n = new_ast(it, ASTKind::Dot(Box::new(n), name), span);
+    } else if consume(it, "->")? {
+      // convert p->x to (*p).x
+      let name = expect_ident(it)?;
+      let span = span_from(it, n.span);
+      let deref = new_ast(it, ASTKind::Deref(Box::new(n)), span);
+      n = new_ast(it, ASTKind::Dot(Box::new(deref), name), span);
     } else {
       break Ok(n);
     }
   }
 }
 
+/// Creates `n++` if `kind` is `Add`, or `n--` if it is `Sub`, at the operator `op`. The old
+/// value of `n` plus or minus 1 is converted back to the type of `n` and assigned to it, while
+/// the expression yields the old value.
+fn postfix_assign(it: &Parser, kind: BinaryOp, n: AST, op: Span) -> AST {
+  let span = n.span.to(op);
+  let value = AST::new(ASTKind::AssignedValue(n.ty.clone()), n.span, n.ty.clone());
+  let one = new_ast(it, ASTKind::Num(1, Type::Int), op);
+  let rhs = convert(binary(it, kind, value, one), &n.ty);
+  new_ast(it, ASTKind::PostfixAssign(Box::new(n), Box::new(rhs)), span)
+}
+
 //' primary     = "(" "{" compound_stmt ")"
 //'             | "(" expr ")"
-//'             | ident "(" fun_args
+//'             | builtin
 //'             | ident
 //'             | num
-//'             | str
-fn parse_primary(it: &mut Tokenizer) -> Expected<AST> {
+//'             | str+
+fn parse_primary(it: &mut Parser) -> Expected<AST> {
+  let start = it.span();
   if consume(it, "(")? {
     if consume(it, "{")? {
       // [GNU] parse statement expression
       let stmts = parse_compound_stmt(it)?;
       expect(it, ")")?;
       if stmts.is_empty() {
-        err!("GNU statement expression is empty")
+        err!(span_from(it, start), "GNU statement expression is empty")
       } else {
-        Ok(AST::Block(stmts))
+        Ok(new_ast(it, ASTKind::Block(stmts), span_from(it, start)))
       }
     } else {
       let n = parse_expr(it)?;
       expect(it, ")")?;
       Ok(n)
     }
+  } else if let Some(n) = parse_builtin(it)? {
+    Ok(n)
   } else if let Some(name) = consume_ident(it)? {
-    if consume(it, "(")? {
-      let args = parse_fun_args(it)?;
-      Ok(AST::Call(name, args))
+    if let Some(n) = it.scope.get_enumerator(&name) {
+      Ok(new_ast(it, ASTKind::Enumerator(name, n), start))
     } else {
-      Ok(AST::Ident(name))
+      Ok(new_ast(it, ASTKind::Ident(name), start))
+    }
+  } else if let Some((n, ty)) = consume_num(it)? {
+    Ok(new_ast(it, ASTKind::Num(n, ty), start))
+  } else if let Some((x, ty)) = consume_float(it)? {
+    Ok(new_ast(it, ASTKind::Float(x, ty), start))
+  } else if let Some(mut s) = consume_str(it)? {
+    // adjacent string literals are concatenated
+    while let Some(next) = consume_str(it)? {
+      s.extend(next);
     }
-  } else if let Some(n) = consume_num(it)? {
-    Ok(AST::Num(n))
-  } else if let Some(s) = consume_str(it)? {
-    Ok(AST::Str(s))
+    Ok(new_ast(it, ASTKind::Str(s), span_from(it, start)))
   } else {
-    err!("unexpected token, expecting `(`, identifier, number or string")
+    let tok = it.current()?;
+    err!(
+      tok.span,
+      "unexpected {}, expecting `(`, identifier, number or string",
+      tok.kind
+    )
   }
 }
 
 //' fun_args    = (expr ("," expr)*)? ")"
-fn parse_fun_args(it: &mut Tokenizer) -> Expected<Vec<AST>> {
+fn parse_fun_args(it: &mut Parser) -> Expected<Vec<AST>> {
   let mut args = Vec::new();
   if consume(it, ")")? {
     Ok(args)
@@ -697,3 +2492,581 @@ fn parse_fun_args(it: &mut Tokenizer) -> Expected<Vec<AST>> {
     Ok(args)
   }
 }
+
+//' builtin     = "__builtin_va_start" "(" expr "," ident ")"
+//'             | "__builtin_va_arg" "(" expr "," type_name ")"
+//'             | "__builtin_va_end" "(" expr ")"
+fn parse_builtin(it: &mut Parser) -> Expected<Option<AST>> {
+  static BUILTINS: [&str; 3] = ["__builtin_va_start", "__builtin_va_arg", "__builtin_va_end"];
+  let start = it.span();
+  let name = match it.current()?.kind {
+    TokenKind::Ident(name) if BUILTINS.contains(&name.as_str()) => name,
+    _ => return Ok(None),
+  };
+  it.advance();
+  expect(it, "(")?;
+  let ap = Box::new(parse_expr(it)?);
+  let kind = match name.as_str() {
+    "__builtin_va_start" => {
+      expect(it, ",")?;
+      // the last named parameter is not needed to find the variadic arguments
+      expect_ident(it)?;
+      ASTKind::VaStart(ap)
+    }
+    "__builtin_va_arg" => {
+      expect(it, ",")?;
+      ASTKind::VaArg(ap, parse_type_name(it)?)
+    }
+    _ => ASTKind::VaEnd(ap),
+  };
+  expect(it, ")")?;
+  Ok(Some(new_ast(it, kind, span_from(it, start))))
+}
+
+#[test]
+fn test_spans() {
+  let input = "int main() {\n  return 1 + x;\n}";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  assert_eq!(toplevels[0].span, Span::new(0, 0, input.len()));
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind {
+    let ret = &body[0];
+    assert_eq!(&input[ret.span.start..ret.span.end], "return 1 + x;");
+    if let StmtKind::Return(Some(expr)) = &ret.kind {
+      assert_eq!(&input[expr.span.start..expr.span.end], "1 + x");
+    } else {
+      unreachable!();
+    }
+  } else {
+    unreachable!();
+  }
+
+  let errs = parse(Tokenizer::new("int main() { return 1 +; }")).unwrap_err();
+  assert_eq!(errs[0].span(), Some(Span::new(0, 23, 24)));
+}
+
+#[test]
+fn test_error_recovery() {
+  let input = "int x = ;
+int f(int a b) { return a; }
+struct S { int m };
+int main() {
+  int y = 1 +;
+  if (y) { y = * ; }
+  for (;;) { break }
+  return y;
+}
+int g() { return 0; }
+int z";
+  let (toplevels, errs) = parse_with_recovery(Tokenizer::new(input));
+  let lines: Vec<_> = errs
+    .iter()
+    .map(|e| input[..e.span().unwrap().start].lines().count())
+    .collect();
+  assert_eq!(lines, [1, 2, 3, 5, 6, 7, 11]);
+  let names: Vec<_> = toplevels
+    .iter()
+    .filter_map(|toplevel| match &toplevel.kind {
+      TopLevelKind::FunDef(_, name, ..) => Some(name.as_str()),
+      _ => None,
+    })
+    .collect();
+  assert_eq!(names, ["main", "g"]);
+}
+
+#[test]
+fn test_typedef() {
+  let input = "typedef int t, *p;
+typedef struct S { t a; } S;
+int main() { t x; p y; S z; int t; t = (p)0 == y; return t; }
+int f() { t t; t = 1; { typedef char t; t c; c = (t)x; } return 0; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  if let TopLevelKind::StructDef(Type::Struct(Some(name), Some((tys, _)))) = &toplevels[0].kind {
+    assert_eq!(name, "S");
+    assert_eq!(tys, &[Type::Int]);
+  } else {
+    unreachable!();
+  }
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[1].kind {
+    let tys: Vec<_> = body
+      .iter()
+      .filter_map(|stmt| match &stmt.kind {
+        StmtKind::VarDef(decls, _) => Some(decls[0].0.clone()),
+        _ => None,
+      })
+      .collect();
+    let s = Type::Struct(Some("S".to_string()), None);
+    assert_eq!(
+      tys,
+      [Type::Int, Type::Pointer(Box::new(Type::Int)), s, Type::Int]
+    );
+  } else {
+    unreachable!();
+  }
+
+  // `t` names a variable once it is declared as one
+  let errs = parse(Tokenizer::new("typedef int t; int main() { int t; t x; }")).unwrap_err();
+  assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_enum() {
+  let input = "enum e { A, B = 5, C, D = (B + C) * 2, };
+int main() { enum e x; int B; return A + B + C + D; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let mut operands = Vec::new();
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind {
+    if let StmtKind::Return(Some(expr)) = &body[2].kind {
+      let mut expr = expr;
+      while let ASTKind::Add(n, m) = &expr.kind {
+        operands.push(m.kind.clone());
+        expr = n;
+      }
+      operands.push(expr.kind.clone());
+    }
+  }
+  let operands: Vec<_> = operands
+    .iter()
+    .rev()
+    .map(|kind| match kind {
+      ASTKind::Enumerator(name, n) => format!("{}={}", name, n),
+      ASTKind::Ident(name) => name.clone(),
+      _ => unreachable!(),
+    })
+    .collect();
+  assert_eq!(operands, ["A=0", "B", "C=6", "D=22"]);
+
+  let errs = parse(Tokenizer::new("int x; enum { A = x, B = 1 / 0 };")).unwrap_err();
+  assert_eq!(errs.len(), 2);
+
+  let input = "enum e { A }; int f() { enum e { B } x; return B; }";
+  assert!(parse(Tokenizer::new(input)).is_ok());
+  // `unsigned int` arithmetic wraps around at 32 bits, and conversion to `int` truncates
+  let input = "enum { A = (unsigned)-1 >> 28, B = 0u - 1 > 0, C = (int)4294967297 };";
+  assert!(parse(Tokenizer::new(input)).is_ok());
+  for input in [
+    "enum Undefined x;",
+    "enum e { A }; enum e { B };",
+    "struct s { int a; }; enum s x;",
+    "enum { A = 2147483647, B };",
+    "enum { A = -2147483649 };",
+    "enum { A = 2147483647 + 1 };",
+  ] {
+    assert_eq!(
+      parse(Tokenizer::new(input)).unwrap_err().len(),
+      1,
+      "{}",
+      input
+    );
+  }
+}
+
+#[test]
+fn test_sizeof() {
+  let input = "int main() { int x[3]; char *p; return sizeof x + sizeof(*p) + sizeof(int); }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let mut operands = Vec::new();
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind {
+    // the `unsigned long` sum is converted to the return type
+    if let StmtKind::Return(Some(expr)) = &body[2].kind {
+      let ASTKind::Cast(Type::Int, expr) = &expr.kind else {
+        unreachable!()
+      };
+      let mut expr: &AST = expr;
+      while let ASTKind::Add(n, m) = &expr.kind {
+        operands.push(m.kind.clone());
+        expr = n;
+      }
+      operands.push(expr.kind.clone());
+    }
+  }
+  let tys: Vec<_> = operands
+    .iter()
+    .rev()
+    .map(|kind| match kind {
+      ASTKind::SizeOf(ty) => ty.clone(),
+      _ => unreachable!(),
+    })
+    .collect();
+  let int_array = Type::Array(Box::new(Type::Int), Some(3));
+  assert_eq!(tys, [int_array, Type::Char, Type::Int]);
+
+  let input = "enum { A = sizeof(struct { char a; int b; }), B = _Alignof(char) };";
+  assert!(parse(Tokenizer::new(input)).is_ok());
+  let errs = parse(Tokenizer::new("struct s; int x = sizeof(struct s);")).unwrap_err();
+  assert_eq!(errs.len(), 1);
+
+  // sizes which do not fit in 32 bits are rejected
+  for input in [
+    "int main() { int a[2000000000]; return 0; }",
+    "int a[2][2000000000];",
+    "struct s { char a[4000000000]; char b[4000000000]; };",
+    "union u { char a[4294967295]; int b; };",
+  ] {
+    assert_eq!(
+      parse(Tokenizer::new(input)).unwrap_err().len(),
+      1,
+      "{}",
+      input
+    );
+  }
+}
+
+#[test]
+fn test_float() {
+  let input = "int main() { float f; return f + 1 < .5e1 + 2.f; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind else {
+    unreachable!()
+  };
+  let StmtKind::Return(Some(expr)) = &body[1].kind else {
+    unreachable!()
+  };
+  let ASTKind::Lt(n, m) = &expr.kind else {
+    unreachable!()
+  };
+  let ASTKind::Cast(ty, n) = &n.kind else {
+    unreachable!()
+  };
+  let ASTKind::Add(lhs, rhs) = &n.kind else {
+    unreachable!()
+  };
+  let mut tys = vec![ty.clone(), lhs.ty.clone(), rhs.ty.clone()];
+  let ASTKind::Add(lhs, rhs) = &m.kind else {
+    unreachable!()
+  };
+  tys.extend([lhs.ty.clone(), rhs.ty.clone()]);
+  // `f + 1` is a `float`, which is converted to `double` to be compared with `5.0 + 2.0f`
+  assert_eq!(
+    tys,
+    [
+      Type::Double,
+      Type::Float,
+      Type::Float,
+      Type::Double,
+      Type::Double
+    ]
+  );
+
+  let input = "enum { A = (int)2.5 * 2 + (1.5 < 2) }; int main() { return A; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind else {
+    unreachable!()
+  };
+  let StmtKind::Return(Some(expr)) = &body[0].kind else {
+    unreachable!()
+  };
+  assert!(matches!(expr.kind, ASTKind::Enumerator(_, 5)));
+  // `-0.0` is a negative zero, which `0.0 - 0.0` is not
+  let input = "enum { B = 1 / -0.0 < 0 }; int main() { return B; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind else {
+    unreachable!()
+  };
+  let StmtKind::Return(Some(expr)) = &body[0].kind else {
+    unreachable!()
+  };
+  assert!(matches!(expr.kind, ASTKind::Enumerator(_, 1)));
+  let errs = parse(Tokenizer::new("long float x;")).unwrap_err();
+  assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_literal() {
+  let input = "int main() { 10; 0x1F; 017; 0b101u; 10l; 0xffffffff; 0xffffffffffffffff; 1ULL; '\\n'; '\\xff'; \
+               \"a\\0b\" \"\\101\\x42\"; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let (mut nums, mut strs) = (Vec::new(), Vec::new());
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[0].kind {
+    for stmt in body {
+      match &stmt.kind {
+        StmtKind::Expr(AST {
+          kind: ASTKind::Num(n, ty),
+          ..
+        }) => nums.push((*n, ty.clone())),
+        StmtKind::Expr(AST {
+          kind: ASTKind::Str(s),
+          ..
+        }) => strs.push(s.clone()),
+        _ => {}
+      }
+    }
+  }
+  assert_eq!(
+    nums,
+    [
+      (10, Type::Int),
+      (31, Type::Int),
+      (15, Type::Int),
+      (5, Type::UInt),
+      (10, Type::Long),
+      // a hexadecimal constant too large for a signed type is of the unsigned type of the same
+      // size
+      (0xffffffff, Type::UInt),
+      (-1, Type::ULong),
+      (1, Type::ULongLong),
+      (10, Type::Int),
+      // `char` is signed
+      (-1, Type::Int),
+    ]
+  );
+  assert_eq!(strs, [b"a\0bAB"]);
+
+  for input in [
+    "int x = 08;",
+    "int x = 1lul;",
+    "int x = 0x;",
+    "int x = 18446744073709551616;",
+    "int x = 9223372036854775808;",
+    "int x = '';",
+    "char *s = \"\\x100\";",
+  ] {
+    assert_eq!(
+      parse(Tokenizer::new(input)).unwrap_err().len(),
+      1,
+      "{}",
+      input
+    );
+  }
+}
+
+#[test]
+fn test_variadic() {
+  let input = "int f(int n, ...); int main() { char c; float x; f(c, c, x); \
+               __builtin_va_list ap; return __builtin_va_arg(ap, int); }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  assert!(matches!(
+    toplevels[0].kind,
+    TopLevelKind::FunDecl(_, _, _, true, _)
+  ));
+  let mut tys = Vec::new();
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[1].kind {
+    if let StmtKind::Expr(AST {
+      kind: ASTKind::Call(_, args),
+      ..
+    }) = &body[2].kind
+    {
+      tys.extend(args.iter().map(|arg| arg.ty.clone()));
+    }
+  }
+  // variadic arguments undergo the default argument promotions
+  assert_eq!(tys, [Type::Int, Type::Int, Type::Double]);
+
+  // a variadic function needs a named parameter
+  let errs = parse(Tokenizer::new("int f(...);")).unwrap_err();
+  assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_fun_pointer() {
+  let input = "int add(int a, int b); int (*fp)(int a, int b); int (*fps[2])(int a); \
+               int apply(int f(int a), int x) { return f(x) + (*f)(x); } \
+               int main() { return fp(1, 2) + (&add)(3, 4); }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let fun_ty = |n| Type::FunTy(Box::new(Type::Int), vec![Type::Int; n], Vec::new(), false);
+  let ptr = |ty| Type::Pointer(Box::new(ty));
+  let mut tys = Vec::new();
+  for toplevel in &toplevels {
+    match &toplevel.kind {
+      TopLevelKind::VarDef(ty, ..) => tys.push(ty.clone()),
+      TopLevelKind::FunDef(_, _, param_tys, ..) => tys.extend(param_tys.iter().cloned()),
+      _ => {}
+    }
+  }
+  let with_names = |ty: Type, names: &[&str]| match ty {
+    Type::FunTy(ret_ty, param_tys, _, variadic) => Type::FunTy(
+      ret_ty,
+      param_tys,
+      names.iter().map(|name| name.to_string()).collect(),
+      variadic,
+    ),
+    ty => ty,
+  };
+  assert_eq!(
+    tys,
+    [
+      ptr(with_names(fun_ty(2), &["a", "b"])),
+      Type::Array(Box::new(ptr(with_names(fun_ty(1), &["a"]))), Some(2)),
+      // the parameter names of the type of a parameter are dropped
+      ptr(fun_ty(1)),
+      Type::Int,
+    ]
+  );
+  let TopLevelKind::FunDef(.., body, _) = &toplevels[4].kind else {
+    unreachable!()
+  };
+  let StmtKind::Return(Some(expr)) = &body[0].kind else {
+    unreachable!()
+  };
+  assert_eq!(expr.ty, Type::Int);
+}
+
+#[test]
+fn test_declarator() {
+  let type_name = |input| parse_type_name(&mut Parser::new(Tokenizer::new(input))).unwrap();
+  let ptr = |ty| Type::Pointer(Box::new(ty));
+  let array = |ty, n| Type::Array(Box::new(ty), Some(n));
+  let fun_ty = |ret_ty, param_tys| Type::FunTy(Box::new(ret_ty), param_tys, Vec::new(), false);
+  assert_eq!(type_name("char *"), ptr(Type::Char));
+  assert_eq!(type_name("int [2][3]"), array(array(Type::Int, 3), 2));
+  assert_eq!(type_name("int (*)[3]"), ptr(array(Type::Int, 3)));
+  assert_eq!(type_name("int *[3]"), array(ptr(Type::Int), 3));
+  assert_eq!(
+    type_name("int *(*)(int, char *)"),
+    ptr(fun_ty(ptr(Type::Int), vec![Type::Int, ptr(Type::Char)]))
+  );
+  assert_eq!(
+    type_name("int (*(*)(int))(void)"),
+    ptr(fun_ty(ptr(fun_ty(Type::Int, vec![])), vec![Type::Int]))
+  );
+  assert_eq!(
+    type_name("int (int [3])"),
+    fun_ty(Type::Int, vec![ptr(Type::Int)])
+  );
+  // an array size is an integer constant expression
+  assert_eq!(type_name("int [2*3]"), array(Type::Int, 6));
+  assert_eq!(
+    type_name("char [sizeof(int) ? 5 : 1]"),
+    array(Type::Char, 5)
+  );
+  let input = "enum { N = 4 }; int a[N];";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let TopLevelKind::VarDef(ty, ..) = &toplevels[0].kind else {
+    unreachable!()
+  };
+  assert_eq!(ty, &array(Type::Int, 4));
+  for input in ["int a[-1];", "int x; int a[x];", "int a[1.5];"] {
+    assert!(parse(Tokenizer::new(input)).is_err(), "{}", input);
+  }
+
+  let input =
+    "int f(int, int (*)(int), char [3][4]); int g(int a[2][3]) { return (char *)a - (char *)0; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let TopLevelKind::FunDecl(_, _, param_tys, ..) = &toplevels[0].kind else {
+    unreachable!()
+  };
+  let fp = ptr(fun_ty(Type::Int, vec![Type::Int]));
+  assert_eq!(param_tys, &[Type::Int, fp, ptr(array(Type::Char, 4))]);
+  let TopLevelKind::FunDef(_, _, param_tys, ..) = &toplevels[1].kind else {
+    unreachable!()
+  };
+  assert_eq!(param_tys, &[ptr(array(Type::Int, 3))]);
+  assert!(parse(Tokenizer::new("int f(int) { return 0; }")).is_err());
+  // a nested declarator is parsed twice, but reports its errors once
+  let errs = parse(Tokenizer::new("int (*f(enum e { A = 1 / 0 } e))(int);")).unwrap_err();
+  assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_initializer() {
+  // describes the structure of an initializer, where `e` is an expression and `0` zero-fills
+  fn shape(init: &Init) -> String {
+    match init {
+      Init::Zero => "0".to_string(),
+      Init::Expr(_) => "e".to_string(),
+      Init::List(inits) => format!("[{}]", inits.iter().map(shape).collect::<String>()),
+    }
+  }
+  let input = "int a[] = {1, [4] = 5}; int m[2][2] = {1, 2, 3}; char s[] = \"ab\"; \
+               struct { int x, y; struct { int z[2]; } w; } p = {.y = 2, {{3}}}; \
+               union { char c; int i; } u = {.i = 1}; int n = {1}; int z[2] = {};";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let defs: Vec<_> = toplevels
+    .iter()
+    .map(|toplevel| match &toplevel.kind {
+      TopLevelKind::VarDef(ty, _, Some(init), _) => (ty.clone(), shape(init)),
+      _ => unreachable!(),
+    })
+    .collect();
+  let array = |ty, n| Type::Array(Box::new(ty), Some(n));
+  assert_eq!(defs[0], (array(Type::Int, 5), "[e000e]".to_string()));
+  assert_eq!(
+    defs[1],
+    (array(array(Type::Int, 2), 2), "[[ee][e0]]".to_string())
+  );
+  assert_eq!(defs[2], (array(Type::Char, 3), "[eee]".to_string()));
+  assert_eq!(defs[3].1, "[0e[[e0]]]");
+  assert_eq!(defs[4].1, "[0e]");
+  assert_eq!(defs[5].1, "e");
+  assert_eq!(defs[6].1, "[00]");
+
+  for input in [
+    "int a[2] = {1, 2, 3};",
+    "int a[];",
+    "int a[] = 1;",
+    "int a[2] = {[2] = 1};",
+    "char s[1] = \"ab\";",
+    "struct { int x; } s = {.y = 1};",
+    "int x = {.y = 1};",
+  ] {
+    assert!(parse(Tokenizer::new(input)).is_err(), "{}", input);
+  }
+}
+
+#[test]
+fn test_struct_tag() {
+  // a tag refers to the struct of the innermost scope declaring it, which may be incomplete
+  let input = "struct t { char a; struct t *next; };
+int main() { struct t x; { struct t { long b; } y; y.b; } x.next->a; }";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let mut tys = Vec::new();
+  if let TopLevelKind::FunDef(.., body, _) = &toplevels[1].kind {
+    if let StmtKind::Block(stmts) = &body[1].kind {
+      if let StmtKind::Expr(expr) = &stmts[1].kind {
+        tys.push(expr.ty.clone());
+      }
+    }
+    if let StmtKind::Expr(expr) = &body[2].kind {
+      tys.push(expr.ty.clone());
+    }
+  }
+  assert_eq!(tys, [Type::Long, Type::Char]);
+
+  for input in [
+    "struct t; struct t x;",
+    "struct t { int a; }; int main() { struct t; struct t x; }",
+    "union u *p; union u x;",
+  ] {
+    assert!(parse(Tokenizer::new(input)).is_err(), "{}", input);
+  }
+  let input = "struct t; struct t *p; struct t { int a; }; struct t x;";
+  assert!(parse(Tokenizer::new(input)).is_ok());
+}
+
+#[test]
+fn test_storage_class() {
+  let input = "extern int a[]; static int f(); int main() { static int n = 1; extern int a[]; }
+int a[2];";
+  let toplevels = parse(Tokenizer::new(input)).unwrap();
+  let storages: Vec<_> = toplevels
+    .iter()
+    .filter_map(|toplevel| match &toplevel.kind {
+      TopLevelKind::VarDef(.., storage) | TopLevelKind::FunDecl(.., storage) => Some(*storage),
+      _ => None,
+    })
+    .collect();
+  assert_eq!(
+    storages,
+    [
+      StorageClass::Extern,
+      StorageClass::Static,
+      StorageClass::Default
+    ]
+  );
+  if let TopLevelKind::FunDef(.., body, StorageClass::Default) = &toplevels[2].kind {
+    let storages: Vec<_> = body
+      .iter()
+      .filter_map(|stmt| match &stmt.kind {
+        StmtKind::VarDef(_, storage) => Some(*storage),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(storages, [StorageClass::Static, StorageClass::Extern]);
+  } else {
+    unreachable!();
+  }
+
+  // only a declaration may leave the size of an array unknown
+  assert!(parse(Tokenizer::new("extern int a[] = {1};")).is_ok());
+  assert!(parse(Tokenizer::new("int a[];")).is_err());
+  assert!(parse(Tokenizer::new("static int a[];")).is_err());
+}
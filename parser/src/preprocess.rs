@@ -0,0 +1,1321 @@
+use crate::common::Expected;
+use crate::diagnostic::Diagnostic;
+use crate::source::{FileId, SourceMap, Span};
+use crate::tokenize::{self, Expansion, Token, TokenKind, Tokenizer};
+use crate::ty::Type;
+use crate::{diag, err};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+/// Headers provided by the compiler, which are included when they are not found in the include
+/// paths.
+static BUILTIN_HEADERS: [(&str, &str); 1] = [(
+  "stdarg.h",
+  "#ifndef __STDARG_H
+#define __STDARG_H
+typedef __builtin_va_list va_list;
+#define va_start(ap, last) __builtin_va_start(ap, last)
+#define va_arg(ap, ty) __builtin_va_arg(ap, ty)
+#define va_end(ap) __builtin_va_end(ap)
+#endif
+",
+)];
+
+/// A macro defined with `#define`.
+#[derive(Clone)]
+struct Macro {
+  /// Span of the macro name in its definition.
+  def: Span,
+  /// Parameters of a function-like macro, not including `__VA_ARGS__` of a variadic one.
+  params: Option<Vec<String>>,
+  variadic: bool,
+  body: Vec<Token>,
+}
+
+/// A conditional directive enclosing the current position.
+struct Cond {
+  /// Span of the `#if`, `#ifdef` or `#ifndef` directive.
+  span: Span,
+  /// Whether one of the groups of the conditional has been included.
+  included: bool,
+  /// Whether `#else` has been seen.
+  in_else: bool,
+}
+
+/// The state of preprocessing a file.
+struct FileContext {
+  file: FileId,
+  depth: usize,
+  /// The remaining tokens in reverse order, so that macro expansions can be pushed back to be
+  /// rescanned.
+  input: Vec<Expected<Token>>,
+  conds: Vec<Cond>,
+}
+
+fn is_punct(item: Option<&Expected<Token>>, op: &'static str) -> bool {
+  matches!(item, Some(Ok(tok)) if tok.kind == TokenKind::Punct(op))
+}
+
+fn item_span(item: &Expected<Token>) -> Option<Span> {
+  match item {
+    Ok(tok) => Some(tok.span),
+    Err(e) => e.span(),
+  }
+}
+
+fn is_directive(item: &Expected<Token>) -> bool {
+  matches!(item, Ok(tok) if tok.bol && tok.kind == TokenKind::Punct("#"))
+}
+
+/// Returns the name of a directive or its argument. Names such as `if` are lexed as keywords.
+fn directive_name(item: &Expected<Token>) -> Option<&str> {
+  match item {
+    Ok(Token {
+      kind: TokenKind::Ident(name),
+      ..
+    }) => Some(name),
+    Ok(Token {
+      kind: TokenKind::Keyword(keyword),
+      ..
+    }) => Some(keyword),
+    _ => None,
+  }
+}
+
+/// Returns the index of the first token on a line after the one containing `tokens[i - 1]`.
+fn line_end(tokens: &[Expected<Token>], mut i: usize) -> usize {
+  while i < tokens.len() && !matches!(&tokens[i], Ok(tok) if tok.bol) {
+    i += 1;
+  }
+  i
+}
+
+/// Returns how `kind` is written in source code.
+fn spelling(kind: &TokenKind) -> String {
+  match kind {
+    TokenKind::Eof => String::new(),
+    TokenKind::Keyword(s) | TokenKind::Punct(s) => s.to_string(),
+    TokenKind::Ident(name) => name.clone(),
+    TokenKind::Num(n, ty) => {
+      let suffix = match ty {
+        Type::UInt => "u",
+        Type::Long => "l",
+        Type::ULong => "ul",
+        Type::LongLong => "ll",
+        Type::ULongLong => "ull",
+        _ => "",
+      };
+      if ty.is_unsigned() {
+        format!("{}{}", *n as u64, suffix)
+      } else {
+        format!("{}{}", n, suffix)
+      }
+    }
+    TokenKind::Float(x, Type::Float) => format!("{:?}f", x),
+    TokenKind::Float(x, _) => format!("{:?}", x),
+    TokenKind::Str(s) => {
+      let mut res = String::from("\"");
+      for &c in s {
+        match c {
+          b'"' => res.push_str("\\\""),
+          b'\\' => res.push_str("\\\\"),
+          0x07 => res.push_str("\\a"),
+          0x08 => res.push_str("\\b"),
+          b'\t' => res.push_str("\\t"),
+          b'\n' => res.push_str("\\n"),
+          0x0b => res.push_str("\\v"),
+          0x0c => res.push_str("\\f"),
+          b'\r' => res.push_str("\\r"),
+          c if c.is_ascii() && !c.is_ascii_control() => res.push(c as char),
+          c => res.push_str(&format!("\\{:03o}", c)),
+        }
+      }
+      res.push('"');
+      res
+    }
+  }
+}
+
+/// Spells out the tokens of a macro argument as the `#` operator does.
+fn stringize(arg: &[Expected<Token>]) -> String {
+  let mut res = String::new();
+  for (i, tok) in arg.iter().flatten().enumerate() {
+    if i > 0 && tok.space {
+      res.push(' ');
+    }
+    res.push_str(&spelling(&tok.kind));
+  }
+  res
+}
+
+/// Concatenates two tokens as the `##` operator does.
+fn paste(lhs: &Token, rhs: &Token) -> Expected<Token> {
+  let text = spelling(&lhs.kind) + &spelling(&rhs.kind);
+  match &tokenize::lex(lhs.span.file, &text)[..] {
+    [Ok(tok), Ok(Token {
+      kind: TokenKind::Eof,
+      ..
+    })] => Ok(Token {
+      kind: tok.kind.clone(),
+      ..lhs.clone()
+    }),
+    _ => err!(
+      lhs.span,
+      "pasting `{}` and `{}` does not give a valid preprocessing token",
+      spelling(&lhs.kind),
+      spelling(&rhs.kind)
+    ),
+  }
+}
+
+/// Returns the current date in the format of `__DATE__`, such as `Jan  1 1970`.
+fn current_date() -> String {
+  static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+  ];
+  let secs = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs());
+  // convert days since 1970-01-01 to a civil date
+  let z = (secs / 86400) as i64 + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!("{} {:2} {}", MONTHS[month as usize - 1], day, year)
+}
+
+/// Reads the arguments of a function-like macro invocation from `input`, where the `(` is
+/// already consumed. Commas split at most `limit` arguments, so that the last argument of a
+/// variadic macro takes the rest. Returns the arguments and the closing `)`.
+fn read_args(
+  input: &mut Vec<Expected<Token>>,
+  name: &Token,
+  limit: usize,
+) -> Expected<(Vec<Vec<Expected<Token>>>, Token)> {
+  let mut args = vec![Vec::new()];
+  let mut depth = 0;
+  loop {
+    let item = match input.pop() {
+      Some(item) => item,
+      None => {
+        return err!(
+          name.span,
+          "unterminated argument list invoking macro `{}`",
+          spelling(&name.kind)
+        )
+      }
+    };
+    if let Ok(tok) = &item {
+      match tok.kind {
+        TokenKind::Punct("(") => depth += 1,
+        TokenKind::Punct(")") if depth == 0 => return Ok((args, tok.clone())),
+        TokenKind::Punct(")") => depth -= 1,
+        TokenKind::Punct(",") if depth == 0 && args.len() < limit => {
+          args.push(Vec::new());
+          continue;
+        }
+        _ => (),
+      }
+    }
+    args.last_mut().unwrap().push(item);
+  }
+}
+
+/// Skips the tokens of an excluded group, up to the `#elif`, `#else` or `#endif` ending it.
+fn skip_cond_incl(input: &mut Vec<Expected<Token>>) {
+  let mut depth = 0;
+  while let Some(item) = input.last() {
+    if is_directive(item) {
+      let name = match input.len().checked_sub(2).map(|i| &input[i]) {
+        Some(Ok(tok)) if !tok.bol => directive_name(&input[input.len() - 2]),
+        _ => None,
+      };
+      match name {
+        Some("if" | "ifdef" | "ifndef") => depth += 1,
+        Some("elif" | "else" | "endif") if depth == 0 => return,
+        Some("endif") => depth -= 1,
+        _ => (),
+      }
+    }
+    input.pop();
+  }
+}
+
+/// Detects an include guard: `#ifndef X` and `#define X` at the start of the file, and the
+/// `#endif` matching the `#ifndef` at its end. Returns `X` and the range of the guarded tokens.
+fn include_guard(tokens: &[Expected<Token>]) -> Option<(String, Range<usize>)> {
+  let directive = |i: usize| {
+    if i < tokens.len() && is_directive(&tokens[i]) {
+      let end = line_end(tokens, i + 1);
+      let names: Option<Vec<_>> = tokens[i + 1..end].iter().map(directive_name).collect();
+      Some((names.unwrap_or_default(), end))
+    } else {
+      None
+    }
+  };
+  let (names, i) = directive(0)?;
+  let guard = match names[..] {
+    ["ifndef", guard] => guard,
+    _ => return None,
+  };
+  let (names, mut i) = directive(i)?;
+  if names[..] != ["define", guard] {
+    return None;
+  }
+  // the guarded tokens start at `#define`, which is processed as usual
+  let start = line_end(tokens, 1);
+  let mut depth = 0;
+  while i < tokens.len() {
+    if let Some((names, end)) = directive(i) {
+      match names.first() {
+        Some(&("if" | "ifdef" | "ifndef")) => depth += 1,
+        // an `#else` branch of the guard itself is not covered by the guard macro
+        Some(&("else" | "elif")) if depth == 0 => return None,
+        Some(&"endif") if depth == 0 => {
+          return (end == tokens.len()).then(|| (guard.to_string(), start..i));
+        }
+        Some(&"endif") => depth -= 1,
+        _ => (),
+      }
+      i = end;
+    } else {
+      i += 1;
+    }
+  }
+  None
+}
+
+/// Runs preprocessing directives over a source file and the files it includes, producing the
+/// token stream the parser consumes.
+pub struct Preprocessor<'a> {
+  source_map: &'a mut SourceMap,
+  include_paths: Vec<PathBuf>,
+  /// Files containing `#pragma once`.
+  pragma_once: HashSet<PathBuf>,
+  /// Files guarded as a whole by `#ifndef X` `#define X` ... `#endif`, mapped to `X`.
+  include_guards: HashMap<PathBuf, String>,
+  macros: HashMap<String, Macro>,
+  /// The expansion of `__DATE__`.
+  date: String,
+  diags: Vec<Diagnostic>,
+}
+
+impl<'a> Preprocessor<'a> {
+  pub fn new(source_map: &'a mut SourceMap, include_paths: Vec<String>) -> Preprocessor<'a> {
+    Preprocessor {
+      source_map,
+      include_paths: include_paths.into_iter().map(PathBuf::from).collect(),
+      pragma_once: HashSet::new(),
+      include_guards: HashMap::new(),
+      macros: HashMap::new(),
+      date: current_date(),
+      diags: Vec::new(),
+    }
+  }
+
+  /// Preprocesses `file` and returns a tokenizer over the result. Errors in directives are
+  /// reported to the tokenizer, so that they come out of the parser with the syntax errors.
+  pub fn preprocess(mut self, file: FileId) -> Tokenizer {
+    let mut out = Vec::new();
+    self.preprocess_file(file, &mut out, 0);
+    let len = self.source_map.get(file).src().len();
+    let mut eof = Token::new(TokenKind::Eof, Span::new(file, len, len));
+    eof.bol = true;
+    out.push(Ok(eof));
+    let mut it = Tokenizer::from_tokens(out);
+    for diag in self.diags {
+      it.report(diag);
+    }
+    it
+  }
+
+  fn canonical_path(&self, file: FileId) -> Option<PathBuf> {
+    fs::canonicalize(self.source_map.get(file).name()).ok()
+  }
+
+  fn preprocess_file(&mut self, file: FileId, out: &mut Vec<Expected<Token>>, depth: usize) {
+    let mut tokens = tokenize::lex(file, self.source_map.get(file).src());
+    tokens.pop(); // Eof
+    if let Some((guard, range)) = include_guard(&tokens) {
+      if let Some(path) = self.canonical_path(file) {
+        self.include_guards.insert(path, guard.clone());
+      }
+      if self.macros.contains_key(&guard) {
+        return;
+      }
+      tokens.truncate(range.end);
+      tokens.drain(..range.start);
+    }
+
+    let mut ctx = FileContext {
+      file,
+      depth,
+      input: tokens.into_iter().rev().collect(),
+      conds: Vec::new(),
+    };
+    while let Some(item) = ctx.input.pop() {
+      let tok = match item {
+        Ok(tok) => tok,
+        Err(_) => {
+          out.push(item);
+          continue;
+        }
+      };
+      if tok.bol && tok.kind == TokenKind::Punct("#") {
+        let mut line = Vec::new();
+        while matches!(ctx.input.last(), Some(item) if !matches!(item, Ok(tok) if tok.bol)) {
+          line.push(ctx.input.pop().unwrap());
+        }
+        let span = line
+          .last()
+          .and_then(item_span)
+          .map_or(tok.span, |span| tok.span.to(span));
+        if let Err(e) = self.directive(&mut ctx, &line, span, out) {
+          self.diags.push(e.or_at(span));
+        }
+        continue;
+      }
+      match self.expand_macro(&tok, &mut ctx.input) {
+        Ok(true) => (),
+        Ok(false) => out.push(Ok(tok)),
+        Err(e) => self.diags.push(e),
+      }
+    }
+    for cond in ctx.conds {
+      let diag = Diagnostic::error("unterminated conditional directive").at(cond.span);
+      self.diags.push(diag);
+    }
+  }
+
+  /// Macro-expands a sequence of tokens which contains no directives, such as a macro argument.
+  fn expand_all(&mut self, tokens: &[Expected<Token>]) -> Expected<Vec<Expected<Token>>> {
+    let mut input: Vec<_> = tokens.iter().rev().cloned().collect();
+    let mut res = Vec::new();
+    while let Some(item) = input.pop() {
+      match item {
+        Ok(tok) if self.expand_macro(&tok, &mut input)? => (),
+        item => res.push(item),
+      }
+    }
+    Ok(res)
+  }
+
+  /// Expands `tok` if it names a macro, pushing the result back to `input` to be rescanned.
+  /// Returns whether `tok` is expanded.
+  fn expand_macro(&mut self, tok: &Token, input: &mut Vec<Expected<Token>>) -> Expected<bool> {
+    let name = match &tok.kind {
+      TokenKind::Ident(name) => name.as_str(),
+      TokenKind::Keyword(keyword) => keyword,
+      _ => return Ok(false),
+    };
+    if tok.hideset.iter().any(|hidden| hidden == name) {
+      return Ok(false);
+    }
+    let builtin = match name {
+      "__FILE__" => Some(TokenKind::Str(
+        self
+          .source_map
+          .get(tok.span.file)
+          .name()
+          .as_bytes()
+          .to_vec(),
+      )),
+      "__LINE__" => {
+        let (line, _) = self.source_map.get(tok.span.file).line_col(tok.span.start);
+        Some(TokenKind::Num(line as i64, Type::Int))
+      }
+      "__DATE__" => Some(TokenKind::Str(self.date.as_bytes().to_vec())),
+      _ => None,
+    };
+    if let Some(kind) = builtin {
+      input.push(Ok(Token {
+        kind,
+        hideset: Vec::new(),
+        ..tok.clone()
+      }));
+      return Ok(true);
+    }
+    let mac = match self.macros.get(name) {
+      Some(mac) => mac.clone(),
+      None => return Ok(false),
+    };
+    let name = name.to_string();
+    let expansion = |site| {
+      Rc::new(Expansion {
+        name: name.clone(),
+        def: mac.def,
+        site,
+      })
+    };
+
+    let (res, mut hideset) = match &mac.params {
+      None => {
+        let res = self.subst(&mac.body, &[], &[], &expansion(tok.span))?;
+        (res, tok.hideset.clone())
+      }
+      Some(params) => {
+        if !is_punct(input.last(), "(") {
+          return Ok(false);
+        }
+        input.pop();
+        let limit = if mac.variadic {
+          params.len() + 1
+        } else {
+          usize::MAX
+        };
+        let (mut args, rparen) = read_args(input, tok, limit)?;
+        if params.is_empty() && !mac.variadic && args[0].is_empty() {
+          args.clear();
+        }
+        if mac.variadic && args.len() == params.len() {
+          args.push(Vec::new());
+        }
+        let expected = params.len() + mac.variadic as usize;
+        let span = tok.span.to(rparen.span);
+        if args.len() < expected {
+          return err!(
+            span,
+            "macro `{}` requires {} arguments, but only {} given",
+            name,
+            params.len(),
+            args.len()
+          );
+        } else if args.len() > expected {
+          return err!(
+            span,
+            "macro `{}` passed {} arguments, but takes just {}",
+            name,
+            args.len(),
+            params.len()
+          );
+        }
+        let mut names = params.clone();
+        if mac.variadic {
+          names.push("__VA_ARGS__".to_string());
+        }
+        let res = self.subst(&mac.body, &names, &args, &expansion(span))?;
+        let hideset = tok
+          .hideset
+          .iter()
+          .filter(|&name| rparen.hideset.contains(name))
+          .cloned()
+          .collect();
+        (res, hideset)
+      }
+    };
+
+    hideset.push(name);
+    for (i, mut item) in res.into_iter().enumerate().rev() {
+      if let Ok(res_tok) = &mut item {
+        res_tok.bol = false;
+        if i == 0 {
+          res_tok.space = tok.space;
+        }
+        res_tok.hideset.extend(hideset.iter().cloned());
+      }
+      input.push(item);
+    }
+    Ok(true)
+  }
+
+  /// Substitutes the arguments of a function-like macro for its parameters in `body` and
+  /// applies the `#` and `##` operators. An object-like macro has no parameters.
+  fn subst(
+    &mut self,
+    body: &[Token],
+    params: &[String],
+    args: &[Vec<Expected<Token>>],
+    expansion: &Rc<Expansion>,
+  ) -> Expected<Vec<Expected<Token>>> {
+    let arg_of = |tok: &Token| match &tok.kind {
+      TokenKind::Ident(name) => params
+        .iter()
+        .position(|param| param == name)
+        .map(|i| &args[i]),
+      _ => None,
+    };
+    let mut res: Vec<Expected<Token>> = Vec::new();
+    // whether the left operand of the next `##` is an empty argument
+    let mut placemarker = false;
+    let mut i = 0;
+    while i < body.len() {
+      let tok = &body[i];
+      let next_is_paste =
+        matches!(body.get(i + 1), Some(tok) if tok.kind == TokenKind::Punct("##"));
+      // `#` is an ordinary token in the body of an object-like macro
+      let stringized = match (&tok.kind, body.get(i + 1)) {
+        (TokenKind::Punct("#"), Some(next)) => arg_of(next),
+        _ => None,
+      };
+      if let Some(arg) = stringized {
+        let mut str_tok = from_body(tok, expansion);
+        str_tok.kind = TokenKind::Str(stringize(arg).into_bytes());
+        res.push(Ok(str_tok));
+        i += 2;
+      } else if tok.kind == TokenKind::Punct(",")
+        && next_is_paste
+        && matches!(body.get(i + 2), Some(Token { kind: TokenKind::Ident(name), .. }) if name == "__VA_ARGS__")
+        && params.last().is_some_and(|param| param == "__VA_ARGS__")
+      {
+        // [GNU] `, ## __VA_ARGS__` drops the comma if the variable arguments are empty
+        let va_args = args.last().unwrap();
+        if !va_args.is_empty() {
+          res.push(Ok(from_body(tok, expansion)));
+          res.extend(self.expand_all(va_args)?);
+        }
+        i += 3;
+      } else if tok.kind == TokenKind::Punct("##") {
+        let lhs = if placemarker { None } else { res.pop() };
+        let rhs = match arg_of(&body[i + 1]) {
+          Some(arg) => arg.clone(),
+          None => vec![Ok(from_body(&body[i + 1], expansion))],
+        };
+        placemarker = lhs.is_none() && rhs.is_empty();
+        let mut rhs = rhs.into_iter();
+        match (lhs, rhs.next()) {
+          (Some(Ok(lhs)), Some(Ok(rhs))) => res.push(paste(&lhs, &rhs)),
+          (lhs, rhs) => res.extend(lhs.into_iter().chain(rhs)),
+        }
+        res.extend(rhs);
+        i += 2;
+      } else if let Some(arg) = arg_of(tok) {
+        if next_is_paste {
+          placemarker = arg.is_empty();
+          res.extend(arg.iter().cloned());
+        } else {
+          res.extend(self.expand_all(arg)?);
+        }
+        i += 1;
+      } else {
+        placemarker = false;
+        res.push(Ok(from_body(tok, expansion)));
+        i += 1;
+      }
+    }
+    Ok(res)
+  }
+
+  fn directive(
+    &mut self,
+    ctx: &mut FileContext,
+    line: &[Expected<Token>],
+    span: Span,
+    out: &mut Vec<Expected<Token>>,
+  ) -> Expected<()> {
+    let name = match line.first() {
+      // null directive
+      None => return Ok(()),
+      Some(item) => directive_name(item),
+    };
+    match name {
+      Some("include") => self.include(ctx.file, &line[1..], span, out, ctx.depth),
+      Some("define") => self.define(&line[1..], span),
+      Some("undef") => {
+        let name = line.get(1).and_then(directive_name);
+        let name = name.ok_or_else(|| Diagnostic::error("macro names must be identifiers"))?;
+        self.macros.remove(name);
+        Ok(())
+      }
+      Some("if") => {
+        let value = self.eval_cond(&line[1..], span);
+        self.start_cond(ctx, span, *value.as_ref().unwrap_or(&false));
+        value.map(|_| ())
+      }
+      Some(directive @ ("ifdef" | "ifndef")) => {
+        let name = line.get(1).and_then(directive_name);
+        let value = match name {
+          Some(name) => Ok(self.is_defined(name) == (directive == "ifdef")),
+          None => err!(span, "macro names must be identifiers"),
+        };
+        self.start_cond(ctx, span, *value.as_ref().unwrap_or(&false));
+        value.map(|_| ())
+      }
+      Some("elif") => {
+        let cond = match ctx.conds.last_mut() {
+          Some(cond) if cond.in_else => return err!(span, "#elif after #else"),
+          Some(cond) => cond,
+          None => return err!(span, "#elif without #if"),
+        };
+        if cond.included {
+          skip_cond_incl(&mut ctx.input);
+          return Ok(());
+        }
+        let value = self.eval_cond(&line[1..], span);
+        let cond = ctx.conds.last_mut().unwrap();
+        cond.included = *value.as_ref().unwrap_or(&false);
+        if !cond.included {
+          skip_cond_incl(&mut ctx.input);
+        }
+        value.map(|_| ())
+      }
+      Some("else") => {
+        let cond = match ctx.conds.last_mut() {
+          Some(cond) if cond.in_else => return err!(span, "#else after #else"),
+          Some(cond) => cond,
+          None => return err!(span, "#else without #if"),
+        };
+        cond.in_else = true;
+        if cond.included {
+          skip_cond_incl(&mut ctx.input);
+        } else {
+          cond.included = true;
+        }
+        Ok(())
+      }
+      Some("endif") => match ctx.conds.pop() {
+        Some(_) => Ok(()),
+        None => err!(span, "#endif without #if"),
+      },
+      Some("pragma") => {
+        if line.get(1).and_then(directive_name) == Some("once") {
+          if let Some(path) = self.canonical_path(ctx.file) {
+            self.pragma_once.insert(path);
+          }
+        }
+        // other pragmas are ignored
+        Ok(())
+      }
+      Some(name) => err!(
+        item_span(&line[0]).unwrap(),
+        "invalid preprocessing directive `#{}`",
+        name
+      ),
+      None => {
+        let tok = line[0].clone()?;
+        err!(
+          tok.span,
+          "unexpected {}, expecting directive name",
+          tok.kind
+        )
+      }
+    }
+  }
+
+  /// Enters a conditional directive, skipping its first group unless `included`.
+  fn start_cond(&mut self, ctx: &mut FileContext, span: Span, included: bool) {
+    ctx.conds.push(Cond {
+      span,
+      included,
+      in_else: false,
+    });
+    if !included {
+      skip_cond_incl(&mut ctx.input);
+    }
+  }
+
+  fn is_defined(&self, name: &str) -> bool {
+    matches!(name, "__FILE__" | "__LINE__" | "__DATE__") || self.macros.contains_key(name)
+  }
+
+  /// Evaluates the controlling expression of `#if` or `#elif`.
+  fn eval_cond(&mut self, line: &[Expected<Token>], span: Span) -> Expected<bool> {
+    let mut tokens = Vec::new();
+    let mut it = line.iter().cloned();
+    while let Some(tok) = it.next().transpose()? {
+      if tok.kind != TokenKind::Ident("defined".to_string()) {
+        tokens.push(Ok(tok));
+        continue;
+      }
+      // `defined X` and `defined(X)` are replaced before macro expansion
+      let mut next = it.next().transpose()?;
+      let paren = matches!(&next, Some(tok) if tok.kind == TokenKind::Punct("("));
+      if paren {
+        next = it.next().transpose()?;
+      }
+      let name = match next.as_ref().map(|tok| &tok.kind) {
+        Some(TokenKind::Ident(name)) => name.clone(),
+        Some(TokenKind::Keyword(keyword)) => keyword.to_string(),
+        _ => return err!(tok.span, "macro names must be identifiers"),
+      };
+      if paren && !is_punct(it.next().as_ref(), ")") {
+        return err!(tok.span, "missing `)` after `defined`");
+      }
+      let value = self.is_defined(&name) as i64;
+      tokens.push(Ok(Token {
+        kind: TokenKind::Num(value, Type::Int),
+        ..tok
+      }));
+    }
+    let tokens = self.expand_all(&tokens)?;
+    let tokens = tokens.into_iter().collect::<Expected<Vec<_>>>()?;
+    if tokens.is_empty() {
+      return err!(span, "#if with no expression");
+    }
+    let mut eval = CondEval {
+      tokens,
+      pos: 0,
+      end: span,
+    };
+    let value = eval.cond_expr(true)?;
+    if let Some(tok) = eval.tokens.get(eval.pos) {
+      return err!(tok.span, "unexpected {} in #if expression", tok.kind);
+    }
+    Ok(value.n != 0)
+  }
+
+  /// Defines a macro given on the command line as `name` or `name=value`. The value defaults
+  /// to `1`.
+  pub fn define_macro(&mut self, def: &str) {
+    let (name, value) = def.split_once('=').unwrap_or((def, "1"));
+    let src = format!("{} {}", name, value);
+    let file = self.source_map.add_file("<command line>".to_string(), src);
+    let mut line = tokenize::lex(file, self.source_map.get(file).src());
+    line.pop(); // Eof
+    let span = Span::new(file, 0, name.len());
+    if let Err(e) = self.define(&line, span) {
+      self.diags.push(e.or_at(span));
+    }
+  }
+
+  pub fn undef_macro(&mut self, name: &str) {
+    self.macros.remove(name);
+  }
+
+  //' include     = "#" "include" (str | "<" path ">")
+  fn include(
+    &mut self,
+    file: FileId,
+    line: &[Expected<Token>],
+    span: Span,
+    out: &mut Vec<Expected<Token>>,
+    depth: usize,
+  ) -> Expected<()> {
+    let (name, quoted, end) = match line.first() {
+      Some(Ok(Token {
+        kind: TokenKind::Str(name),
+        ..
+      })) => (String::from_utf8_lossy(name).into_owned(), true, 1),
+      Some(Ok(tok)) if tok.kind == TokenKind::Punct("<") => {
+        let close = line
+          .iter()
+          .position(|item| matches!(item, Ok(tok) if tok.kind == TokenKind::Punct(">")))
+          .ok_or_else(|| Diagnostic::error("missing terminating `>` character").at(tok.span))?;
+        let start = tok.span.end;
+        let end = item_span(&line[close]).unwrap().start;
+        let name = self.source_map.get(file).src()[start..end].to_string();
+        (name, false, close + 1)
+      }
+      _ => return err!(span, "#include expects \"FILENAME\" or <FILENAME>"),
+    };
+    if let Some(extra) = line.get(end).and_then(item_span) {
+      return err!(extra, "extra tokens at end of #include directive");
+    }
+    if depth >= MAX_INCLUDE_DEPTH {
+      return err!(span, "#include nested too deeply");
+    }
+
+    let (name, src) = match self.find_include(file, &name, quoted) {
+      Some(path) => {
+        if let Ok(canonical) = fs::canonicalize(&path) {
+          if self.pragma_once.contains(&canonical) {
+            return Ok(());
+          }
+          if let Some(guard) = self.include_guards.get(&canonical) {
+            if self.macros.contains_key(guard) {
+              return Ok(());
+            }
+          }
+        }
+        let src =
+          fs::read_to_string(&path).map_err(|_| diag!(span, "failed to read `{}`", name))?;
+        (path.to_string_lossy().into_owned(), src)
+      }
+      None => match BUILTIN_HEADERS.iter().find(|(header, _)| *header == name) {
+        Some((_, src)) => (format!("<built-in>/{}", name), src.to_string()),
+        None => return err!(span, "`{}` file not found", name),
+      },
+    };
+    let included = self.source_map.add_included_file(name, src, span);
+    self.preprocess_file(included, out, depth + 1);
+    Ok(())
+  }
+
+  //' define      = "#" "define" ident ("(" macro_params)? token*
+  //' macro_params = (ident ("," ident)* ("," "...")? | "...")? ")"
+  fn define(&mut self, line: &[Expected<Token>], span: Span) -> Expected<()> {
+    let line = line.iter().cloned().collect::<Expected<Vec<_>>>()?;
+    let (name, def) = match line.first() {
+      Some(tok) => match &tok.kind {
+        TokenKind::Ident(name) => (name.clone(), tok.span),
+        TokenKind::Keyword(keyword) => (keyword.to_string(), tok.span),
+        _ => return err!(tok.span, "macro names must be identifiers"),
+      },
+      None => return err!(span, "macro names must be identifiers"),
+    };
+    let mut it = line[1..].iter().peekable();
+    let mut params = None;
+    let mut variadic = false;
+    if matches!(it.peek(), Some(tok) if tok.kind == TokenKind::Punct("(") && !tok.space) {
+      it.next();
+      let mut names = Vec::new();
+      loop {
+        let tok = it.next();
+        match tok.map(|tok| &tok.kind) {
+          Some(TokenKind::Punct(")")) if names.is_empty() => break,
+          Some(TokenKind::Ident(name)) => names.push(name.clone()),
+          Some(TokenKind::Punct("...")) => variadic = true,
+          Some(kind) => {
+            return err!(
+              tok.unwrap().span,
+              "unexpected {}, expecting parameter name",
+              kind
+            )
+          }
+          None => return err!(span, "missing `)` in macro parameter list"),
+        }
+        let tok = it.next();
+        match tok.map(|tok| &tok.kind) {
+          Some(TokenKind::Punct(")")) => break,
+          Some(TokenKind::Punct(",")) if !variadic => (),
+          Some(kind) => {
+            return err!(
+              tok.unwrap().span,
+              "unexpected {}, expecting `,` or `)`",
+              kind
+            )
+          }
+          None => return err!(span, "missing `)` in macro parameter list"),
+        }
+      }
+      params = Some(names);
+    }
+
+    let body: Vec<_> = it.cloned().collect();
+    for (i, tok) in body.iter().enumerate() {
+      if tok.kind == TokenKind::Punct("##") && (i == 0 || i + 1 == body.len()) {
+        return err!(
+          tok.span,
+          "`##` cannot appear at either end of a macro expansion"
+        );
+      }
+      if let (TokenKind::Punct("#"), Some(names)) = (&tok.kind, &params) {
+        let is_param = |name: &String| names.contains(name) || variadic && name == "__VA_ARGS__";
+        if !matches!(body.get(i + 1), Some(Token { kind: TokenKind::Ident(name), .. }) if is_param(name))
+        {
+          return err!(tok.span, "`#` is not followed by a macro parameter");
+        }
+      }
+    }
+    let mac = Macro {
+      def,
+      params,
+      variadic,
+      body,
+    };
+    self.macros.insert(name, mac);
+    Ok(())
+  }
+
+  /// Searches `name` in the directory of `file` for the quoted form, then in the include paths.
+  fn find_include(&self, file: FileId, name: &str, quoted: bool) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+      return path.is_file().then(|| path.to_path_buf());
+    }
+    let current_dir = Path::new(self.source_map.get(file).name())
+      .parent()
+      .filter(|_| quoted)
+      .map(|dir| dir.join(name));
+    current_dir
+      .into_iter()
+      .chain(self.include_paths.iter().map(|dir| dir.join(name)))
+      .find(|path| path.is_file())
+  }
+}
+
+/// Evaluates an integer constant expression of `#if` after macro expansion.
+struct CondEval {
+  tokens: Vec<Token>,
+  pos: usize,
+  /// Span of the directive, where running out of tokens is reported.
+  end: Span,
+}
+
+//' cond_expr   = binary ("?" cond_expr ":" cond_expr)?
+//' binary      = unary (binop unary)*   (by the precedence of BINARY_OPS)
+//' unary       = ("+" | "-" | "~" | "!") unary
+//'             | "(" cond_expr ")"
+//'             | num
+//'             | ident
+impl CondEval {
+  /// Binary operators from the lowest precedence to the highest.
+  const BINARY_OPS: [&'static [&'static str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+  ];
+
+  fn consume(&mut self, op: &'static str) -> bool {
+    let found = matches!(self.tokens.get(self.pos), Some(tok) if tok.kind == TokenKind::Punct(op));
+    if found {
+      self.pos += 1;
+    }
+    found
+  }
+
+  fn expect(&mut self, op: &'static str) -> Expected<()> {
+    if self.consume(op) {
+      Ok(())
+    } else {
+      match self.tokens.get(self.pos) {
+        Some(tok) => err!(tok.span, "unexpected {}, expecting `{}`", tok.kind, op),
+        None => err!(self.end, "unexpected end of line, expecting `{}`", op),
+      }
+    }
+  }
+
+  /// Evaluates a conditional expression. Division by zero is an error only if `live`, that is,
+  /// unless the operand is skipped by `&&`, `||` or `?:`.
+  fn cond_expr(&mut self, live: bool) -> Expected<CondValue> {
+    let cond = self.binary(0, live)?;
+    if self.consume("?") {
+      let then = self.cond_expr(live && cond.n != 0)?;
+      self.expect(":")?;
+      let els = self.cond_expr(live && cond.n == 0)?;
+      // the result has the common type of both operands
+      let n = if cond.n != 0 { then.n } else { els.n };
+      Ok(CondValue::new(n, then.unsigned || els.unsigned))
+    } else {
+      Ok(cond)
+    }
+  }
+
+  fn binary(&mut self, level: usize, live: bool) -> Expected<CondValue> {
+    if level == CondEval::BINARY_OPS.len() {
+      return self.unary(live);
+    }
+    let mut lhs = self.binary(level + 1, live)?;
+    'outer: loop {
+      for &op in CondEval::BINARY_OPS[level] {
+        let start = self.pos;
+        if !self.consume(op) {
+          continue;
+        }
+        let rhs_live = match op {
+          "||" => live && lhs.n == 0,
+          "&&" => live && lhs.n != 0,
+          _ => live,
+        };
+        let rhs = self.binary(level + 1, rhs_live)?;
+        // the usual arithmetic conversions: if either operand is unsigned, both are
+        let unsigned = lhs.unsigned || rhs.unsigned;
+        let (n, m) = (lhs.n, rhs.n);
+        let (un, um) = (n as u64, m as u64);
+        lhs = match op {
+          "||" => CondValue::bool(n != 0 || m != 0),
+          "&&" => CondValue::bool(n != 0 && m != 0),
+          "|" => CondValue::new(n | m, unsigned),
+          "^" => CondValue::new(n ^ m, unsigned),
+          "&" => CondValue::new(n & m, unsigned),
+          "==" => CondValue::bool(n == m),
+          "!=" => CondValue::bool(n != m),
+          "<" if unsigned => CondValue::bool(un < um),
+          "<=" if unsigned => CondValue::bool(un <= um),
+          ">" if unsigned => CondValue::bool(un > um),
+          ">=" if unsigned => CondValue::bool(un >= um),
+          "<" => CondValue::bool(n < m),
+          "<=" => CondValue::bool(n <= m),
+          ">" => CondValue::bool(n > m),
+          ">=" => CondValue::bool(n >= m),
+          // the result of a shift has the type of its left operand
+          "<<" => CondValue::new(n.wrapping_shl(m as u32), lhs.unsigned),
+          ">>" if lhs.unsigned => CondValue::new(un.wrapping_shr(m as u32) as i64, true),
+          ">>" => CondValue::new(n.wrapping_shr(m as u32), false),
+          "+" => CondValue::new(n.wrapping_add(m), unsigned),
+          "-" => CondValue::new(n.wrapping_sub(m), unsigned),
+          "*" => CondValue::new(n.wrapping_mul(m), unsigned),
+          "/" | "%" if m == 0 => {
+            if rhs_live {
+              return err!(self.tokens[start].span, "division by zero in #if");
+            }
+            CondValue::new(0, unsigned)
+          }
+          "/" if unsigned => CondValue::new((un / um) as i64, true),
+          "%" if unsigned => CondValue::new((un % um) as i64, true),
+          "/" => CondValue::new(n.wrapping_div(m), false),
+          "%" => CondValue::new(n.wrapping_rem(m), false),
+          _ => unreachable!(),
+        };
+        continue 'outer;
+      }
+      break Ok(lhs);
+    }
+  }
+
+  fn unary(&mut self, live: bool) -> Expected<CondValue> {
+    if self.consume("+") {
+      self.unary(live)
+    } else if self.consume("-") {
+      let value = self.unary(live)?;
+      Ok(CondValue::new(value.n.wrapping_neg(), value.unsigned))
+    } else if self.consume("~") {
+      let value = self.unary(live)?;
+      Ok(CondValue::new(!value.n, value.unsigned))
+    } else if self.consume("!") {
+      Ok(CondValue::bool(self.unary(live)?.n == 0))
+    } else if self.consume("(") {
+      let value = self.cond_expr(live)?;
+      self.expect(")")?;
+      Ok(value)
+    } else {
+      let tok = match self.tokens.get(self.pos) {
+        Some(tok) => tok,
+        None => return err!(self.end, "unexpected end of line in #if expression"),
+      };
+      let value = match &tok.kind {
+        TokenKind::Num(n, ty) => CondValue::new(*n, ty.is_unsigned()),
+        // identifiers remaining after macro expansion evaluate to 0
+        TokenKind::Ident(_) | TokenKind::Keyword(_) => CondValue::new(0, false),
+        _ => return err!(tok.span, "unexpected {} in #if expression", tok.kind),
+      };
+      self.pos += 1;
+      Ok(value)
+    }
+  }
+}
+
+/// A value in `#if`, where every signed integer acts as `intmax_t` and every unsigned integer
+/// as `uintmax_t`.
+#[derive(Clone, Copy)]
+struct CondValue {
+  n: i64,
+  unsigned: bool,
+}
+
+impl CondValue {
+  fn new(n: i64, unsigned: bool) -> CondValue {
+    CondValue { n, unsigned }
+  }
+
+  /// The `int` result of a comparison or logical operator.
+  fn bool(b: bool) -> CondValue {
+    CondValue::new(b as i64, false)
+  }
+}
+
+/// Copies a token of a macro body into an expansion, which it takes the span of.
+fn from_body(tok: &Token, expansion: &Rc<Expansion>) -> Token {
+  Token {
+    span: expansion.site,
+    bol: false,
+    expansion: Some(expansion.clone()),
+    ..tok.clone()
+  }
+}
+
+#[test]
+fn test_preprocess() {
+  let dir = std::env::temp_dir().join(format!("try-rust-pp-{}", std::process::id()));
+  fs::create_dir_all(dir.join("inc")).unwrap();
+  fs::write(dir.join("once.h"), "#pragma once\nint once;\n").unwrap();
+  fs::write(
+    dir.join("inc/guard.h"),
+    "#ifndef GUARD_H\n#define GUARD_H\nint guard;\n#endif\n",
+  )
+  .unwrap();
+  // not an include guard, since the `#else` branch is taken on the second inclusion
+  fs::write(
+    dir.join("else.h"),
+    "#ifndef ELSE_H\n#define ELSE_H\nint first;\n#else\nint again;\n#endif\n",
+  )
+  .unwrap();
+  let main = "#include \"once.h\"\n#include <guard.h>\n#include \"once.h\"\n#include <guard.h>\n\
+              #include \"else.h\"\n#include \"else.h\"\n\
+              #\nint main;\n#include \"missing.h\"\n#line 1\n";
+  fs::write(dir.join("main.c"), main).unwrap();
+
+  let mut sm = SourceMap::new();
+  let file = sm.add_file(
+    dir.join("main.c").to_string_lossy().into_owned(),
+    main.to_string(),
+  );
+  let include_paths = vec![dir.join("inc").to_string_lossy().into_owned()];
+  let mut it = Preprocessor::new(&mut sm, include_paths).preprocess(file);
+  let mut names = Vec::new();
+  while let Ok(tok) = it.current() {
+    match tok.kind {
+      TokenKind::Ident(name) => names.push(name),
+      TokenKind::Eof => break,
+      _ => (),
+    }
+    it.advance();
+  }
+  assert_eq!(names, ["once", "guard", "first", "again", "main"]);
+
+  let errs = it.take_diagnostics();
+  assert_eq!(errs.len(), 2);
+  assert!(errs[0].message.starts_with("`missing.h` file not found"));
+  assert!(errs[1]
+    .message
+    .starts_with("invalid preprocessing directive `#line`"));
+
+  let guard_file = (0..4)
+    .find(|&id| sm.get(id).name().ends_with("guard.h"))
+    .unwrap();
+  let site = sm.get(guard_file).include_site().unwrap();
+  assert_eq!(sm.get(file).line_col(site.start), (2, 1));
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_macro() {
+  let expand = |src: &str| {
+    let mut sm = SourceMap::new();
+    let file = sm.add_file("macro.c".to_string(), src.to_string());
+    let mut it = Preprocessor::new(&mut sm, Vec::new()).preprocess(file);
+    let mut res = Vec::new();
+    while let Ok(tok) = it.current() {
+      if tok.kind == TokenKind::Eof {
+        break;
+      }
+      res.push(spelling(&tok.kind));
+      it.advance();
+    }
+    (res.join(" "), it.take_diagnostics())
+  };
+  let check = |src: &str, expected: &str| {
+    let (res, errs) = expand(src);
+    assert!(errs.is_empty(), "{:?}", errs);
+    assert_eq!(res, expected);
+  };
+
+  check("#define N 3\nN + N", "3 + 3");
+  check("#define N 3\n#undef N\nN", "N");
+  check(
+    "#define ASSERT(x, y) f(x, y, #y)\nASSERT(3, 1 +2)",
+    "f ( 3 , 1 + 2 , \"1 +2\" )",
+  );
+  check("#define F (x)\nF", "( x )");
+  check("#define F(x) x\nF F(1)", "F 1");
+  check(
+    "#define CAT(a, b) a ## b\nCAT(x, 1) CAT(, y) CAT(z,)",
+    "x1 y z",
+  );
+  check("#define S(x) #x\nS(\"a\\n\")", "\"\\\"a\\\\n\\\"\"");
+  check("#define X a ## b\nX", "ab");
+  check("#define H # x\nH", "# x");
+  check(
+    "#define F(fmt, ...) f(fmt, __VA_ARGS__)\n#define G(fmt, ...) g(fmt, ## __VA_ARGS__)\n\
+     F(1, 2, (3, 4)) G(1) G(1, 2)",
+    "f ( 1 , 2 , ( 3 , 4 ) ) g ( 1 ) g ( 1 , 2 )",
+  );
+  check("#define f(x) x + f(x)\n#define g f\ng(1)", "1 + f ( 1 )");
+  check("#define A B\n#define B A\nA B", "A B");
+  check("\n__LINE__ __FILE__", "2 \"macro.c\"");
+  check("#define L __LINE__\nL\nL", "2 3");
+  check(
+    "#include <stdarg.h>\n#include <stdarg.h>\nva_start(ap, x)",
+    "typedef __builtin_va_list va_list ; __builtin_va_start ( ap , x )",
+  );
+
+  let (_, errs) = expand("#define F(x, y) x\nF(1)\nF(1, 2, 3)\n#define G(x) #y\n");
+  let messages: Vec<_> = errs.iter().map(|e| e.message.as_str()).collect();
+  assert!(messages[0].starts_with("macro `F` requires 2 arguments, but only 1 given"));
+  assert!(messages[1].starts_with("macro `F` passed 3 arguments, but takes just 2"));
+  assert!(messages[2].starts_with("`#` is not followed by a macro parameter"));
+
+  let mut sm = SourceMap::new();
+  let file = sm.add_file("m.c".to_string(), "#define ONE 1\nint x = ONE;".to_string());
+  let mut it = Preprocessor::new(&mut sm, Vec::new()).preprocess(file);
+  while !matches!(
+    it.current(),
+    Ok(Token {
+      kind: TokenKind::Num(..),
+      ..
+    })
+  ) {
+    it.advance();
+  }
+  let tok = it.current().unwrap();
+  let expansion = tok.expansion.unwrap();
+  assert_eq!(
+    (expansion.def, expansion.site),
+    (Span::new(file, 8, 11), Span::new(file, 22, 25))
+  );
+  assert_eq!(tok.span, expansion.site);
+}
+
+#[test]
+fn test_conditional() {
+  let expand = |src: &str| {
+    let mut sm = SourceMap::new();
+    let file = sm.add_file("cond.c".to_string(), src.to_string());
+    let mut pp = Preprocessor::new(&mut sm, Vec::new());
+    pp.define_macro("DEBUG");
+    pp.define_macro("LEVEL=2");
+    pp.define_macro("NDEBUG");
+    pp.undef_macro("NDEBUG");
+    let mut it = pp.preprocess(file);
+    let mut res = Vec::new();
+    while let Ok(tok) = it.current() {
+      if tok.kind == TokenKind::Eof {
+        break;
+      }
+      res.push(spelling(&tok.kind));
+      it.advance();
+    }
+    (res.join(" "), it.take_diagnostics())
+  };
+  let check = |src: &str, expected: &str| {
+    let (res, errs) = expand(src);
+    assert!(errs.is_empty(), "{:?}", errs);
+    assert_eq!(res, expected);
+  };
+
+  check("#ifdef DEBUG\na\n#else\nb\n#endif", "a");
+  check("#ifndef NDEBUG\na\n#endif\n#ifdef NDEBUG\nb\n#endif", "a");
+  check("#if LEVEL > 1 && defined(DEBUG)\na\n#endif", "a");
+  check(
+    "#if LEVEL == 1\na\n#elif LEVEL == 2\nb\n#elif 1\nc\n#else\nd\n#endif",
+    "b",
+  );
+  check(
+    "#if 0\n#if 1\na\n#else\nb\n#endif\n#bogus\n#elif defined LEVEL\nc\n#endif",
+    "c",
+  );
+  check(
+    "#if UNDEFINED || (1 << 3) % 5 != 3\na\n#else\nb\n#endif",
+    "b",
+  );
+  check("#if 0 && 1 / 0\na\n#elif 1 ? 2 : 1 / 0\nb\n#endif", "b");
+  check("#if -1 < 0 && ~0 == -1 && !0\na\n#endif", "a");
+  check(
+    "#if -1 > 0u && (1 ? -1 : 0u) > 0 && !(-1 > 0)\na\n#endif",
+    "a",
+  );
+  check(
+    "#if -2 / 2u == 0x7fffffffffffffff && -3 % 2u == 1\na\n#endif",
+    "a",
+  );
+  check(
+    "#if -1 >> 63 == -1 && -1u >> 63 == 1 && (1 << 63) < 0\na\n#endif",
+    "a",
+  );
+  check("#define F(x) x * 2\n#if F(3) == 6\na\n#endif", "a");
+
+  let (_, errs) = expand("#if 1 / 0\n#endif\n#else\n#endif\n#if 1 +\n#endif\n#if 1\n");
+  let messages: Vec<_> = errs.iter().map(|e| e.message.as_str()).collect();
+  assert!(messages[0].starts_with("division by zero in #if"));
+  assert!(messages[1].starts_with("#else without #if"));
+  assert!(messages[2].starts_with("#endif without #if"));
+  assert!(messages[3].starts_with("unexpected end of line in #if expression"));
+  assert!(messages[4].starts_with("unterminated conditional directive"));
+}
@@ -0,0 +1,209 @@
+use crate::diagnostic::{Diagnostic, Label};
+use std::fmt::Write;
+
+pub type FileId = usize;
+
+// ----- Span -----
+
+/// A byte range `[start, end)` in a source file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+  pub file: FileId,
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  pub fn new(file: FileId, start: usize, end: usize) -> Span {
+    Span { file, start, end }
+  }
+
+  /// Returns the span covering both `self` and `other`.
+  pub fn to(self, other: Span) -> Span {
+    if self.file != other.file {
+      return self;
+    }
+    Span {
+      file: self.file,
+      start: std::cmp::min(self.start, other.start),
+      end: std::cmp::max(self.end, other.end),
+    }
+  }
+}
+
+// ----- SourceFile -----
+
+pub struct SourceFile {
+  name: String,
+  src: String,
+  line_starts: Vec<usize>,
+  include_site: Option<Span>,
+}
+
+impl SourceFile {
+  pub fn new(name: String, src: String) -> SourceFile {
+    let mut line_starts = vec![0];
+    line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+    SourceFile {
+      name,
+      src,
+      line_starts,
+      include_site: None,
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn src(&self) -> &str {
+    &self.src
+  }
+
+  /// Returns the span of the `#include` directive that brought in this file.
+  pub fn include_site(&self) -> Option<Span> {
+    self.include_site
+  }
+
+  /// Returns the 1-based line and column of the byte offset `pos`.
+  pub fn line_col(&self, pos: usize) -> (usize, usize) {
+    let line = match self.line_starts.binary_search(&pos) {
+      Ok(line) => line,
+      Err(line) => line - 1,
+    };
+    let start = self.line_starts[line];
+    let end = std::cmp::min(pos, self.src.len());
+    let col = self.src[start..end].chars().count();
+    (line + 1, col + 1)
+  }
+
+  /// Returns the text of the 1-based line `line` without its line break.
+  pub fn line(&self, line: usize) -> &str {
+    let start = self.line_starts[line - 1];
+    let end = self
+      .line_starts
+      .get(line)
+      .map(|&end| end - 1)
+      .unwrap_or(self.src.len());
+    self.src[start..end].trim_end_matches('\r')
+  }
+}
+
+// ----- SourceMap -----
+
+#[derive(Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+  pub fn new() -> SourceMap {
+    SourceMap { files: Vec::new() }
+  }
+
+  pub fn add_file(&mut self, name: String, src: String) -> FileId {
+    self.files.push(SourceFile::new(name, src));
+    self.files.len() - 1
+  }
+
+  /// Adds a file brought in by the `#include` directive at `site`.
+  pub fn add_included_file(&mut self, name: String, src: String, site: Span) -> FileId {
+    let file = self.add_file(name, src);
+    self.files[file].include_site = Some(site);
+    file
+  }
+
+  pub fn get(&self, file: FileId) -> &SourceFile {
+    &self.files[file]
+  }
+
+  /// Renders `diag` with the source lines its labels point at, underlined with `^` for the
+  /// primary label and `-` for secondary labels.
+  pub fn render(&self, diag: &Diagnostic) -> String {
+    let mut res = format!("{}: {}", diag.severity, diag.message);
+    for label in &diag.labels {
+      if let Some(file) = self.files.get(label.span.file) {
+        render_label(&mut res, file, label);
+      }
+    }
+    let mut site = diag
+      .span()
+      .and_then(|span| self.files.get(span.file)?.include_site);
+    while let Some(span) = site {
+      let file = &self.files[span.file];
+      let (line, col) = file.line_col(span.start);
+      write!(
+        res,
+        "\n  = note: in file included from {}:{}:{}",
+        file.name(),
+        line,
+        col
+      )
+      .unwrap();
+      site = file.include_site;
+    }
+    for note in &diag.notes {
+      write!(res, "\n  = note: {}", note).unwrap();
+    }
+    res
+  }
+}
+
+fn render_label(res: &mut String, file: &SourceFile, label: &Label) {
+  let span = label.span;
+  let (line, col) = file.line_col(span.start);
+  let text = file.line(line);
+  let gutter = line.to_string().len();
+  // The underline covers the span, but never runs past the end of the first line.
+  let len = file.src()[span.start..std::cmp::max(span.start, span.end)]
+    .chars()
+    .take_while(|&c| c != '\n')
+    .count();
+  let len = std::cmp::max(len, 1);
+  let pad: String = text
+    .chars()
+    .take(col - 1)
+    .map(|c| if c == '\t' { '\t' } else { ' ' })
+    .collect();
+  let marker = if label.primary { "^" } else { "-" };
+  write!(res, "\n{:gutter$}--> {}:{}:{}", "", file.name(), line, col).unwrap();
+  write!(res, "\n{:gutter$} |", "").unwrap();
+  write!(res, "\n{} | {}", line, text).unwrap();
+  write!(res, "\n{:gutter$} | {}{}", "", pad, marker.repeat(len)).unwrap();
+  if !label.message.is_empty() {
+    write!(res, " {}", label.message).unwrap();
+  }
+}
+
+#[test]
+fn test_source_map() {
+  let mut sm = SourceMap::new();
+  let file = sm.add_file("a.c".to_string(), "int x;\nint y = @;\n".to_string());
+  assert_eq!(sm.get(file).line_col(0), (1, 1));
+  assert_eq!(sm.get(file).line_col(15), (2, 9));
+  assert_eq!(sm.get(file).line(2), "int y = @;");
+
+  let diag = Diagnostic::error("unexpected character").at(Span::new(file, 15, 16));
+  let expected = "error: unexpected character
+ --> a.c:2:9
+  |
+2 | int y = @;
+  |         ^";
+  assert_eq!(sm.render(&diag), expected);
+
+  let diag = Diagnostic::error("redefinition of `y`")
+    .with_primary(Span::new(file, 11, 12), "redefined here")
+    .with_label(Span::new(file, 4, 5), "previous definition")
+    .with_note("each variable may only be defined once");
+  let expected = "error: redefinition of `y`
+ --> a.c:2:5
+  |
+2 | int y = @;
+  |     ^ redefined here
+ --> a.c:1:5
+  |
+1 | int x;
+  |     - previous definition
+  = note: each variable may only be defined once";
+  assert_eq!(sm.render(&diag), expected);
+}
@@ -1,13 +1,74 @@
-use crate::{common::Expected, err};
+use crate::diagnostic::Diagnostic;
+use crate::source::{FileId, Span};
+use crate::ty::Type;
+use crate::{common::Expected, diag, err};
+use std::fmt;
+use std::rc::Rc;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Token<'a> {
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
   Eof,
-  Keyword(&'a str),
+  Keyword(&'static str),
   Ident(String),
-  Num(i64),
-  Str(String),
-  Punct(&'a str),
+  /// An integer constant and its type. Unsigned values are stored as their bit pattern.
+  Num(i64, Type),
+  /// A floating constant and its type, which is `float` or `double`.
+  Float(f64, Type),
+  /// A string literal, which may contain any bytes.
+  Str(Vec<u8>),
+  Punct(&'static str),
+}
+
+impl fmt::Display for TokenKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TokenKind::Eof => write!(f, "end of file"),
+      TokenKind::Keyword(keyword) => write!(f, "keyword `{}`", keyword),
+      TokenKind::Ident(name) => write!(f, "identifier `{}`", name),
+      TokenKind::Num(n, ty) if ty.is_unsigned() => write!(f, "number `{}`", *n as u64),
+      TokenKind::Num(n, _) => write!(f, "number `{}`", n),
+      TokenKind::Float(x, _) => write!(f, "number `{}`", x),
+      TokenKind::Str(_) => write!(f, "string literal"),
+      TokenKind::Punct(op) => write!(f, "`{}`", op),
+    }
+  }
+}
+
+/// The macro expansion a token was produced by.
+#[derive(Debug)]
+pub struct Expansion {
+  pub name: String,
+  /// Span of the macro name in its `#define`.
+  pub def: Span,
+  /// Span of the macro invocation.
+  pub site: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+  pub kind: TokenKind,
+  /// Span of the token, or of the macro invocation if the token comes from a macro body.
+  pub span: Span,
+  /// Whether the token is the first one on its line.
+  pub bol: bool,
+  /// Whether the token is preceded by whitespace or a comment.
+  pub space: bool,
+  pub expansion: Option<Rc<Expansion>>,
+  /// Macros that must not be expanded again from this token.
+  pub(crate) hideset: Vec<String>,
+}
+
+impl Token {
+  pub fn new(kind: TokenKind, span: Span) -> Token {
+    Token {
+      kind,
+      span,
+      bol: false,
+      space: false,
+      expansion: None,
+      hideset: Vec::new(),
+    }
+  }
 }
 
 /// Finds the first occurence of `pat` in `s`, starting at position `pos`.
@@ -15,121 +76,436 @@ fn find_str(s: &str, pat: &str, pos: usize) -> Option<usize> {
   s[pos..].find(pat).map(|offset| pos + offset)
 }
 
-fn string_literal_end(s: &str, mut i: usize) -> Option<usize> {
-  while i < s.len() {
-    match &s[i..i + 1] {
-      "\"" => return Some(i),
-      "\n" => return None,
-      "\\" => i += 2,
+/// Returns the position of the `quote` that closes the literal whose contents start at `i`.
+fn literal_end(s: &str, mut i: usize, quote: u8) -> Option<usize> {
+  let bytes = s.as_bytes();
+  while i < bytes.len() {
+    match bytes[i] {
+      c if c == quote => return Some(i),
+      b'\n' => return None,
+      b'\\' => i += 2,
       _ => i += 1,
     }
   }
-  return None;
+  None
 }
 
-fn read_escaped_char(s: String) -> String {
-  let mut res = String::with_capacity(s.len());
+/// Replaces the escape sequences in `s`, the contents of a string literal or character constant
+/// at position `pos` of `file`, by the bytes they stand for.
+fn read_escaped_char(file: FileId, pos: usize, s: &str) -> Expected<Vec<u8>> {
+  let bytes = s.as_bytes();
+  let mut res = Vec::with_capacity(bytes.len());
   let mut i = 0;
-  while i < s.len() {
-    if &s[i..i + 1] == "\\" && i + 1 != s.len() {
-      let ch = match &s[i + 1..i + 2] {
-        "a" => "\x07",
-        "b" => "\x08",
-        "t" => "\x09",
-        "n" => "\x0a",
-        "v" => "\x0b",
-        "f" => "\x0c",
-        "r" => "\x0d",
-        ch => &ch,
-      };
-      res.push_str(&ch);
-      i += 2;
-    } else {
-      res.push_str(&s[i..i + 1]);
+  while i < bytes.len() {
+    if bytes[i] != b'\\' || i + 1 == bytes.len() {
+      res.push(bytes[i]);
       i += 1;
+      continue;
     }
+    let start = i;
+    i += 2;
+    let ch = match bytes[start + 1] {
+      b'a' => 0x07,
+      b'b' => 0x08,
+      b't' => 0x09,
+      b'n' => 0x0a,
+      b'v' => 0x0b,
+      b'f' => 0x0c,
+      b'r' => 0x0d,
+      // a GNU extension for the escape character
+      b'e' => 0x1b,
+      b'0'..=b'7' => {
+        // up to three octal digits
+        let len = bytes[start + 1..]
+          .iter()
+          .take(3)
+          .take_while(|c| matches!(c, b'0'..=b'7'))
+          .count();
+        i = start + 1 + len;
+        let value = u32::from_str_radix(&s[start + 1..i], 8).unwrap();
+        value.try_into().map_err(|_| {
+          let span = Span::new(file, pos + start, pos + i);
+          diag!(span, "octal escape sequence out of range")
+        })?
+      }
+      b'x' => {
+        let len = bytes[i..]
+          .iter()
+          .take_while(|c| c.is_ascii_hexdigit())
+          .count();
+        i += len;
+        let span = Span::new(file, pos + start, pos + i);
+        if len == 0 {
+          return err!(span, "\\x used with no following hex digits");
+        }
+        let digits = s[start + 2..i].trim_start_matches('0');
+        match u8::from_str_radix(if digits.is_empty() { "0" } else { digits }, 16) {
+          Ok(value) => value,
+          Err(_) => return err!(span, "hex escape sequence out of range"),
+        }
+      }
+      // `\\`, `\'`, `\"`, `\?` and unknown escapes stand for the character itself
+      _ => {
+        let len = s[start + 1..].chars().next().unwrap().len_utf8();
+        i = start + 1 + len;
+        res.extend_from_slice(&bytes[start + 1..i]);
+        continue;
+      }
+    };
+    res.push(ch);
   }
-  res
+  Ok(res)
 }
 
-fn tokenize<'a>(s: &'a str) -> (Expected<Token<'a>>, &'a str) {
-  static KEYWORDS: [&str; 10] = [
-    "return", "if", "else", "for", "while", "break", "continue", "int", "char", "struct",
+/// Returns the type of an integer constant with `value`, which is the first type in the list
+/// for its suffix the value fits in, or `None` if there is no such type. Decimal constants
+/// without a `u` suffix have only signed types in their lists.
+fn integer_literal_type(value: u64, decimal: bool, suffix: &str) -> Option<Type> {
+  let suffix = suffix.to_ascii_lowercase();
+  let unsigned = suffix.contains('u');
+  let candidates: &[Type] = match suffix.trim_matches('u') {
+    "" => &[
+      Type::Int,
+      Type::UInt,
+      Type::Long,
+      Type::ULong,
+      Type::LongLong,
+      Type::ULongLong,
+    ],
+    "l" => &[Type::Long, Type::ULong, Type::LongLong, Type::ULongLong],
+    _ => &[Type::LongLong, Type::ULongLong],
+  };
+  candidates
+    .iter()
+    .filter(|ty| {
+      if unsigned {
+        ty.is_unsigned()
+      } else {
+        !decimal || !ty.is_unsigned()
+      }
+    })
+    .find(|ty| {
+      let bits = ty.layout(&|_| None).unwrap().size * 8 - !ty.is_unsigned() as u32;
+      bits == 64 || value >> bits == 0
+    })
+    .cloned()
+}
+
+/// Reads the integer constant `s`, which consists of the digits of a decimal, octal (`0`),
+/// hexadecimal (`0x`) or binary (`0b`) constant followed by a suffix of `u`, `l` or `ll`.
+fn integer_literal(file: FileId, pos: usize, s: &str) -> Expected<TokenKind> {
+  let span = Span::new(file, pos, pos + s.len());
+  let lower = s.to_ascii_lowercase();
+  let (radix, prefix_len) = if lower.starts_with("0x") {
+    (16, 2)
+  } else if lower.starts_with("0b") {
+    (2, 2)
+  } else if s.starts_with('0') {
+    (8, 1)
+  } else {
+    (10, 0)
+  };
+  // `8` and `9` are read as part of an octal constant to be reported as invalid digits
+  let digits_len = s[prefix_len..]
+    .find(|c: char| !(c.is_digit(radix) || radix == 8 && c.is_ascii_digit()))
+    .unwrap_or(s.len() - prefix_len);
+  let (digits, suffix) = s[prefix_len..].split_at(digits_len);
+  static SUFFIXES: [&str; 7] = ["", "u", "l", "ul", "lu", "ll", "ull"];
+  let valid_suffix = (SUFFIXES.contains(&suffix.to_ascii_lowercase().as_str())
+    || suffix.eq_ignore_ascii_case("llu"))
+    && !suffix.contains("lL")
+    && !suffix.contains("Ll");
+  if digits.is_empty() && radix != 8 {
+    return err!(span, "integer constant `{}` has no digits", s);
+  } else if !valid_suffix {
+    return err!(
+      span,
+      "invalid suffix `{}` on integer constant",
+      &s[prefix_len + digits_len..]
+    );
+  }
+  if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+    return err!(span, "invalid digit `{}` in octal constant", c);
+  }
+  let value = if digits.is_empty() {
+    Ok(0)
+  } else {
+    u64::from_str_radix(digits, radix)
+  };
+  match value
+    .ok()
+    .and_then(|value| Some((value, integer_literal_type(value, radix == 10, suffix)?)))
+  {
+    Some((value, ty)) => Ok(TokenKind::Num(value as i64, ty)),
+    None => err!(span, "integer literal `{}` is too large", s),
+  }
+}
+
+/// Returns the length of the decimal floating constant at the start of `s`, excluding its suffix,
+/// or `None` if `s` does not start with one. A floating constant has a fraction part, an
+/// exponent part or both, as in `1.5`, `.5`, `1.` or `1e-3`.
+fn float_literal_len(s: &str) -> Option<usize> {
+  let bytes = s.as_bytes();
+  let digits = |i: usize| i + bytes[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+  let int_end = digits(0);
+  let mut end = int_end;
+  let mut is_float = false;
+  if bytes.get(end) == Some(&b'.') {
+    end = digits(end + 1);
+    // a lone `.` is not a number
+    is_float = int_end > 0 || end > int_end + 1;
+  }
+  if is_float || int_end > 0 {
+    if let Some(b'e' | b'E') = bytes.get(end) {
+      let sign = matches!(bytes.get(end + 1), Some(b'+' | b'-')) as usize;
+      if bytes.get(end + 1 + sign).is_some_and(u8::is_ascii_digit) {
+        end = digits(end + 1 + sign);
+        is_float = true;
+      }
+    }
+  }
+  if is_float {
+    Some(end)
+  } else {
+    None
+  }
+}
+
+/// Reads the token starting at or after `pos`, and returns it with the position just past it.
+fn tokenize(file: FileId, src: &str, pos: usize) -> (Expected<Token>, usize) {
+  static KEYWORDS: [&str; 30] = [
+    "return", "if", "else", "for", "while", "do", "break", "continue", "goto", "switch", "case",
+    "default", "void", "_Bool", "char", "short", "int", "long", "float", "double", "signed",
+    "unsigned", "struct", "union", "enum", "typedef", "static", "extern", "sizeof", "_Alignof",
+  ];
+  static THREE_CHAR_OPS: [&str; 3] = ["...", "<<=", ">>="];
+  static TWO_CHAR_OPS: [&str; 20] = [
+    "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "++", "--", "##", "&&",
+    "||", "<<", ">>", "->",
   ];
-  static TWO_CHAR_OPS: [&str; 10] = ["==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "++", "--"];
+  static ONE_CHAR_OPS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
 
+  let s = &src[pos..];
+  let token = |kind, len: usize| {
+    let span = Span::new(file, pos, pos + len);
+    let mut tok = Token::new(kind, span);
+    tok.bol = pos == 0;
+    (Ok(tok), pos + len)
+  };
   if s.is_empty() {
-    (Ok(Token::Eof), s)
+    token(TokenKind::Eof, 0)
   } else if s.starts_with(|c: char| c.is_ascii_whitespace()) {
-    let pos = s
+    let len = s
       .find(|c: char| !c.is_ascii_whitespace())
       .unwrap_or(s.len());
-    tokenize(&s[pos..])
+    let newline = s[..len].contains('\n');
+    let (item, end) = tokenize(file, src, pos + len);
+    let item = item.map(|tok| Token {
+      bol: tok.bol || newline,
+      space: true,
+      ..tok
+    });
+    (item, end)
   } else if s.starts_with(|c: char| c == '_' || c.is_ascii_alphabetic()) {
-    let pos = s
+    let len = s
       .find(|c: char| c != '_' && !c.is_ascii_alphabetic() && !c.is_ascii_digit())
       .unwrap_or(s.len());
-    if KEYWORDS.contains(&&s[..pos]) {
-      (Ok(Token::Keyword(&s[..pos])), &s[pos..])
+    if let Some(&keyword) = KEYWORDS.iter().find(|&&keyword| keyword == &s[..len]) {
+      token(TokenKind::Keyword(keyword), len)
     } else {
-      (Ok(Token::Ident(s[..pos].to_string())), &s[pos..])
+      token(TokenKind::Ident(s[..len].to_string()), len)
     }
-  } else if s.starts_with(|c: char| c.is_ascii_digit()) {
-    let pos = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
-    let tok = if let Ok(num) = s[..pos].parse() {
-      Ok(Token::Num(num))
+  } else if let Some(len) = float_literal_len(s) {
+    // a suffix `f` makes the constant a `float`, and `l` a `long double`, which is `double`
+    let (ty, suffix_len) = match s[len..].chars().next() {
+      Some('f' | 'F') => (Type::Float, 1),
+      Some('l' | 'L') => (Type::Double, 1),
+      _ => (Type::Double, 0),
+    };
+    let value = if ty == Type::Float {
+      s[..len].parse::<f32>().map(f64::from)
     } else {
-      err!("failed to read integer")
+      s[..len].parse::<f64>()
     };
-    (tok, &s[pos..])
+    token(TokenKind::Float(value.unwrap(), ty), len + suffix_len)
+  } else if s.starts_with(|c: char| c.is_ascii_digit()) {
+    let len = s
+      .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+      .unwrap_or(s.len());
+    match integer_literal(file, pos, &s[..len]) {
+      Ok(kind) => token(kind, len),
+      Err(e) => (Err(e), pos + len),
+    }
   } else if s.starts_with('"') {
-    if let Some(pos) = string_literal_end(s, 1) {
-      let data = read_escaped_char(s[1..pos].to_string());
-      (Ok(Token::Str(data)), &s[pos + 1..])
+    if let Some(end) = literal_end(s, 1, b'"') {
+      match read_escaped_char(file, pos + 1, &s[1..end]) {
+        Ok(data) => token(TokenKind::Str(data), end + 1),
+        Err(e) => (Err(e), pos + end + 1),
+      }
     } else {
-      (err!("missing terminating `\"` character"), &s[s.len()..])
+      let span = Span::new(file, pos, pos + 1);
+      (err!(span, "missing terminating `\"` character"), src.len())
     }
-  } else if s.starts_with("//") {
-    let pos = s.find('\n').unwrap_or(s.len());
-    tokenize(&s[pos..])
-  } else if s.starts_with("/*") {
-    if let Some(pos) = find_str(s, "*/", 2) {
-      tokenize(&s[pos + 2..])
+  } else if s.starts_with('\'') {
+    // a character constant has type `int` and the value of its `char`, which is signed
+    if let Some(end) = literal_end(s, 1, b'\'') {
+      let span = Span::new(file, pos, pos + end + 1);
+      match read_escaped_char(file, pos + 1, &s[1..end]).as_deref() {
+        Ok(&[c]) => token(TokenKind::Num(c as i8 as i64, Type::Int), end + 1),
+        Ok([]) => (err!(span, "empty character constant"), pos + end + 1),
+        Ok(_) => (
+          err!(
+            span,
+            "multi-character character constants are not supported"
+          ),
+          pos + end + 1,
+        ),
+        Err(e) => (Err(e.clone()), pos + end + 1),
+      }
     } else {
-      (err!("unterminated block comment"), &s[s.len()..])
+      let span = Span::new(file, pos, pos + 1);
+      (err!(span, "missing terminating `'` character"), src.len())
     }
-  } else if s.starts_with(|c: char| c.is_ascii_punctuation()) {
-    if s.len() < 2 {
-      (Ok(Token::Punct(&s[..1])), &s[1..])
-    } else if TWO_CHAR_OPS.contains(&&s[..2]) {
-      (Ok(Token::Punct(&s[..2])), &s[2..])
+  } else if s.starts_with("//") {
+    let len = s.find('\n').unwrap_or(s.len());
+    let (item, end) = tokenize(file, src, pos + len);
+    (item.map(|tok| Token { space: true, ..tok }), end)
+  } else if s.starts_with("/*") {
+    if let Some(end) = find_str(s, "*/", 2) {
+      let (item, end) = tokenize(file, src, pos + end + 2);
+      (item.map(|tok| Token { space: true, ..tok }), end)
     } else {
-      (Ok(Token::Punct(&s[..1])), &s[1..])
+      let span = Span::new(file, pos, pos + 2);
+      (err!(span, "unterminated block comment"), src.len())
     }
+  } else if let Some(&op) = THREE_CHAR_OPS.iter().find(|&&op| s.starts_with(op)) {
+    token(TokenKind::Punct(op), 3)
+  } else if let Some(&op) = TWO_CHAR_OPS.iter().find(|&&op| s.starts_with(op)) {
+    token(TokenKind::Punct(op), 2)
+  } else if let Some(i) = ONE_CHAR_OPS.find(s.chars().next().unwrap()) {
+    token(TokenKind::Punct(&ONE_CHAR_OPS[i..i + 1]), 1)
   } else {
-    (err!("unexpected character"), &s[1..])
+    let c = s.chars().next().unwrap();
+    let span = Span::new(file, pos, pos + c.len_utf8());
+    (
+      err!(span, "unexpected character `{}`", c),
+      pos + c.len_utf8(),
+    )
+  }
+}
+
+/// Splits the whole of `src` into tokens. The result always ends with `TokenKind::Eof`.
+pub fn lex(file: FileId, src: &str) -> Vec<Expected<Token>> {
+  let mut items = Vec::new();
+  let mut pos = 0;
+  loop {
+    let (item, end) = tokenize(file, src, pos);
+    let eof = matches!(
+      item,
+      Ok(Token {
+        kind: TokenKind::Eof,
+        ..
+      })
+    );
+    items.push(item);
+    if eof {
+      break items;
+    }
+    pos = end;
   }
 }
 
+/// A cursor over a stream of tokens, which is either lexed from a single source or produced
+/// by the preprocessor.
 #[derive(Clone)]
-pub struct Tokenizer<'a> {
-  item: Expected<Token<'a>>,
-  input: &'a str,
+pub struct Tokenizer {
+  items: Rc<[Expected<Token>]>,
+  idx: usize,
+  prev_span: Span,
+  diags: Vec<Diagnostic>,
 }
 
-impl<'a> Tokenizer<'a> {
-  pub fn new(input: &'a str) -> Tokenizer {
-    let (item, input) = tokenize(input);
-    Tokenizer { item, input }
+/// A saved position of a `Tokenizer`, to which it can backtrack.
+#[derive(Clone, Copy)]
+pub struct Position {
+  idx: usize,
+  prev_span: Span,
+}
+
+impl Tokenizer {
+  pub fn new(input: &str) -> Tokenizer {
+    Tokenizer::with_file(0, input)
+  }
+
+  /// Creates a tokenizer whose spans point into `file`.
+  pub fn with_file(file: FileId, input: &str) -> Tokenizer {
+    Tokenizer::from_tokens(lex(file, input))
+  }
+
+  /// Creates a tokenizer over `items`, which must end with `TokenKind::Eof`.
+  pub fn from_tokens(items: Vec<Expected<Token>>) -> Tokenizer {
+    let prev_span = match items.first() {
+      Some(Ok(tok)) => Span::new(tok.span.file, tok.span.start, tok.span.start),
+      _ => Span::default(),
+    };
+    Tokenizer {
+      items: items.into(),
+      idx: 0,
+      prev_span,
+      diags: Vec::new(),
+    }
+  }
+
+  pub fn advance(&mut self) {
+    self.prev_span = self.span();
+    if self.idx + 1 < self.items.len() {
+      self.idx += 1;
+    }
+  }
+
+  pub fn current(&mut self) -> Expected<Token> {
+    self.items[self.idx].clone()
+  }
+
+  /// Returns the token `n` tokens ahead of the current one, without consuming any token.
+  pub fn peek(&self, n: usize) -> Expected<Token> {
+    let idx = std::cmp::min(self.idx + n, self.items.len() - 1);
+    self.items[idx].clone()
+  }
+
+  /// Returns the span of the current token.
+  pub fn span(&self) -> Span {
+    match &self.items[self.idx] {
+      Ok(tok) => tok.span,
+      Err(e) => e.span().unwrap_or(self.prev_span),
+    }
+  }
+
+  /// Returns the span of the most recently consumed token.
+  pub fn prev_span(&self) -> Span {
+    self.prev_span
+  }
+
+  /// Records a diagnostic found while producing the tokens, such as a preprocessing error.
+  pub fn report(&mut self, diag: Diagnostic) {
+    self.diags.push(diag);
+  }
+
+  pub fn position(&self) -> Position {
+    Position {
+      idx: self.idx,
+      prev_span: self.prev_span,
+    }
   }
 
-  pub fn advance(&mut self) -> () {
-    let (item, input) = tokenize(self.input);
-    self.item = item;
-    self.input = input;
+  /// Moves back to a position saved by `position`.
+  pub fn rewind(&mut self, pos: Position) {
+    self.idx = pos.idx;
+    self.prev_span = pos.prev_span;
   }
 
-  pub fn current(&mut self) -> Expected<Token<'a>> {
-    self.item.clone()
+  /// Takes the diagnostics recorded so far.
+  pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+    std::mem::take(&mut self.diags)
   }
 }
@@ -1,21 +1,429 @@
+use crate::common::JoinView;
+use std::cmp;
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Type {
-  Int,
+  Void,
+  Bool,
   Char,
+  UChar,
+  Short,
+  UShort,
+  Int,
+  UInt,
+  Long,
+  ULong,
+  LongLong,
+  ULongLong,
+  Float,
+  Double,
   Pointer(Box<Type>),
-  Array(Box<Type>, u32),
-  FunTy(Box<Type>, Vec<Type>, Vec<String>),
+  /// An array type with its element type and length, which is `None` for an array of unknown
+  /// size until an initializer completes it.
+  Array(Box<Type>, Option<u32>),
+  /// A function type with its return type, parameter types and names, and whether it is
+  /// variadic.
+  FunTy(Box<Type>, Vec<Type>, Vec<String>, bool),
   Struct(Option<String>, Option<(Vec<Type>, Vec<String>)>),
+  Union(Option<String>, Option<(Vec<Type>, Vec<String>)>),
 }
 
 impl fmt::Display for Type {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
-      Type::Int => write!(f, "int"),
+      Type::Void => write!(f, "void"),
+      Type::Bool => write!(f, "_Bool"),
       Type::Char => write!(f, "char"),
-      _ => todo!(),
+      Type::UChar => write!(f, "unsigned char"),
+      Type::Short => write!(f, "short"),
+      Type::UShort => write!(f, "unsigned short"),
+      Type::Int => write!(f, "int"),
+      Type::UInt => write!(f, "unsigned int"),
+      Type::Long => write!(f, "long"),
+      Type::ULong => write!(f, "unsigned long"),
+      Type::LongLong => write!(f, "long long"),
+      Type::ULongLong => write!(f, "unsigned long long"),
+      Type::Float => write!(f, "float"),
+      Type::Double => write!(f, "double"),
+      Type::Pointer(ty) => write!(f, "{}*", ty),
+      Type::Array(ty, Some(size)) => write!(f, "{}[{}]", ty, size),
+      Type::Array(ty, None) => write!(f, "{}[]", ty),
+      Type::FunTy(ret_ty, param_tys, _param_names, variadic) => {
+        write!(f, "{}({}", ret_ty, JoinView::new(param_tys.iter(), ", "))?;
+        if *variadic {
+          write!(f, ", ...")?;
+        }
+        write!(f, ")")
+      }
+      Type::Struct(Some(name), _) => write!(f, "struct {}", name),
+      Type::Struct(None, _) => write!(f, "struct <anonymous>"),
+      Type::Union(Some(name), _) => write!(f, "union {}", name),
+      Type::Union(None, _) => write!(f, "union <anonymous>"),
+    }
+  }
+}
+
+/// Size and alignment of a type in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+  pub size: u32,
+  pub align: u32,
+}
+
+impl Layout {
+  pub fn new(size: u32, align: u32) -> Layout {
+    Layout { size, align }
+  }
+}
+
+impl Type {
+  /// Returns the layout of the type on x86-64, or `None` if the type is incomplete or too large
+  /// for its size to fit in `u32`. `tags`
+  /// returns the member types of a struct or union the type refers to by tag only.
+  pub fn layout(&self, tags: &dyn Fn(&Type) -> Option<Vec<Type>>) -> Option<Layout> {
+    match self {
+      Type::Void => None,
+      Type::Bool | Type::Char | Type::UChar => Some(Layout::new(1, 1)),
+      Type::Short | Type::UShort => Some(Layout::new(2, 2)),
+      Type::Int | Type::UInt => Some(Layout::new(4, 4)),
+      Type::Long | Type::ULong | Type::LongLong | Type::ULongLong => Some(Layout::new(8, 8)),
+      Type::Float => Some(Layout::new(4, 4)),
+      Type::Double => Some(Layout::new(8, 8)),
+      Type::Pointer(_) => Some(Layout::new(8, 8)),
+      Type::Array(ty, len) => {
+        let len = (*len)?;
+        let layout = ty.layout(tags)?;
+        Some(Layout::new(layout.size.checked_mul(len)?, layout.align))
+      }
+      Type::FunTy(..) => None,
+      Type::Struct(_, Some((mem_tys, _))) => Some(struct_layout(mem_tys, tags)?.0),
+      Type::Struct(_, None) => Some(struct_layout(&tags(self)?, tags)?.0),
+      Type::Union(_, Some((mem_tys, _))) => union_layout(mem_tys, tags),
+      Type::Union(_, None) => union_layout(&tags(self)?, tags),
+    }
+  }
+
+  /// Returns whether the type is an integer type, which includes `_Bool` and `char`.
+  pub fn is_integer(&self) -> bool {
+    self.rank().is_some()
+  }
+
+  /// Returns whether the type is a floating type.
+  pub fn is_float(&self) -> bool {
+    matches!(self, Type::Float | Type::Double)
+  }
+
+  /// Returns whether the type is an arithmetic type, which is an integer or floating type.
+  pub fn is_arithmetic(&self) -> bool {
+    self.is_integer() || self.is_float()
+  }
+
+  /// Returns whether the type is an unsigned integer type. `_Bool` is unsigned, and `char` is
+  /// signed as on x86-64.
+  pub fn is_unsigned(&self) -> bool {
+    matches!(
+      self,
+      Type::Bool | Type::UChar | Type::UShort | Type::UInt | Type::ULong | Type::ULongLong
+    )
+  }
+
+  /// Returns the integer conversion rank of the type, or `None` if it is not an integer type.
+  fn rank(&self) -> Option<u32> {
+    match self {
+      Type::Bool => Some(0),
+      Type::Char | Type::UChar => Some(1),
+      Type::Short | Type::UShort => Some(2),
+      Type::Int | Type::UInt => Some(3),
+      Type::Long | Type::ULong => Some(4),
+      Type::LongLong | Type::ULongLong => Some(5),
+      _ => None,
     }
   }
+
+  /// Returns the unsigned integer type corresponding to the type.
+  pub fn to_unsigned(&self) -> Type {
+    match self {
+      Type::Char => Type::UChar,
+      Type::Short => Type::UShort,
+      Type::Int => Type::UInt,
+      Type::Long => Type::ULong,
+      Type::LongLong => Type::ULongLong,
+      ty => ty.clone(),
+    }
+  }
+
+  /// Returns the type a value of the type is converted to by the integer promotions. Integer
+  /// types of lower rank than `int` are promoted to `int`, which can represent all of their
+  /// values, and other types are left as they are.
+  pub fn promote(&self) -> Type {
+    match self.rank() {
+      Some(rank) if rank < Type::Int.rank().unwrap() => Type::Int,
+      _ => self.clone(),
+    }
+  }
+
+  /// Returns the common type the operands of types `lhs` and `rhs` are converted to by the usual
+  /// arithmetic conversions, or `None` if either of them is not an arithmetic type.
+  pub fn common_type(lhs: &Type, rhs: &Type) -> Option<Type> {
+    if !lhs.is_arithmetic() || !rhs.is_arithmetic() {
+      return None;
+    } else if *lhs == Type::Double || *rhs == Type::Double {
+      return Some(Type::Double);
+    } else if *lhs == Type::Float || *rhs == Type::Float {
+      return Some(Type::Float);
+    }
+    let (lhs, rhs) = (lhs.promote(), rhs.promote());
+    let (lhs_rank, rhs_rank) = (lhs.rank()?, rhs.rank()?);
+    if lhs == rhs {
+      return Some(lhs);
+    }
+    let (signed, unsigned, signed_rank, unsigned_rank) =
+      match (lhs.is_unsigned(), rhs.is_unsigned()) {
+        (false, false) | (true, true) => {
+          return Some(if lhs_rank >= rhs_rank { lhs } else { rhs });
+        }
+        (false, true) => (lhs, rhs, lhs_rank, rhs_rank),
+        (true, false) => (rhs, lhs, rhs_rank, lhs_rank),
+      };
+    let size = |ty: &Type| ty.layout(&|_| None).unwrap().size;
+    if unsigned_rank >= signed_rank {
+      Some(unsigned)
+    } else if size(&signed) > size(&unsigned) {
+      // the signed type can represent all values of the unsigned type
+      Some(signed)
+    } else {
+      Some(signed.to_unsigned())
+    }
+  }
+}
+
+/// Returns the layout of a struct with members of `mem_tys`, together with the offset of each
+/// member. Each member is placed at the next offset aligned for it, and the struct is padded
+/// to a multiple of its alignment. Returns `None` if a member is incomplete or the struct is
+/// too large.
+pub fn struct_layout(
+  mem_tys: &[Type],
+  tags: &dyn Fn(&Type) -> Option<Vec<Type>>,
+) -> Option<(Layout, Vec<u32>)> {
+  let mut size = 0;
+  let mut align = 1;
+  let mut offsets = Vec::with_capacity(mem_tys.len());
+  for ty in mem_tys {
+    let layout = ty.layout(tags)?;
+    let offset = align_to(size, layout.align)?;
+    offsets.push(offset);
+    size = offset.checked_add(layout.size)?;
+    align = cmp::max(align, layout.align);
+  }
+  Some((Layout::new(align_to(size, align)?, align), offsets))
+}
+
+/// Returns the layout of a union with members of `mem_tys`, which all start at offset 0.
+pub fn union_layout(mem_tys: &[Type], tags: &dyn Fn(&Type) -> Option<Vec<Type>>) -> Option<Layout> {
+  let mut size = 0;
+  let mut align = 1;
+  for ty in mem_tys {
+    let layout = ty.layout(tags)?;
+    size = cmp::max(size, layout.size);
+    align = cmp::max(align, layout.align);
+  }
+  Some(Layout::new(align_to(size, align)?, align))
+}
+
+/// Register class of an eightbyte of a struct or union passed by value on x86-64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegClass {
+  /// Passed in a general purpose register.
+  Integer,
+  /// Passed in a vector register, as every member in it is of a floating type.
+  Sse,
+}
+
+/// Returns the classes of the eightbytes of a struct or union of type `ty` by the System V
+/// x86-64 ABI, or `None` if it is larger than 16 bytes and is passed in memory. `ty` must be
+/// complete.
+pub fn classify(ty: &Type, tags: &dyn Fn(&Type) -> Option<Vec<Type>>) -> Option<Vec<RegClass>> {
+  let size = ty.layout(tags)?.size;
+  if size > 16 {
+    return None;
+  }
+  let mut classes = vec![None; size.div_ceil(8) as usize];
+  classify_impl(ty, 0, tags, &mut classes);
+  // an eightbyte of padding only is passed as an integer
+  Some(
+    classes
+      .into_iter()
+      .map(|class| class.unwrap_or(RegClass::Integer))
+      .collect(),
+  )
+}
+
+/// Merges the classes of the scalars in `ty` at `offset` into the eightbytes they occupy. An
+/// eightbyte is `Sse` only if all of its scalars are of a floating type.
+fn classify_impl(
+  ty: &Type,
+  offset: u32,
+  tags: &dyn Fn(&Type) -> Option<Vec<Type>>,
+  classes: &mut [Option<RegClass>],
+) {
+  match ty {
+    Type::Array(elem_ty, len) => {
+      let size = elem_ty.layout(tags).unwrap().size;
+      for i in 0..len.unwrap() {
+        classify_impl(elem_ty, offset + i * size, tags, classes);
+      }
+    }
+    Type::Struct(_, mems) => {
+      let mem_tys = match mems {
+        Some((mem_tys, _)) => mem_tys.clone(),
+        None => tags(ty).unwrap(),
+      };
+      let (_, offsets) = struct_layout(&mem_tys, tags).unwrap();
+      for (mem_ty, mem_offset) in std::iter::zip(&mem_tys, offsets) {
+        classify_impl(mem_ty, offset + mem_offset, tags, classes);
+      }
+    }
+    Type::Union(_, mems) => {
+      let mem_tys = match mems {
+        Some((mem_tys, _)) => mem_tys.clone(),
+        None => tags(ty).unwrap(),
+      };
+      for mem_ty in &mem_tys {
+        classify_impl(mem_ty, offset, tags, classes);
+      }
+    }
+    ty => {
+      let class = &mut classes[offset as usize / 8];
+      *class = match (*class, ty.is_float()) {
+        (None | Some(RegClass::Sse), true) => Some(RegClass::Sse),
+        _ => Some(RegClass::Integer),
+      };
+    }
+  }
+}
+
+/// Rounds `n` up to the nearest multiple of `align`, or returns `None` if it overflows.
+pub fn align_to(n: u32, align: u32) -> Option<u32> {
+  n.checked_next_multiple_of(align)
+}
+
+#[test]
+fn test_layout() {
+  let no_tags = |_: &Type| None;
+  let mems = |tys: Vec<Type>| {
+    let names = (0..tys.len()).map(|i| format!("m{}", i)).collect();
+    Some((tys, names))
+  };
+  let int_ptr = Type::Pointer(Box::new(Type::Int));
+  assert_eq!(Type::Char.layout(&no_tags), Some(Layout::new(1, 1)));
+  assert_eq!(int_ptr.layout(&no_tags), Some(Layout::new(8, 8)));
+  let array = Type::Array(Box::new(Type::Char), Some(10));
+  assert_eq!(array.layout(&no_tags), Some(Layout::new(10, 1)));
+
+  let tys = vec![Type::Char, Type::Int, Type::Char];
+  let (layout, offsets) = struct_layout(&tys, &no_tags).unwrap();
+  assert_eq!(layout, Layout::new(12, 4));
+  assert_eq!(offsets, [0, 4, 8]);
+  let empty = Type::Struct(None, mems(Vec::new()));
+  assert_eq!(empty.layout(&no_tags), Some(Layout::new(0, 1)));
+
+  let union = Type::Union(None, mems(vec![array.clone(), Type::Int]));
+  assert_eq!(union.layout(&no_tags), Some(Layout::new(12, 4)));
+  let tys = vec![Type::Int, Type::Long, Type::Short];
+  let (layout, offsets) = struct_layout(&tys, &no_tags).unwrap();
+  assert_eq!(layout, Layout::new(24, 8));
+  assert_eq!(offsets, [0, 8, 16]);
+
+  // `struct s { char a[10]; }` referred to by tag
+  let tagged = Type::Struct(Some("s".to_string()), None);
+  let tags = |ty: &Type| match ty {
+    Type::Struct(Some(name), None) if name == "s" => Some(vec![array.clone()]),
+    _ => None,
+  };
+  let outer = Type::Struct(None, mems(vec![Type::Char, tagged.clone()]));
+  assert_eq!(outer.layout(&tags), Some(Layout::new(11, 1)));
+  assert_eq!(tagged.layout(&no_tags), None);
+}
+
+#[test]
+fn test_classify() {
+  use RegClass::{Integer, Sse};
+  let no_tags = |_: &Type| None;
+  let mems = |tys: Vec<Type>| {
+    let names = (0..tys.len()).map(|i| format!("m{}", i)).collect();
+    Some((tys, names))
+  };
+  let ints = Type::Struct(None, mems(vec![Type::Int, Type::Int]));
+  assert_eq!(classify(&ints, &no_tags), Some(vec![Integer]));
+  let longs = Type::Struct(None, mems(vec![Type::Long, Type::Int]));
+  assert_eq!(classify(&longs, &no_tags), Some(vec![Integer, Integer]));
+  let mixed = Type::Struct(None, mems(vec![Type::Double, Type::Char]));
+  assert_eq!(classify(&mixed, &no_tags), Some(vec![Sse, Integer]));
+  let floats = Type::Struct(None, mems(vec![Type::Float, Type::Float, Type::Float]));
+  assert_eq!(classify(&floats, &no_tags), Some(vec![Sse, Sse]));
+  // a float sharing an eightbyte with an integer is passed as an integer
+  let shared = Type::Struct(None, mems(vec![Type::Float, Type::Short, Type::Double]));
+  assert_eq!(classify(&shared, &no_tags), Some(vec![Integer, Sse]));
+  let array = Type::Struct(None, mems(vec![Type::Array(Box::new(Type::Char), Some(3))]));
+  assert_eq!(classify(&array, &no_tags), Some(vec![Integer]));
+  let union = Type::Union(None, mems(vec![Type::Float, Type::Double]));
+  assert_eq!(classify(&union, &no_tags), Some(vec![Sse]));
+  let large = Type::Struct(None, mems(vec![Type::Long, Type::Long, Type::Int]));
+  assert_eq!(classify(&large, &no_tags), None);
+  let empty = Type::Struct(None, mems(Vec::new()));
+  assert_eq!(classify(&empty, &no_tags), Some(Vec::new()));
+}
+
+#[test]
+fn test_common_type() {
+  assert_eq!(Type::Char.promote(), Type::Int);
+  assert_eq!(Type::Bool.promote(), Type::Int);
+  assert_eq!(Type::UShort.promote(), Type::Int);
+  assert_eq!(Type::ULong.promote(), Type::ULong);
+  assert_eq!(
+    Type::common_type(&Type::Char, &Type::Short),
+    Some(Type::Int)
+  );
+  assert_eq!(Type::common_type(&Type::Int, &Type::Long), Some(Type::Long));
+  assert_eq!(Type::common_type(&Type::UInt, &Type::Int), Some(Type::UInt));
+  assert_eq!(Type::common_type(&Type::UChar, &Type::Int), Some(Type::Int));
+  // `long` can represent all values of `unsigned int`, which is narrower
+  assert_eq!(
+    Type::common_type(&Type::UInt, &Type::Long),
+    Some(Type::Long)
+  );
+  assert_eq!(
+    Type::common_type(&Type::UInt, &Type::LongLong),
+    Some(Type::LongLong)
+  );
+  assert_eq!(
+    Type::common_type(&Type::Int, &Type::ULong),
+    Some(Type::ULong)
+  );
+  assert_eq!(
+    Type::common_type(&Type::UInt, &Type::ULong),
+    Some(Type::ULong)
+  );
+  assert_eq!(
+    Type::common_type(&Type::Long, &Type::LongLong),
+    Some(Type::LongLong)
+  );
+  // `long long` cannot represent all values of `unsigned long`, which has the same size
+  assert_eq!(
+    Type::common_type(&Type::LongLong, &Type::ULong),
+    Some(Type::ULongLong)
+  );
+  assert_eq!(
+    Type::common_type(&Type::Float, &Type::ULong),
+    Some(Type::Float)
+  );
+  assert_eq!(
+    Type::common_type(&Type::Float, &Type::Double),
+    Some(Type::Double)
+  );
+  assert_eq!(Type::Float.promote(), Type::Float);
+  let int_ptr = Type::Pointer(Box::new(Type::Int));
+  assert_eq!(Type::common_type(&int_ptr, &Type::Int), None);
+  assert_eq!(Type::common_type(&int_ptr, &Type::Double), None);
 }
@@ -0,0 +1,152 @@
+#include "test.h"
+
+struct small
+{
+  char a;
+  short b;
+};
+struct pair
+{
+  long a;
+  long b;
+};
+struct mixed
+{
+  double d;
+  char c;
+};
+struct floats
+{
+  float x, y, z;
+};
+struct big
+{
+  long a[4];
+};
+struct ints
+{
+  int a;
+  int b;
+};
+struct triple
+{
+  int a, b, c;
+};
+
+long small_sum(struct small s);
+struct pair pair_make(long a, long b);
+double mixed_sum(struct mixed m);
+struct floats floats_scale(struct floats f, float k);
+long big_sum(struct big b);
+struct big big_make(long n);
+long pair_last(long a, long b, long c, long d, long e, struct pair p);
+long call_pair(long (*f)(struct pair p), long a, long b);
+long call_big(struct big (*f)(long n), long n);
+int ints_sum(struct ints s);
+struct ints ints_make(int a, int b);
+int call_ints(int (*f)(struct ints s), int a, int b);
+int triple_sum(struct triple t);
+struct triple triple_make(int a, int b, int c);
+
+long pair_diff(struct pair p) { return p.a - p.b; }
+int ints_diff(struct ints s) { return s.a - s.b; }
+
+struct big big_iota(long n)
+{
+  struct big b;
+  int i;
+  for (i = 0; i < 4; i++)
+    b.a[i] = n + i;
+  return b;
+}
+
+struct pair pair_swap(struct pair p)
+{
+  struct pair q;
+  q.a = p.b;
+  q.b = p.a;
+  return q;
+}
+
+struct small small_make(char a, short b)
+{
+  struct small s;
+  s.a = a;
+  s.b = b;
+  return s;
+}
+
+struct mixed mixed_make(double d, char c)
+{
+  struct mixed m;
+  m.d = d;
+  m.c = c;
+  return m;
+}
+
+struct floats floats_add(struct floats f, struct floats g)
+{
+  struct floats h;
+  h.x = f.x + g.x;
+  h.y = f.y + g.y;
+  h.z = f.z + g.z;
+  return h;
+}
+
+struct big big_add(struct big b, long n)
+{
+  int i;
+  for (i = 0; i < 4; i++)
+    b.a[i] = b.a[i] + n;
+  return b;
+}
+
+long big_first(struct big *p) { return p->a[0]; }
+
+long many(long a, long b, long c, long d, long e, struct pair p) { return a + b + c + d + e + p.b; }
+
+int main()
+{
+  struct small s = {1, 2};
+  struct pair p = {5, 3};
+  struct mixed m = {1.5, 2};
+  struct floats f = {1, 2, 3};
+  struct big b = {{1, 2, 3, 4}};
+
+  // calling C
+  ASSERT(3, small_sum(s));
+  ASSERT(7, pair_make(7, 8).a);
+  ASSERT(8, pair_make(7, 8).b);
+  ASSERT(3, (int)(mixed_sum(m) * 2 - 4));
+  ASSERT(9, (int)floats_scale(f, 3).z);
+  ASSERT(10, big_sum(b));
+  ASSERT(13, big_make(2).a[3] + big_make(2).a[2] * 2);
+  ASSERT(18, pair_last(1, 2, 3, 4, 5, p));
+  ASSERT(12, ({ struct ints t = {1, 2}; ints_sum(t); }));
+  ASSERT(7, ints_make(7, 8).a);
+  ASSERT(8, ints_make(7, 8).b);
+  ASSERT(123, ({ struct triple t = {1, 2, 3}; triple_sum(t); }));
+  ASSERT(6, triple_make(4, 5, 6).c);
+
+  // called from C
+  ASSERT(2, call_pair(pair_diff, 5, 3));
+  ASSERT(26, call_big(big_iota, 5));
+  ASSERT(-4, call_ints(ints_diff, 5, 9));
+
+  // calling compiled functions
+  ASSERT(3, pair_swap(p).a);
+  ASSERT(5, pair_swap(p).b);
+  ASSERT(5, pair_swap(pair_swap(p)).a);
+  ASSERT(7, ({ struct small t = small_make(3, 4); t.a + t.b; }));
+  ASSERT(2, mixed_make(2.5, 2).c);
+  ASSERT(5, (int)(mixed_make(2.5, 2).d * 2));
+  ASSERT(6, (int)floats_add(f, f).z);
+  ASSERT(9, big_add(b, 2).a[3] + big_add(b, 1).a[0] * 3 - 3);
+  ASSERT(1, b.a[0]);
+  ASSERT(4, ({ struct big c = big_add(b, 3); big_first(&c); }));
+  ASSERT(18, many(1, 2, 3, 4, 5, p));
+  ASSERT(2, ({ struct pair (*fp)(struct pair p) = pair_swap; fp(pair_make(1, 2)).a; }));
+  ASSERT(3, ({ struct pair q; q = pair_swap(p); q.a; }));
+
+  return 0;
+}
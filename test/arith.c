@@ -1,5 +1,12 @@
 #include "test.h"
 
+int calls;
+int *count_call(int *p)
+{
+  calls = calls + 1;
+  return p;
+}
+
 int main()
 {
   ASSERT(0, 0);
@@ -32,5 +39,72 @@ int main()
   ASSERT(1, 1 >= 1);
   ASSERT(0, 1 >= 2);
 
+  ASSERT(5, 17 % 6);
+  ASSERT(-5, -17 % 6);
+  ASSERT(2, ({ int i=10; i%=4; i; }));
+
+  ASSERT(-1, ~0);
+  ASSERT(0, ~-1);
+  ASSERT(0, 0&1);
+  ASSERT(1, 3&1);
+  ASSERT(3, 7&3);
+  ASSERT(10, -1&10);
+  ASSERT(1, 0|1);
+  ASSERT(3, 1|2);
+  ASSERT(0, 0^0);
+  ASSERT(0, 7^7);
+  ASSERT(5, 3^6);
+  ASSERT(2, ({ int i=6; i&=3; i; }));
+  ASSERT(7, ({ int i=6; i|=3; i; }));
+  ASSERT(10, ({ int i=15; i^=5; i; }));
+
+  ASSERT(1, 1<<0);
+  ASSERT(8, 1<<3);
+  ASSERT(10, 5<<1);
+  ASSERT(2, 5>>1);
+  ASSERT(-1, -1>>1);
+  ASSERT(1, ({ int i=1; i<<=0; i; }));
+  ASSERT(8, ({ int i=1; i<<=3; i; }));
+  ASSERT(10, ({ int i=5; i<<=1; i; }));
+  ASSERT(2, ({ int i=5; i>>=1; i; }));
+  ASSERT(-1, ({ int i=-1; i>>=1; i; }));
+  // the lhs of a compound assignment is evaluated once
+  ASSERT(109, ({ int a[2]={1, 2}; int i=0; a[i++] |= 8; i * 100 + a[0]; }));
+  ASSERT(13, ({ int x=3; calls=0; *count_call(&x) <<= 2; x + calls; }));
+  ASSERT(22, ({ int a[2]={0, 0}; int i=0; ++a[i++]; --a[i++]; i * 10 + a[0] - a[1]; }));
+  ASSERT(3, ({ int x=3; calls=0; (*count_call(&x))++; calls + 2; }));
+
+  ASSERT(1, 1 | 2 & 0);
+  ASSERT(7, 4 ^ 2 | 1 ^ 0);
+  ASSERT(1, 5 & 3 == 3);
+  ASSERT(4, 1 << 2 + 1 >> 1);
+  ASSERT(1, 1 < 1 << 1);
+
+  ASSERT(0, !1);
+  ASSERT(0, !2);
+  ASSERT(1, !0);
+  ASSERT(0, !!0);
+  ASSERT(1, !!-3);
+  ASSERT(1, 0||1);
+  ASSERT(1, 0||(2-2)||5);
+  ASSERT(0, 0||0);
+  ASSERT(0, 0||(2-2));
+  ASSERT(0, 0&&1);
+  ASSERT(0, (2-2)&&5);
+  ASSERT(1, 1&&5);
+  ASSERT(1, 1 || 0 && 0);
+  ASSERT(1, 2 & 1 || 1);
+
+  ASSERT(3, ({ int x=0; (x=1) && (x=x+2); x; }));
+  ASSERT(0, ({ int x=0; x && (x=5); x; }));
+  ASSERT(1, ({ int x=0; (x=1) || (x=5); x; }));
+  ASSERT(5, ({ int x=0; x || (x=5); x; }));
+  ASSERT(2, ({ int x=0; (x=x+1) && (x=x+1) || (x=x+10); x; }));
+  ASSERT(10, ({ int x=0; (x=x+1) && (x=x-1) || (x=x+10); x; }));
+  ASSERT(3, ({ char *p="abc"; int n=0; for (; p && *p; p=p+1) n=n+1; n; }));
+  ASSERT(0, ({ char *p="abc"; !p; }));
+  ASSERT(1, ({ char *p="abc"; p ? 1 : 0; }));
+  ASSERT(7, ({ int x=1; x && (x=2) ? x+5 : 0; }));
+
   return 0;
 }
@@ -21,5 +21,34 @@ int main()
   ASSERT(10, ({ int i=0; while(i<10) i=i+1; i; }));
   ASSERT(55, ({ int i=0; int j=0; while(i<=10) {j=i+j; i=i+1;} j; }));
 
+  ASSERT(5, ({ int i=0; switch(0) { case 0:i=5;break; case 1:i=6;break; case 2:i=7;break; } i; }));
+  ASSERT(6, ({ int i=0; switch(1) { case 0:i=5;break; case 1:i=6;break; case 2:i=7;break; } i; }));
+  ASSERT(7, ({ int i=0; switch(2) { case 0:i=5;break; case 1:i=6;break; case 2:i=7;break; } i; }));
+  ASSERT(0, ({ int i=0; switch(3) { case 0:i=5;break; case 1:i=6;break; case 2:i=7;break; } i; }));
+  ASSERT(5, ({ int i=0; switch(0) { case 0:i=5;break; default:i=7; } i; }));
+  ASSERT(7, ({ int i=0; switch(1) { case 0:i=5;break; default:i=7; } i; }));
+  ASSERT(2, ({ int i=0; switch(1) { case 0: 0; case 1: 0; case 2: 0; i=2; } i; }));
+  ASSERT(0, ({ int i=0; switch(3) { case 0: 0; case 1: 0; case 2: 0; i=2; } i; }));
+  ASSERT(6, ({ int i=0; switch(1) { case 1: i=i+1; case 2: i=i+2; default: i=i+3; } i; }));
+  ASSERT(8, ({ int i=0; switch(4) { default: i=5; case 1: i=i+3; } i; }));
+  ASSERT(1, ({ int i=0; switch(2*3-4) { case 1+1: i=1; break; case 3: i=3; } i; }));
+  ASSERT(4, ({ int i=0; switch(2) { case 1: { case 2: i=4; } } i; }));
+  ASSERT(8, ({ int i=0; int j=0; for (i=0; i<6; i=i+1) switch(i%3) { case 0: continue; case 1: j=j+1; break; default: j=j+3; } j; }));
+  ASSERT(10, ({ int i=0; int j=0; for (;;) { switch(i) { case 10: break; default: i=i+1; continue; } break; } i; }));
+  ASSERT(3, ({ int i=0; switch(1) { case 1: switch(2) { case 1: i=1; break; case 2: i=2; } i=i+1; } i; }));
+
+  ASSERT(7, ({ int i=0; int j=0; do { j++; } while (i++ < 6); j; }));
+  ASSERT(4, ({ int i=0; int j=0; int k=0; do { if (++j > 3) break; continue; k++; } while (1); j; }));
+  ASSERT(1, ({ int i=0; do i++; while (0); i; }));
+
+  ASSERT(3, ({ int i=0; goto a; a: i++; b: i++; c: i++; i; }));
+  ASSERT(2, ({ int i=0; goto e; d: i++; e: i++; f: i++; i; }));
+  ASSERT(1, ({ int i=0; goto i; g: i++; h: i++; i: i++; i; }));
+  ASSERT(10, ({ int i=0; loop: i++; if (i < 10) goto loop; i; }));
+  ASSERT(1, ({ typedef int foo; goto foo; foo:; 1; }));
+  ASSERT(1, ({ int x=0; goto L1; { L1: x=1; } x; }));
+  ASSERT(3, ({ int x=0; goto L2; x=5; if (x) { x=2; L2: x=x+3; } x; }));
+  ASSERT(2, ({ int i=0; goto L3; while (i < 5) { L3: i++; if (i == 2) break; } i; }));
+
   return 0;
 }
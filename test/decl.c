@@ -0,0 +1,55 @@
+#include "test.h"
+
+int add(int, int);
+int apply(int (*)(int, int), int, int);
+int sum(int (*)[3], int);
+
+int add(int x, int y) { return x + y; }
+int apply(int (*f)(int, int), int x, int y) { return f(x, y); }
+
+int sum(int (*a)[3], int n)
+{
+  int s = 0;
+  int i;
+  int j;
+  for (i = 0; i < n; i++)
+    for (j = 0; j < 3; j++)
+      s += a[i][j];
+  return s;
+}
+
+int *(*pick(int n))(int *, int *);
+int *first(int *a, int *b) { return a; }
+int *second(int *a, int *b) { return b; }
+int *(*pick(int n))(int *, int *) { return n ? second : first; }
+
+int main()
+{
+  ASSERT(6, ({ int a[2][3]; a[1][2] = 6; a[1][2]; }));
+  ASSERT(5, ({ int a[2][3][4]; a[1][2][3] = 5; a[1][2][3]; }));
+  ASSERT(24, ({ enum { N = 4 }; int a[N + 2]; sizeof(a); }));
+  ASSERT(12, ({ char a[2 * 3][sizeof(short)]; sizeof(a); }));
+  ASSERT(21, ({ int a[2][3]; int i; int j; for (i = 0; i < 2; i++) for (j = 0; j < 3; j++) a[i][j] = i * 3 + j + 1; sum(a, 2); }));
+  ASSERT(4, ({ int a[2][3]; int (*p)[3] = a; a[1][0] = 4; p[1][0]; }));
+  ASSERT(7, ({ int a[2][3]; int (*p)[3] = a + 1; (*p)[2] = 7; a[1][2]; }));
+  ASSERT(3, ({ int x = 3; int *a[2]; a[1] = &x; *a[1]; }));
+
+  ASSERT(1, ({ long x = 257; *(char *)&x; }));
+  ASSERT(2, ({ char a[2]; a[1] = 2; *((char *)(long)a + 1); }));
+  ASSERT(3, ({ int x = 3; void *p = &x; *(int *)p; }));
+  ASSERT(1, ({ int x = 0; (long)&x == (long)(char *)&x; }));
+
+  ASSERT(1, sizeof(char [1]));
+  ASSERT(8, sizeof(char *));
+  ASSERT(6, sizeof(char [2][3]));
+  ASSERT(8, sizeof(char (*)[3]));
+  ASSERT(16, sizeof(char *[2]));
+  ASSERT(8, sizeof(int (*)(int, int)));
+  ASSERT(8, sizeof(long (*)[3][4]));
+
+  ASSERT(5, apply(add, 2, 3));
+  ASSERT(1, ({ int x = 1; int y = 2; *pick(0)(&x, &y); }));
+  ASSERT(2, ({ int x = 1; int y = 2; *pick(1)(&x, &y); }));
+
+  return 0;
+}
@@ -0,0 +1,29 @@
+#include "test.h"
+
+enum color { RED, GREEN = 5, BLUE };
+typedef enum { ZERO, ONE, TWO, } num;
+
+int main()
+{
+  ASSERT(0, ({ enum { zero, one, two }; zero; }));
+  ASSERT(1, ({ enum { zero, one, two }; one; }));
+  ASSERT(2, ({ enum { zero, one, two }; two; }));
+  ASSERT(5, ({ enum { five=5, six, seven }; five; }));
+  ASSERT(6, ({ enum { five=5, six, seven }; six; }));
+  ASSERT(0, ({ enum { zero, five=5, three=3, four }; zero; }));
+  ASSERT(5, ({ enum { zero, five=5, three=3, four }; five; }));
+  ASSERT(3, ({ enum { zero, five=5, three=3, four }; three; }));
+  ASSERT(4, ({ enum { zero, five=5, three=3, four }; four; }));
+  ASSERT(9, ({ enum { a=2*3, b=a+3 }; b; }));
+  ASSERT(1, ({ enum { a=-1, b, c }; c; }));
+
+  ASSERT(0, RED);
+  ASSERT(6, BLUE);
+  ASSERT(5, ({ enum color c=GREEN; c; }));
+  ASSERT(2, ({ num n=TWO; n; }));
+  ASSERT(3, ({ int RED=3; RED; }));
+  ASSERT(7, ({ enum { RED=7 }; RED; }));
+  ASSERT(0, RED);
+
+  return 0;
+}
@@ -0,0 +1,68 @@
+#include "test.h"
+
+float add_float(float x, float y) { return x + y; }
+double add_double(double x, double y) { return x + y; }
+int to_int(double x) { return x; }
+double to_double(int x) { return x; }
+
+int main()
+{
+  ASSERT(4, sizeof(float));
+  ASSERT(8, sizeof(double));
+  ASSERT(8, sizeof(long double));
+  ASSERT(4, sizeof(1.5f));
+  ASSERT(8, sizeof(1.5));
+  ASSERT(8, sizeof(.5e1));
+
+  ASSERT(3, (int)3.99);
+  ASSERT(-3, (int)-3.99);
+  ASSERT(1, (_Bool)0.1);
+  ASSERT(0, (_Bool)0.0);
+  ASSERT(5, (char)5.5);
+  ASSERT(255, (unsigned char)255.5);
+  ASSERT(1, 1e3 == 1000);
+  ASSERT(1, 1.5e-1 == 0.15);
+  ASSERT(1, 10. == 10);
+
+  ASSERT(1, 0.1f + 0.2f == 0.3f);
+  ASSERT(0, 0.1 + 0.2 == 0.3);
+  ASSERT(1, 0.1f != 0.1);
+  ASSERT(1, (float)0.1 == 0.1f);
+  ASSERT(7, (int)(1.5 * 5));
+  ASSERT(2, (int)(5.0 / 2));
+  ASSERT(-2, (int)(0.5 - 2.5));
+  ASSERT(1, 2.5 < 3);
+  ASSERT(0, 3.0 < 3);
+  ASSERT(1, 3.0 <= 3);
+  ASSERT(1, 3.5 > 3);
+  ASSERT(1, -0.5 < 0);
+
+  ASSERT(0, !3.5);
+  ASSERT(1, !0.0);
+  ASSERT(1, 0.5 && 2);
+  ASSERT(0, 0.0 || 0);
+  ASSERT(3, 0.5 ? 3 : 4);
+  ASSERT(4, 0.0 ? 3 : 4);
+  ASSERT(1, ({ double x = 0.0 / 0.0; x != x; }));
+  ASSERT(0, ({ double x = 0.0 / 0.0; x == x || x < 0 || x >= 0; }));
+  ASSERT(1, 1 / -0.0 < 0);
+  ASSERT(1, ({ double x = 0.0; 1 / -x < 0; }));
+  ASSERT(1, ({ float x = 0.0; 1 / -x < 0; }));
+  ASSERT(-3, ({ int x = 3; -x; }));
+
+  ASSERT(6, ({ float x = 2.5; int i = 0; while (x < 8) { x = x + 1; i++; } i; }));
+  ASSERT(5, ({ double x = 2.5; x = x * 2; (int)x; }));
+  ASSERT(7, ({ int i = 3.9; i + 4; }));
+  ASSERT(1, ({ unsigned long x = -1; double d = x; d > 0; }));
+  ASSERT(1, ({ float x = 16777217; x == 16777216; }));
+  ASSERT(0, ({ double x = 16777217; x == 16777216; }));
+
+  ASSERT(3, (int)add_float(1.25, 1.75));
+  ASSERT(1, add_float(0.1, 0.2) == 0.3f);
+  ASSERT(0, add_double(0.1, 0.2) == 0.3);
+  ASSERT(3, to_int(3.7));
+  ASSERT(1, to_double(3) == 3.0);
+  ASSERT(5, (int)(to_double(2) + 3));
+
+  return 0;
+}
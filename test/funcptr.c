@@ -0,0 +1,82 @@
+#include "test.h"
+
+int add(int x, int y) { return x + y; }
+int sub(int x, int y) { return x - y; }
+int mul(int x, int y) { return x * y; }
+
+int apply(int (*f)(int x, int y), int x, int y) { return f(x, y); }
+int apply2(int f(int x, int y), int x, int y) { return (*f)(x, y); }
+
+int (*choose(int op))(int x, int y)
+{
+  if (op == '+')
+    return add;
+  if (op == '-')
+    return sub;
+  return mul;
+}
+
+int counter;
+void incr(int n) { counter += n; }
+
+struct handler
+{
+  char op;
+  int (*fn)(int x, int y);
+};
+
+int dispatch(struct handler *hs, int n, char op, int x, int y)
+{
+  int i;
+  for (i = 0; i < n; i++)
+    if (hs[i].op == op)
+      return hs[i].fn(x, y);
+  return -1;
+}
+
+int main()
+{
+  int (*fp)(int x, int y);
+  fp = add;
+  ASSERT(3, fp(1, 2));
+  ASSERT(3, (*fp)(1, 2));
+  fp = &sub;
+  ASSERT(-1, fp(1, 2));
+  ASSERT(7, (&add)(3, 4));
+  ASSERT(7, (*add)(3, 4));
+
+  ASSERT(5, apply(add, 2, 3));
+  ASSERT(6, apply(mul, 2, 3));
+  ASSERT(-1, apply2(sub, 2, 3));
+
+  ASSERT(9, choose('+')(4, 5));
+  ASSERT(-1, choose('-')(4, 5));
+  ASSERT(20, choose('*')(4, 5));
+
+  int (*table[3])(int x, int y);
+  table[0] = add;
+  table[1] = sub;
+  table[2] = mul;
+  ASSERT(11, table[0](5, 6));
+  ASSERT(-1, table[1](5, 6));
+  ASSERT(30, table[2](5, 6));
+  ASSERT(8, sizeof(fp));
+  ASSERT(24, sizeof(table));
+
+  void (*vf)(int n);
+  vf = incr;
+  vf(2);
+  vf(3);
+  ASSERT(5, counter);
+
+  struct handler hs[2];
+  hs[0].op = '+';
+  hs[0].fn = add;
+  hs[1].op = '*';
+  hs[1].fn = mul;
+  ASSERT(7, dispatch(hs, 2, '+', 3, 4));
+  ASSERT(12, dispatch(hs, 2, '*', 3, 4));
+  ASSERT(-1, dispatch(hs, 2, '-', 3, 4));
+
+  return 0;
+}
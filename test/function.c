@@ -31,6 +31,31 @@ int sub_char(char a, char b, char c)
   return (int)(a - b - c);
 }
 
+void set3(int *x)
+{
+  *x = 3;
+}
+
+void set_sign(int *x, int y)
+{
+  if (y < 0)
+  {
+    *x = -1;
+    return;
+  }
+  *x = 1;
+}
+
+int ret7(void)
+{
+  return 7;
+}
+
+void *id_ptr(void *p)
+{
+  return p;
+}
+
 int fib(int x)
 {
   if (x <= 1)
@@ -53,5 +78,12 @@ int main()
 
   ASSERT(1, ({ sub_char((char)7, (char)3, (char)3); }));
 
+  ASSERT(3, ({ int x = 0; set3(&x); x; }));
+  ASSERT(-1, ({ int x = 0; set_sign(&x, -5); x; }));
+  ASSERT(1, ({ int x = 0; set_sign(&x, 5); x; }));
+  ASSERT(7, ret7());
+  ASSERT(5, ({ int x = 5; int *p = id_ptr(&x); *p; }));
+  ASSERT(8, ({ void *p; sizeof(p); }));
+
   return 0;
 }
@@ -0,0 +1,108 @@
+#include "test.h"
+
+int strcmp(char *p, char *q);
+
+int g1[3] = {1, 2, 3};
+int g2[2][3] = {{1, 2, 3}, {4, 5, 6}};
+int g3[] = {1, 2, 3, 4};
+int g4[5] = {1, 2};
+char g5[] = "abc";
+char g6[2][4] = {"ab", "cde"};
+struct
+{
+  char a;
+  int b;
+  long c[2];
+} g7 = {1, 2, {3, 4}};
+struct
+{
+  int x, y;
+} g8[] = {{1, 2}, [2] = {.y = 6}};
+int g9[3][2] = {1, 2, 3, 4, 5};
+double g10[] = {1.5, 2};
+union
+{
+  long a;
+  char b;
+} g11 = {3};
+char *g12 = "xyz";
+int *g13 = &g1[1];
+
+int main()
+{
+  ASSERT(1, ({ int x[3] = {1, 2, 3}; x[0]; }));
+  ASSERT(2, ({ int x[3] = {1, 2, 3}; x[1]; }));
+  ASSERT(3, ({ int x[3] = {1, 2, 3}; x[2]; }));
+  ASSERT(2, ({ int x[2][3] = {{1, 2, 3}, {4, 5, 6}}; x[0][1]; }));
+  ASSERT(4, ({ int x[2][3] = {{1, 2, 3}, {4, 5, 6}}; x[1][0]; }));
+  ASSERT(6, ({ int x[2][3] = {{1, 2, 3}, {4, 5, 6}}; x[1][2]; }));
+  ASSERT(0, ({ int x[3] = {}; x[0]; }));
+  ASSERT(0, ({ int x[3] = {1}; x[2]; }));
+  ASSERT(2, ({ int x[2][3] = {{1, 2}}; x[0][1]; }));
+  ASSERT(0, ({ int x[2][3] = {{1, 2}}; x[1][0]; }));
+  ASSERT(5, ({ int x[2][3] = {1, 2, 3, 4, 5}; x[1][1]; }));
+  ASSERT(0, ({ int x[2][3] = {1, 2, 3, 4, 5}; x[1][2]; }));
+
+  ASSERT('a', ({ char x[4] = "abc"; x[0]; }));
+  ASSERT('c', ({ char x[4] = "abc"; x[2]; }));
+  ASSERT(0, ({ char x[4] = "abc"; x[3]; }));
+  ASSERT('c', ({ char x[3] = "abc"; x[2]; }));
+  ASSERT(0, ({ char x[8] = "abc"; x[7]; }));
+  ASSERT('e', ({ char x[2][4] = {"abc", "def"}; x[1][1]; }));
+  ASSERT(4, ({ char x[] = "abc"; sizeof(x); }));
+  ASSERT(6, ({ char x[] = "abc" "def"; sizeof(x) - 1; }));
+
+  ASSERT(3, ({ int x[] = {1, 2, 3}; sizeof(x) / sizeof(x[0]); }));
+  ASSERT(5, ({ int x[] = {1, [4] = 5}; sizeof(x) / sizeof(x[0]); }));
+  ASSERT(2, ({ int x[][2] = {{1, 2}, {3, 4}, {5}}; sizeof(x) / sizeof(x[0]) - 1; }));
+
+  ASSERT(1, ({ struct {int a; int b; int c;} x = {1, 2, 3}; x.a; }));
+  ASSERT(3, ({ struct {int a; int b; int c;} x = {1, 2, 3}; x.c; }));
+  ASSERT(0, ({ struct {int a; int b; int c;} x = {1}; x.b; }));
+  ASSERT(2, ({ struct {int a; int b;} x[2] = {{1, 2}, {3, 4}}; x[0].b; }));
+  ASSERT(3, ({ struct {int a; int b;} x[2] = {1, 2, 3, 4}; x[1].a; }));
+  ASSERT(0, ({ struct {int a; int b;} x[2] = {{1, 2}}; x[1].b; }));
+  ASSERT(4, ({ struct {int a; int b[2];} x = {1, 2, 4}; x.b[1]; }));
+
+  ASSERT(2, ({ struct {int a; int b; int c;} x = {.b = 2}; x.b; }));
+  ASSERT(0, ({ struct {int a; int b; int c;} x = {.b = 2}; x.a; }));
+  ASSERT(3, ({ struct {int a; int b; int c;} x = {.b = 2, 3}; x.c; }));
+  ASSERT(1, ({ struct {int a; int b; int c;} x = {.c = 3, .a = 1}; x.a; }));
+  ASSERT(7, ({ struct {int a; struct {int b; int c;} d;} x = {.d.c = 7}; x.d.c; }));
+  ASSERT(5, ({ int x[3] = {[2] = 5}; x[2]; }));
+  ASSERT(0, ({ int x[3] = {[2] = 5}; x[0]; }));
+  ASSERT(4, ({ int x[4] = {[1] = 2, 3, 4}; x[3]; }));
+  ASSERT(6, ({ int x[2][3] = {[1][2] = 6}; x[1][2]; }));
+  ASSERT(9, ({ int x[3] = {1, 2, 3, [0] = 9}; x[0]; }));
+
+  ASSERT(3, ({ union {int a; char b[4];} x = {3}; x.a; }));
+  ASSERT(1, ({ union {int a; char b[4];} x = {.b = {1, 2}}; x.b[0]; }));
+  ASSERT(3, ({ struct t {int a; int b;} x = {1, 3}; struct t y = x; y.b; }));
+  ASSERT(3, ({ int x = {3}; x; }));
+
+  ASSERT(1, g1[0]);
+  ASSERT(3, g1[2]);
+  ASSERT(5, g2[1][1]);
+  ASSERT(4, sizeof(g3) / sizeof(g3[0]));
+  ASSERT(4, g3[3]);
+  ASSERT(2, g4[1]);
+  ASSERT(0, g4[4]);
+  ASSERT(4, sizeof(g5));
+  ASSERT(0, strcmp(g5, "abc"));
+  ASSERT(0, strcmp(g6[1], "cde"));
+  ASSERT(1, g7.a);
+  ASSERT(2, g7.b);
+  ASSERT(4, g7.c[1]);
+  ASSERT(3, sizeof(g8) / sizeof(g8[0]));
+  ASSERT(2, g8[0].y);
+  ASSERT(0, g8[1].x);
+  ASSERT(6, g8[2].y);
+  ASSERT(5, g9[2][0]);
+  ASSERT(0, g9[2][1]);
+  ASSERT(2, g10[1]);
+  ASSERT(3, g11.a);
+  ASSERT(0, strcmp(g12, "xyz"));
+  ASSERT(2, *g13);
+
+  return 0;
+}
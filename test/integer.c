@@ -0,0 +1,66 @@
+#include "test.h"
+
+int sub_short(short a, short b) { return a - b; }
+long add_long(long a, long b) { return a + b; }
+unsigned char to_uchar(int x) { return x; }
+
+int main()
+{
+  ASSERT(1, ({ _Bool x; sizeof(x); }));
+  ASSERT(2, ({ short x; sizeof(x); }));
+  ASSERT(2, ({ unsigned short x; sizeof(x); }));
+  ASSERT(8, ({ long x; sizeof(x); }));
+  ASSERT(8, ({ long long x; sizeof(x); }));
+  ASSERT(8, ({ long unsigned int x; sizeof(x); }));
+  ASSERT(4, ({ signed x; sizeof(x); }));
+  ASSERT(4, ({ unsigned x; sizeof(x); }));
+  ASSERT(1, ({ signed char x; sizeof(x); }));
+  ASSERT(2, sizeof(short int));
+  ASSERT(8, sizeof(unsigned long long));
+
+  ASSERT(1, ({ _Bool x = 5; x; }));
+  ASSERT(0, ({ _Bool x = 0; x; }));
+  ASSERT(1, ({ _Bool x = 256; x; }));
+  ASSERT(44, ({ char x = 300; x; }));
+  ASSERT(-1, ({ char x = 255; x; }));
+  ASSERT(255, ({ unsigned char x = 255; x; }));
+  ASSERT(255, ({ unsigned char x = -1; x; }));
+  ASSERT(-1, ({ short x = 65535; x; }));
+  ASSERT(65535, ({ unsigned short x = -1; x; }));
+  ASSERT(4464, ({ short x = 70000; x; }));
+
+  ASSERT(7, sub_short(10, 3));
+  ASSERT(7, add_long(3, 4));
+  ASSERT(44, to_uchar(300));
+
+  ASSERT(1, (unsigned)-1 > 0);
+  ASSERT(0, (unsigned)-1 < 0);
+  ASSERT(0, -1 < (unsigned)0);
+  ASSERT(1, -1 < 0);
+  ASSERT(1, (unsigned char)-1 > 0);
+  ASSERT(15, (unsigned)-1 >> 28);
+  ASSERT(15, (unsigned long)-1 >> 60);
+  ASSERT(-1, -1 >> 28);
+  ASSERT(-1, -1L >> 60);
+  ASSERT(1, (unsigned)-1 / 2 > 0);
+  ASSERT(-3, -7 / 2);
+  ASSERT(1, (unsigned)-7 % 2);
+  ASSERT(-1, -7 % 2);
+
+  ASSERT(1, ({ char x = 1; x + 256 == 257; }));
+  ASSERT(256, ({ unsigned char x = 255; x + 1; }));
+  ASSERT(0, ({ unsigned char x = 255; x += 1; x; }));
+  ASSERT(-128, ({ char x = 127; x++; x; }));
+  ASSERT(255, ({ unsigned char x = 255; x++; }));
+  ASSERT(0, ({ unsigned char x = 255; x++; x; }));
+  ASSERT(0, ({ unsigned char x = 0; x--; }));
+  ASSERT(1, ({ _Bool x = 1; x++; }));
+  ASSERT(1, ({ _Bool x = 1; x++; x; }));
+  ASSERT(1, ({ _Bool x = 0; x--; x; }));
+  ASSERT(4, ({ int a[2] = {3, 4}; int *p = a; p++; *p; }));
+  ASSERT(3, ({ int a[2] = {3, 4}; int *p = a; *p++; }));
+  ASSERT(1, ({ short x = -1; unsigned long y = x; y == (unsigned long)-1; }));
+  ASSERT(1, ({ unsigned short x = -1; long y = x; y == 65535; }));
+
+  return 0;
+}
@@ -0,0 +1,49 @@
+#include "test.h"
+
+int main()
+{
+  ASSERT(97, 'a');
+  ASSERT(10, '\n');
+  ASSERT(-128, '\x80');
+  ASSERT(39, '\'');
+  ASSERT(34, '"');
+  ASSERT(0, '\0');
+  ASSERT(4, sizeof('a'));
+
+  ASSERT(511, 0777);
+  ASSERT(0, 0x0);
+  ASSERT(10, 0xa);
+  ASSERT(10, 0XA);
+  ASSERT(48879, 0xbeef);
+  ASSERT(48879, 0xBEEF);
+  ASSERT(48879, 0XBEEF);
+  ASSERT(0, 0b0);
+  ASSERT(1, 0b1);
+  ASSERT(47, 0b101111);
+  ASSERT(47, 0B101111);
+
+  ASSERT(4, sizeof(0));
+  ASSERT(8, sizeof(0L));
+  ASSERT(8, sizeof(0LU));
+  ASSERT(8, sizeof(0UL));
+  ASSERT(8, sizeof(0LL));
+  ASSERT(8, sizeof(0LLU));
+  ASSERT(8, sizeof(0Ull));
+  ASSERT(8, sizeof(0l));
+  ASSERT(8, sizeof(0ll));
+  ASSERT(8, sizeof(0x0L));
+  ASSERT(8, sizeof(0b0L));
+
+  ASSERT(-1, 0xffffffffffffffff);
+  ASSERT(1, 0xffffffffffffffff > 0);
+  ASSERT(0, -1 > 0);
+  ASSERT(1, -1 > 0u);
+  ASSERT(1, -1 > 0ul);
+  ASSERT(1, 9223372036854775807 > 0);
+  ASSERT(0, 0x8000000000000000 < 0);
+  ASSERT(1, -9223372036854775807 - 1 < 0);
+  ASSERT(4, 2u * 2);
+  ASSERT(1, (1u - 2) / 2 > 0);
+
+  return 0;
+}
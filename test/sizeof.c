@@ -0,0 +1,35 @@
+#include "test.h"
+
+struct pair { char a; int b; };
+union num { char c[10]; int i; };
+typedef int t[3];
+
+int main()
+{
+  ASSERT(1, sizeof(char));
+  ASSERT(4, sizeof(int));
+  ASSERT(8, sizeof(char *));
+  ASSERT(8, sizeof(int **));
+  ASSERT(12, sizeof(t));
+  ASSERT(8, sizeof(struct pair));
+  ASSERT(12, sizeof(union num));
+  ASSERT(3, ({ struct { char a, b, c; } x; sizeof(x); }));
+  ASSERT(12, ({ struct { char a; int b; char c; } x; sizeof(x); }));
+  ASSERT(24, ({ struct { char a; long b; char c; } x; sizeof(x); }));
+  ASSERT(12, ({ struct { struct pair p; char c; } x; sizeof(x); }));
+  ASSERT(4, ({ int x; sizeof(x + 1); }));
+  ASSERT(8, ({ int x; sizeof(x + 1L); }));
+  ASSERT(8, ({ char x[4]; sizeof(x + 1); }));
+  ASSERT(1, ({ char x[4]; sizeof(*x); }));
+  ASSERT(0, ({ int x=0; sizeof(x=3); x; }));
+
+  ASSERT(1, _Alignof(char));
+  ASSERT(4, _Alignof(int));
+  ASSERT(8, _Alignof(long));
+  ASSERT(8, _Alignof(char *));
+  ASSERT(4, _Alignof(struct pair));
+  ASSERT(4, _Alignof(union num));
+  ASSERT(1, _Alignof(struct { char a; char b[3]; }));
+
+  return 0;
+}
@@ -0,0 +1,67 @@
+#include "test.h"
+
+extern long ext_var;
+extern long ext_arr[];
+long common_helper();
+
+static long helper() { return 2; }
+
+static int counter()
+{
+  static int n;
+  n = n + 1;
+  return n;
+}
+
+int counter2()
+{
+  static int n = 10;
+  n = n + 1;
+  return n;
+}
+
+int shadowed()
+{
+  static int n = 5;
+  {
+    static int n = 100;
+    n = n + 1;
+  }
+  return n;
+}
+
+extern int later[];
+int sum_later() { return later[0] + later[2]; }
+int later[3] = {1, 2, 3};
+
+static int g = 3;
+extern int g;
+
+extern int declared;
+int declared = 6;
+
+int main()
+{
+  ASSERT(7, ext_var);
+  ASSERT(3, ext_arr[2]);
+  ASSERT(2, helper());
+  ASSERT(1, common_helper());
+
+  ASSERT(1, counter());
+  ASSERT(2, counter());
+  ASSERT(3, counter());
+  ASSERT(11, counter2());
+  ASSERT(12, counter2());
+  ASSERT(5, shadowed());
+  ASSERT(5, shadowed());
+  ASSERT(3, ({ static char s[] = "abc"; sizeof(s) - 1; }));
+  ASSERT(2, ({ static int x[2] = {1, 2}; x[1]; }));
+
+  ASSERT(4, sum_later());
+  ASSERT(3, g);
+  ASSERT(6, declared);
+  ASSERT(8, ({ extern long ext_var; ext_var = 8; ext_var; }));
+  ASSERT(8, ext_var);
+
+  return 0;
+}
@@ -3,13 +3,13 @@
 int main()
 {
   ASSERT(0, (int)""[0]);
-  // ASSERT(1, sizeof(""));
+  ASSERT(1, sizeof(""));
 
   ASSERT(97, (int)"abc"[0]);
   ASSERT(98, (int)"abc"[1]);
   ASSERT(99, (int)"abc"[2]);
   ASSERT(0, (int)"abc"[3]);
-  // ASSERT(4, sizeof("abc"));
+  ASSERT(4, sizeof("abc"));
 
   ASSERT(7, (int)"\a"[0]);
   ASSERT(8, (int)"\b"[0]);
@@ -28,12 +28,21 @@ int main()
   ASSERT(10, (int)"\ax\ny"[2]);
   ASSERT(121, (int)"\ax\ny"[3]);
 
-  // ASSERT(0, (int)"\0"[0]);
-  // ASSERT(16, (int)"\20"[0]);
-  // ASSERT(65, (int)"\101"[0]);
-  // ASSERT(104, (int)"\1500"[0]);
-  // ASSERT(0, (int)"\x00"[0]);
-  // ASSERT(119, (int)"\x77"[0]);
+  ASSERT(0, (int)"\0"[0]);
+  ASSERT(16, (int)"\20"[0]);
+  ASSERT(65, (int)"\101"[0]);
+  ASSERT(104, (int)"\1500"[0]);
+  ASSERT(0, (int)"\x00"[0]);
+  ASSERT(119, (int)"\x77"[0]);
+  ASSERT(-91, (int)"\xa5"[0]);
+  ASSERT(27, (int)"\e"[0]);
+  ASSERT(39, (int)"\'"[0]);
+  ASSERT(63, (int)"\?"[0]);
+
+  ASSERT(7, sizeof("abc" "def"));
+  ASSERT(9, sizeof("abc" "d" "efgh"));
+  ASSERT(100, (int)("abc" "def")[3]);
+  ASSERT(0, (int)("abc" "def")[6]);
 
   return 0;
 }
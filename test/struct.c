@@ -11,39 +11,58 @@ int main()
   ASSERT(3, ({ struct {int a; int b;} x[3]; x[0].a=1; x[1].b=2; x[0].a + x[1].b; }));
   ASSERT(3, ({ struct {int a[3];} x; x.a[0]=1; x.a[1]=2; x.a[0] + x.a[1]; }));
 
-  // ASSERT(0, ({ struct {int a; int b;} x[3]; int *p=x; p[0]=0; x[0].a; }));
-  // ASSERT(1, ({ struct {int a; int b;} x[3]; int *p=x; p[1]=1; x[0].b; }));
-  // ASSERT(2, ({ struct {int a; int b;} x[3]; int *p=x; p[2]=2; x[1].a; }));
-  // ASSERT(3, ({ struct {int a; int b;} x[3]; int *p=x; p[3]=3; x[1].b; }));
+  ASSERT(0, ({ struct {int a; int b;} x[3]; int *p=(int *)x; p[0]=0; x[0].a; }));
+  ASSERT(1, ({ struct {int a; int b;} x[3]; int *p=(int *)x; p[1]=1; x[0].b; }));
+  ASSERT(2, ({ struct {int a; int b;} x[3]; int *p=(int *)x; p[2]=2; x[1].a; }));
+  ASSERT(3, ({ struct {int a; int b;} x[3]; int *p=(int *)x; p[3]=3; x[1].b; }));
 
-  // ASSERT(6, ({ struct {int a[3]; int b[5];} x; int *p=&x; x.a[0]=6; p[0]; }));
-  // ASSERT(7, ({ struct {int a[3]; int b[5];} x; int *p=&x; x.b[0]=7; p[3]; }));
+  ASSERT(6, ({ struct {int a[3]; int b[5];} x; int *p=(int *)&x; x.a[0]=6; p[0]; }));
+  ASSERT(7, ({ struct {int a[3]; int b[5];} x; int *p=(int *)&x; x.b[0]=7; p[3]; }));
 
   ASSERT(6, ({ struct { struct { int b; } a; } x; x.a.b=6; x.a.b; }));
 
-  // ASSERT(8, ({ struct {int a;} x; sizeof(x); }));
-  // ASSERT(16, ({ struct {int a; int b;} x; sizeof(x); }));
-  // ASSERT(16, ({ struct {int a, b;} x; sizeof(x); }));
-  // ASSERT(24, ({ struct {int a[3];} x; sizeof(x); }));
-  // ASSERT(32, ({ struct {int a;} x[4]; sizeof(x); }));
-  // ASSERT(48, ({ struct {int a[3];} x[2]; sizeof(x); }));
-  // ASSERT(2, ({ struct {char a; char b;} x; sizeof(x); }));
-  // ASSERT(9, ({ struct {char a; int b;} x; sizeof(x); }));
-  // ASSERT(0, ({ struct {} x; sizeof(x); }));
+  ASSERT(4, ({ struct {int a;} x; sizeof(x); }));
+  ASSERT(8, ({ struct {int a; int b;} x; sizeof(x); }));
+  ASSERT(8, ({ struct {int a, b;} x; sizeof(x); }));
+  ASSERT(12, ({ struct {int a[3];} x; sizeof(x); }));
+  ASSERT(16, ({ struct {int a;} x[4]; sizeof(x); }));
+  ASSERT(24, ({ struct {int a[3];} x[2]; sizeof(x); }));
+  ASSERT(2, ({ struct {char a; char b;} x; sizeof(x); }));
+  ASSERT(8, ({ struct {char a; int b;} x; sizeof(x); }));
+  ASSERT(16, ({ struct {char a; long b;} x; sizeof(x); }));
+  ASSERT(0, ({ struct {} x; sizeof(x); }));
 
   // struct tag
   ASSERT(0, ({ struct t {int a; int b;} x; struct t y; y.a; }));
   ASSERT(0, ({ struct t {int a; int b;}; struct t y; y.a; }));
-  // ASSERT(16, ({ struct t {int a; int b;} x; struct t y; sizeof(y); }));
-  // ASSERT(16, ({ struct t {int a; int b;}; struct t y; sizeof(y); }));
-  // ASSERT(2, ({ struct t {char a[2];}; { struct t {char a[4];}; } struct t y; sizeof(y); }));
+  ASSERT(8, ({ struct t {int a; int b;} x; struct t y; sizeof(y); }));
+  ASSERT(8, ({ struct t {int a; int b;}; struct t y; sizeof(y); }));
+  ASSERT(2, ({ struct t {char a[2];}; { struct t {char a[4];}; } struct t y; sizeof(y); }));
   ASSERT(3, ({ struct t {int x;}; int t=1; struct t y; y.x=2; t+y.x; }));
 
   // struct assignment
-  // ASSERT(3, ({ struct {int a,b;} x,y; x.a=3; y=x; y.a; }));
+  ASSERT(3, ({ struct {int a,b;} x,y; x.a=3; y=x; y.a; }));
   ASSERT(7, ({ struct t {int a,b;}; struct t x; x.a=7; struct t y; struct t *z=&y; *z=x; y.a; }));
   ASSERT(7, ({ struct t {int a,b;}; struct t x; x.a=7; struct t y, *p=&x, *q=&y; *q=*p; y.a; }));
-  // ASSERT(5, ({ struct t {char a, b;} x, y; x.a=(char)5; y=x; y.a; }));
+  ASSERT(5, ({ struct t {char a, b;} x, y; x.a=(char)5; y=x; y.a; }));
+  ASSERT(3, ({ struct t {int a; char b[2];} x, y; x.b[1]=(char)3; y=x; y.b[1]; }));
+  ASSERT(4, ({ union {int a; char b;} x, y; x.a=4; y=x; y.a; }));
+
+  // arrow operator
+  ASSERT(3, ({ struct {int a,b;} x, *p=&x; p->a=3; x.a; }));
+  ASSERT(5, ({ struct {int a,b;} x, *p=&x; x.b=5; p->b; }));
+  ASSERT(7, ({ struct t {int a; struct t2 {int b;} c;} x, *p=&x; p->c.b=7; x.c.b; }));
+  ASSERT(2, ({ struct {int a[3];} x, *p=&x; p->a[2]=2; x.a[2]; }));
+  ASSERT(6, ({ struct {int a,b;} x[2], *p=x; (p+1)->a=6; x[1].a; }));
+
+  // incomplete and self-referential struct
+  ASSERT(3, ({ struct node {int v; struct node *next;} a, b; a.v=1; b.v=2; a.next=&b; b.next=&a; a.next->next->v + a.next->v; }));
+  ASSERT(7, ({ union u {int a; union u *p;} x, y; y.a=7; x.p=&y; x.p->a; }));
+  ASSERT(9, ({ struct s {struct t *p;} x; struct t {int v;} y; y.v=9; x.p=&y; x.p->v; }));
+  ASSERT(5, ({ struct t; struct t *p; struct t {int a;} x; p=&x; x.a=5; p->a; }));
+  ASSERT(8, ({ struct t *p; sizeof(p); }));
+  ASSERT(4, ({ struct t {char a;}; int n; { struct t; struct t {char a[4];} y; n=sizeof(y); } n; }));
+  ASSERT(1, ({ struct t {char a;} x; { struct t {char a[4];}; } struct t y; sizeof(y); }));
 
   return 0;
 }
@@ -1,8 +1,20 @@
 #!/bin/bash
 # usage: LLVM_SYS_120_PREFIX=/opt/homebrew/opt/llvm@12 ./test-c.sh
 cat <<EOF | clang -xc -c -o tmp2.o -
+#include <stdarg.h>
 int ret3() { return 3; }
 int ret5() { return 5; }
+int ext = 7;
+int sum(int n, ...)
+{
+  va_list ap;
+  va_start(ap, n);
+  int s = 0;
+  for (int i = 0; i < n; i++)
+    s += va_arg(ap, int);
+  va_end(ap);
+  return s;
+}
 EOF
 
 ESC=$(printf '\033')
@@ -173,6 +185,209 @@ assert 4 'int sub(); int sub(); int sub() { return 4; } int main() { return sub(
 assert 0 'int sub(int a); int sub(int b) { return b; } int main() { return sub(0); }'
 assert_fail 'int sub(); int sub(int a) { return a; } int main() { return 0; }'
 assert 21 'int sub(int a, int b, int c, int d, int e, int f); int sub(int g, int h, int i, int j, int k, int l) { return g+h+i+j+k+l; } int main() { return sub(1,2,3,4,5,6); }'
+# variadic function declaration
+assert 6 'int sum(int n, ...); int main() { return sum(3, 1, 2, 3); }'
+assert 6 'int sum(int n, ...); int main() { char c=2; return sum(2, c, 4); }'
+assert 0 'int printf(char *fmt, ...); int main() { return 0; }'
+assert_fail 'int sum(int n, ...); int sum(int n); int main() { return 0; }'
+assert_fail 'int f(int n, ...) { return n; } int main() { return f(1); }'
+
+# typedef
+assert 3 'typedef int MyInt; int main() { MyInt x=3; return x; }'
+assert 5 'typedef int MyInt; MyInt f(MyInt x) { return x+2; } int main() { return f(3); }'
+assert 4 'int main() { typedef int t; { int t=4; return t; } }'
+assert_fail 'int main() { typedef int t; t=3; return 0; }'
+assert 3 'typedef struct node { struct node *next; } Node; int main() { return 3; }'
+assert 2 'struct s { int a; }; int main() { struct t { int b; }; return 2; }'
+
+# enum
+assert 6 'enum { A, B=5, C }; int main() { return C; }'
+assert 3 'int main() { enum { x, y=x+3 }; return y; }'
+assert 4 'enum e { A=4 }; int main() { enum e v=A; return v; }'
+assert 2 'enum { A=1 }; int main() { int A=2; return A; }'
+assert_fail 'int main() { enum { A }; A=1; return 0; }'
+assert_fail 'int x; int main() { enum { A=x }; return 0; }'
+# sizeof
+assert 4 'int main() { return sizeof(int); }'
+assert 8 'int main() { return sizeof(long); }'
+assert 1 'int main() { return sizeof(char); }'
+assert 12 'int main() { int x[3]; return sizeof(x); }'
+assert 4 'int main() { int x; return sizeof x; }'
+assert 3 'int main() { int x=3; sizeof(x=5); return x; }'
+assert 4 'int main() { return _Alignof(int); }'
+# bitwise, shift and modulo
+assert 2 'int main() { return 17 % 5; }'
+assert 3 'int main() { int x=11; x %= 4; return x; }'
+assert 5 'int main() { return 7 & 13; }'
+assert 15 'int main() { return 7 | 13; }'
+assert 10 'int main() { return 7 ^ 13; }'
+assert 0 'int main() { return ~-1; }'
+assert 40 'int main() { return 5 << 3; }'
+assert 5 'int main() { return 40 >> 3; }'
+assert 6 'int main() { int x=7; x &= 14; return x; }'
+assert 15 'int main() { int x=7; x |= 8; return x; }'
+assert 9 'int main() { int x=12; x ^= 5; return x; }'
+assert 48 'int main() { int x=3; x <<= 4; return x; }'
+assert 7 'int main() { int x=1; int y=2; x += y += 4; return x; }'
+assert 3 'int main() { int x=48; x >>= 4; return x; }'
+assert 3 'int main() { return 1 | 2 & 3 ^ 1; }'
+# logical
+assert 1 'int main() { return 0 || 2; }'
+assert 0 'int main() { return 0 && 2; }'
+assert 1 'int main() { return 3 && 2; }'
+assert 1 'int main() { return !0; }'
+assert 0 'int main() { return !7; }'
+assert 3 'int main() { int x=0; (x=1) && (x=x+2); return x; }'
+assert 0 'int main() { int x=0; x && (x=5); return x; }'
+assert 1 'int main() { int x=0; (x=1) || (x=5); return x; }'
+assert 5 'int main() { int x=0; x || (x=5); return x; }'
+assert 4 'int main() { int i=0; int n=0; for (; i<10 && n<4; i=i+1) n=n+1; return i; }'
+assert 1 'int main() { if (1 && !0 || 0) return 1; return 0; }'
+# switch
+assert 6 'int main() { int i=0; switch(1) { case 0: i=5; break; case 1: i=6; break; } return i; }'
+assert 7 'int main() { int i=0; switch(3) { case 0: i=5; break; default: i=7; } return i; }'
+assert 0 'int main() { int i=0; switch(3) { case 0: i=5; break; case 1: i=6; } return i; }'
+assert 6 'int main() { int i=0; switch(1) { case 1: i=i+1; case 2: i=i+2; default: i=i+3; } return i; }'
+assert 20 'int main() { switch(2) { case 1: return 10; case 2: return 20; default: return 30; } }'
+assert 30 'int main() { switch(9) { case 1: return 10; case 2: return 20; default: return 30; } }'
+assert 8 'int main() { int i; int j=0; for (i=0; i<6; i=i+1) switch(i%3) { case 0: continue; case 1: j=j+1; break; default: j=j+3; } return j; }'
+assert_fail 'int main() { switch(1) { case 1: case 1: ; } return 0; }'
+assert_fail 'int main() { switch(1) { default: default: ; } return 0; }'
+assert_fail 'int main() { case 1: return 0; }'
+assert_fail 'int main() { int x=1; switch(1) { case x: ; } return 0; }'
+# do while
+assert 7 'int main() { int i=0; int j=0; do { j=j+1; } while (i++ < 6); return j; }'
+assert 1 'int main() { int i=0; do i=i+1; while (0); return i; }'
+assert 4 'int main() { int j=0; do { if (++j > 3) break; continue; } while (1); return j; }'
+assert 3 'int main() { do return 3; while (1); }'
+
+# goto and labels
+assert 3 'int main() { int i=0; goto a; a: i++; b: i++; c: i++; return i; }'
+assert 10 'int main() { int i=0; loop: i=i+1; if (i<10) goto loop; return i; }'
+assert 5 'int main() { int i=0; goto end; i=3; end: return i+5; }'
+assert 3 'int main() { { goto x; } x: return 3; }'
+assert 1 'int main() { int x=0; goto L; { L: x=1; } return x; }'
+assert 3 'int main() { int x=0; goto L; x=5; if (x) { x=2; L: x=x+3; } return x; }'
+assert 4 'int main() { int x=1; switch (x) { return 0; { case 1: x=4; } } return x; }'
+assert 2 'int main() { int i=0; goto b; a: return i; b: i=2; goto a; }'
+assert_fail 'int main() { goto a; return 0; }'
+assert_fail 'int main() { a: a: return 0; }'
+assert_fail 'int f() { a: return 0; } int main() { goto a; return 1; }'
+# void
+assert 3 'void f() {} int main() { f(); return 3; }'
+assert 3 'void f(int x) { if (x) return; } int main() { f(1); f(0); return 3; }'
+assert 3 'int f(void) { return 3; } int main() { return f(); }'
+assert 3 'void f(void); int main() { f(); return 3; } void f(void) { return; }'
+assert_fail 'void f() { return 1; } int main() { return 0; }'
+assert_fail 'int f() { return; } int main() { return 0; }'
+assert_fail 'void f() {} int main() { return f(); }'
+assert_fail 'int main() { void x; return 0; }'
+# integer types
+assert 1 'int main() { _Bool x=5; return x; }'
+assert 44 'int main() { char x=300; return x; }'
+assert 255 'int main() { unsigned char x=-1; return x; }'
+assert 255 'int main() { unsigned char x=255; return x++; }'
+assert 1 'int main() { unsigned char x=255; x++; return x == 0; }'
+assert 1 'int main() { _Bool x=1; return x++; }'
+assert 1 'int main() { unsigned x=-1; return x > 0; }'
+assert 15 'int main() { unsigned long x=-1; return x >> 60; }'
+assert 5 'int main() { long x=5000000000; return x / 1000000000; }'
+assert 3 'int main() { unsigned long x=-1; return x / 4611686018427387904; }'
+assert 1 'int main() { unsigned long x=-1; return x > 4294967295; }'
+assert 1 'int main() { unsigned long x=-1; x=x+2; return x; }'
+assert 4 'int main() { long x=1; return (x << 40) >> 38; }'
+assert 1 'int main() { long x=-9223372036854775807-1; return x < 0; }'
+assert 5 'long g=5000000000; int main() { return g / 1000000000; }'
+assert 1 'unsigned long g=0x8000000000000000+0x8000000000000000; int main() { return g == 0; }'
+assert 3 'int main() { unsigned x=7; return x / 2; }'
+assert 1 'int main() { unsigned x=0; x=x-1; return x / 2 == 2147483647; }'
+assert 7 'short f(short a, short b) { return a - b; } int main() { return f(10, 3); }'
+assert_fail 'int main() { signed unsigned x; return 0; }'
+
+# floating point
+assert 3 'int main() { double x=3.7; return x; }'
+assert 7 'int main() { float x=1.5; return x * 5; }'
+assert 2 'int main() { return 5.0 / 2; }'
+assert 1 'int main() { return 0.1f + 0.2f == 0.3f; }'
+assert 0 'int main() { return 0.1 + 0.2 == 0.3; }'
+assert 1 'int main() { return 2.5 < 3; }'
+assert 1 'int main() { double x=0.0; return !x; }'
+assert 1 'int main() { return 1 / -0.0 < 0; }'
+assert 1 'int main() { double x=0.0; return 1 / -x < 0; }'
+assert 1 'double g=-0.0; int main() { return 1 / g < 0; }'
+assert 2 'int main() { double x=0.5; if (x) return 2; return 3; }'
+assert 3 'double f(double x) { return x + 1; } int main() { return f(2); }'
+assert 3 'float f(float x, int y) { return x * y; } int main() { return f(1.5, 2); }'
+assert_fail 'int main() { double x=1.5; return x % 2; }'
+assert_fail 'int main() { return 1.5 << 1; }'
+
+# literals
+assert 97 "int main() { return 'a'; }"
+assert 10 "int main() { return '\\n'; }"
+assert 255 "int main() { return '\\xff' + 256; }"
+assert 65 "int main() { return '\\101'; }"
+assert 31 'int main() { return 0x1F; }'
+assert 15 'int main() { return 017; }'
+assert 5 'int main() { return 0b101; }'
+assert 42 'int main() { return 42ul; }'
+assert 1 'int main() { return -1 < 0; }'
+assert 3 'int main() { int x=-3; return -x; }'
+assert_fail 'int main() { return 08; }'
+assert_fail 'int main() { return 1lul; }'
+assert_fail "int main() { return ''; }"
+
+# variadic functions
+assert_fail 'int f(int n, ...) { return n; } int main() { return f(1, 2); }'
+
+# function pointers
+assert 3 'int add(int a, int b) { return a+b; } int main() { int (*fp)(int a, int b); fp=add; return fp(1, 2); }'
+assert 3 'int add(int a, int b) { return a+b; } int main() { int (*fp)(int a, int b); fp=&add; return (*fp)(1, 2); }'
+assert 7 'int add(int a, int b) { return a+b; } int apply(int f(int a, int b), int x) { return f(x, 4); } int main() { return apply(add, 3); }'
+assert 5 'int g() { return 5; } int (*h())() { return g; } int main() { return h()(); }'
+assert_fail 'int main() { int x; return x(); }'
+
+# declarators
+assert 1 'int main() { return (char)257; }'
+assert 8 'int main() { return sizeof(int (*)(int, char *)); }'
+assert 24 'int main() { return sizeof(int [2][3]); }'
+assert 3 'int add(int, int); int add(int a, int b) { return a+b; } int main() { return add(1, 2); }'
+assert_fail 'int add(int, int b) { return b; } int main() { return add(1, 2); }'
+
+# initializers
+assert 3 'int main() { int x={3}; return x; }'
+assert_fail 'int main() { int x[2]={1, 2}; return 0; }'
+
+# global variables
+assert 3 'int x=3; int main() { return x; }'
+assert 0 'int x; int main() { return x; }'
+assert 7 'int x; int set() { x=7; return 0; } int main() { set(); return x; }'
+assert 9 'int x=1+2*4; int main() { return x; }'
+assert 5 'double d=2.5; int main() { return d*2; }'
+assert 5 'int x=3; int main() { int x=5; return x; }'
+assert 7 'extern int ext; int main() { return ext; }'
+assert 8 'int main() { extern int ext; return ext+1; }'
+assert_fail 'int x=1; int x=2; int main() { return x; }'
+assert_fail 'int y; int x=y; int main() { return x; }'
+assert_fail 'extern char a; int a; int main() { return 0; }'
+assert_fail 'int f; int f() { return 0; } int main() { return 0; }'
+
+# storage classes
+assert 4 'static int ret3() { return 4; } int main() { return ret3(); }'
+assert 2 'static int f(); int f() { return 2; } int main() { return f(); }'
+assert 5 'extern int ret5(); int main() { return ret5(); }'
+assert 2 'static int ext=2; int main() { return ext; }'
+assert 5 'static int x=5; extern int x; int main() { extern int x; return x; }'
+assert 3 'int count() { static int n; n=n+1; return n; } int main() { count(); count(); return count(); }'
+assert 12 'int f() { static int n=10; return n=n+1; } int g() { static int n=1; return n; } int main() { f(); return f()-g()+1; }'
+assert 6 'int main() { static int n=1; { static int n=5; return n+1; } }'
+assert 4 'int f_x=3; int f() { static int x=1; return x; } int main() { return f()+f_x; }'
+assert 4 'int f() { static int x=1; return x; } int f_x=3; int main() { return f()+f_x; }'
+assert 3 'int f() { static int x=1; { static int x_1=2; { static int x=0; } return x+x_1; } } int main() { return f(); }'
+assert_fail 'int f(); static int f() { return 1; } int main() { return f(); }'
+assert_fail 'extern int x; static int x; int main() { return x; }'
+assert_fail 'int main() { int y=1; static int x=y; return x; }'
+assert_fail 'int main() { extern int x=1; return x; }'
+
 exit 0
 # addr & deref
 assert 3 'int main() { int x=3; return *&x; }'
@@ -266,8 +266,8 @@ assert 7 'int x=7; int* y=&x; int main() { return *y; }'
 assert 7 'int x, y; int main() { x=3; y=4; return x+y; }'
 assert_fail 'int x; int x; int main() { return x; }'
 assert_fail 'int x[4]=7; int main() { return x[0]; }'
-# assert 8 'int x; int main() { return sizeof(x); }'
-# assert 32 'int x[4]; int main() { return sizeof(x); }'
+assert 4 'int x; int main() { return sizeof(x); }'
+assert 16 'int x[4]; int main() { return sizeof(x); }'
 
 # cast
 assert 1 'int main() { char c=(char)1; return (int)c; }'
@@ -281,8 +281,8 @@ assert 1 'int main() { char x=(char)1; return (int)x; }'
 assert 1 'int main() { char x=(char)1; char y=(char)2; return (int)x; }'
 assert 2 'int main() { char x=(char)1; char y=(char)2; return (int)y; }'
 assert 1 'int sub_char(char a, char b, char c) { return (int)(a-b-c); } int main() { return sub_char((char)7, (char)3, (char)3); }'
-# assert 1 'int main() { char x; return sizeof(x); }'
-# assert 10 'int main() { char x[10]; return sizeof(x); }'
+assert 1 'int main() { char x; return sizeof(x); }'
+assert 10 'int main() { char x[10]; return sizeof(x); }'
 
 # string literal
 assert 97 'int main() { return (int)"abc"[0]; }'
@@ -293,8 +293,8 @@ assert 0 'int main() { return (int)""[0]; }'
 assert 195 'int main() { return (int)"abc"[0] + (int)"abc2"[1]; }'
 assert 0 'int print_str(char* str); int main() { print_str("Hello, World!"); return 0; }'
 assert_fail 'int print_str(char* str); int main() { print_str("Hello, World!); return 0; }'
-# assert 1 'int main() { return sizeof(""); }'
-# assert 4 'int main() { return sizeof("abc"); }'
+assert 1 'int main() { return sizeof(""); }'
+assert 4 'int main() { return sizeof("abc"); }'
 
 # escape sequences
 assert 7 'int main() { return (int)"\a"[0]; }'
@@ -332,6 +332,261 @@ assert_fail 'int main() { ({}); return 0; }'
 # assert_fail 'int main() { ({ return 0; }); return 1; }'
 # assert 1 'int main() { ({ 0; return 1; 2; }); return 3; }'
 
+# typedef
+assert 3 'typedef int MyInt; int main() { MyInt x=3; return x; }'
+assert 5 'typedef int MyInt; MyInt f(MyInt x) { return x+2; } int main() { return f(3); }'
+assert 4 'int main() { typedef int t; { int t=4; return t; } }'
+assert 2 'int main() { typedef int t, *p; t x=2; p y=&x; return *y; }'
+assert 3 'typedef struct P { int x; int y; } P; int main() { P p; p.x=1; p.y=2; return p.x+p.y; }'
+assert_fail 'int main() { typedef int t; t=3; return 0; }'
+
+# union
+assert 3 'int main() { union { int a; char b; } x; x.a=3; return x.a; }'
+assert 2 'int main() { union { int a; char b[8]; } x; x.a=515; return (int)x.b[1]; }'
+assert 5 'union u { int a; char b; }; int main() { union u x; x.a=5; return x.a; }'
+assert_fail 'int main() { union { int a; } x; x.b=1; return 0; }'
+assert_fail 'int main() { union u x; return 0; }'
+
+# enum
+assert 6 'enum { A, B=5, C }; int main() { return C; }'
+assert 3 'int main() { enum { x, y=x+3 }; return y; }'
+assert 4 'enum e { A=4 }; int main() { enum e v=A; return v; }'
+assert 2 'enum { A=1 }; int main() { int A=2; return A; }'
+assert_fail 'int main() { enum { A }; A=1; return 0; }'
+assert_fail 'int x; int main() { enum { A=x }; return 0; }'
+
+# sizeof
+assert 4 'int main() { return sizeof(int); }'
+assert 8 'int main() { return sizeof(long); }'
+assert 1 'int main() { return sizeof(char); }'
+assert 12 'int main() { int x[3]; return sizeof(x); }'
+assert 4 'int main() { int x; return sizeof x; }'
+assert 3 'int main() { int x=3; sizeof(x=5); return x; }'
+assert 4 'int main() { return _Alignof(int); }'
+assert 8 'struct s { char a; int b; }; int main() { return sizeof(struct s); }'
+assert 12 'struct s { char a; int b; char c; }; int main() { return sizeof(struct s); }'
+assert 12 'union u { char a[9]; int b; }; int main() { return sizeof(union u); }'
+assert 4 'struct s { char a; int b; }; int main() { return _Alignof(struct s); }'
+assert_fail 'struct s; int main() { return sizeof(struct s); }'
+
+# bitwise, shift and modulo
+assert 2 'int main() { return 17 % 5; }'
+assert 3 'int main() { int x=11; x %= 4; return x; }'
+assert 5 'int main() { return 7 & 13; }'
+assert 15 'int main() { return 7 | 13; }'
+assert 10 'int main() { return 7 ^ 13; }'
+assert 0 'int main() { return ~-1; }'
+assert 40 'int main() { return 5 << 3; }'
+assert 5 'int main() { return 40 >> 3; }'
+assert 6 'int main() { int x=7; x &= 14; return x; }'
+assert 15 'int main() { int x=7; x |= 8; return x; }'
+assert 9 'int main() { int x=12; x ^= 5; return x; }'
+assert 48 'int main() { int x=3; x <<= 4; return x; }'
+assert 7 'int main() { int x=1; int y=2; x += y += 4; return x; }'
+assert 3 'int main() { int x=48; x >>= 4; return x; }'
+assert 3 'int main() { return 1 | 2 & 3 ^ 1; }'
+assert 255 'int main() { return -1 >> 28; }'
+assert 5 'enum { A = 1 << 3 | 7 & ~1, B = A % 9 }; int main() { return B; }'
+assert_fail 'enum { A = 1 % 0 }; int main() { return A; }'
+
+# logical
+assert 1 'int main() { return 0 || 2; }'
+assert 0 'int main() { return 0 && 2; }'
+assert 1 'int main() { return 3 && 2; }'
+assert 1 'int main() { return !0; }'
+assert 0 'int main() { return !7; }'
+assert 3 'int main() { int x=0; (x=1) && (x=x+2); return x; }'
+assert 0 'int main() { int x=0; x && (x=5); return x; }'
+assert 1 'int main() { int x=0; (x=1) || (x=5); return x; }'
+assert 5 'int main() { int x=0; x || (x=5); return x; }'
+assert 4 'int main() { int i=0; int n=0; for (; i<10 && n<4; i=i+1) n=n+1; return i; }'
+assert 1 'int main() { if (1 && !0 || 0) return 1; return 0; }'
+assert 1 'enum { A = 2 && !0 || 1/1 }; int main() { return A; }'
+assert 0 'enum { A = 0 && 1/0 }; int main() { return A; }'
+assert 2 'int main() { char *p="ab"; int n=0; while (p && *p) { n=n+1; p=p+1; } return n; }'
+
+# switch
+assert 6 'int main() { int i=0; switch(1) { case 0: i=5; break; case 1: i=6; break; } return i; }'
+assert 7 'int main() { int i=0; switch(3) { case 0: i=5; break; default: i=7; } return i; }'
+assert 0 'int main() { int i=0; switch(3) { case 0: i=5; break; case 1: i=6; } return i; }'
+assert 6 'int main() { int i=0; switch(1) { case 1: i=i+1; case 2: i=i+2; default: i=i+3; } return i; }'
+assert 20 'int main() { switch(2) { case 1: return 10; case 2: return 20; default: return 30; } }'
+assert 30 'int main() { switch(9) { case 1: return 10; case 2: return 20; default: return 30; } }'
+assert 8 'int main() { int i; int j=0; for (i=0; i<6; i=i+1) switch(i%3) { case 0: continue; case 1: j=j+1; break; default: j=j+3; } return j; }'
+assert_fail 'int main() { switch(1) { case 1: case 1: ; } return 0; }'
+assert_fail 'int main() { switch(1) { default: default: ; } return 0; }'
+assert_fail 'int main() { case 1: return 0; }'
+assert_fail 'int main() { int x=1; switch(1) { case x: ; } return 0; }'
+assert 2 'enum { A, B, C }; int main() { int i=0; switch(C) { case A: i=0; break; case B: i=1; break; case C: i=2; } return i; }'
+
+# do while
+assert 7 'int main() { int i=0; int j=0; do { j=j+1; } while (i++ < 6); return j; }'
+assert 1 'int main() { int i=0; do i=i+1; while (0); return i; }'
+assert 4 'int main() { int j=0; do { if (++j > 3) break; continue; } while (1); return j; }'
+assert 3 'int main() { do return 3; while (1); }'
+
+# goto and labels
+assert 3 'int main() { int i=0; goto a; a: i++; b: i++; c: i++; return i; }'
+assert 10 'int main() { int i=0; loop: i=i+1; if (i<10) goto loop; return i; }'
+assert 5 'int main() { int i=0; goto end; i=3; end: return i+5; }'
+assert 3 'int main() { { goto x; } x: return 3; }'
+assert 1 'int main() { int x=0; goto L; { L: x=1; } return x; }'
+assert 3 'int main() { int x=0; goto L; x=5; if (x) { x=2; L: x=x+3; } return x; }'
+assert 4 'int main() { int x=1; switch (x) { return 0; { case 1: x=4; } } return x; }'
+assert 2 'int main() { int i=0; goto b; a: return i; b: i=2; goto a; }'
+assert_fail 'int main() { goto a; return 0; }'
+assert_fail 'int main() { a: a: return 0; }'
+assert_fail 'int f() { a: return 0; } int main() { goto a; return 1; }'
+
+# void
+assert 3 'void f() {} int main() { f(); return 3; }'
+assert 3 'void f(int* p) { *p=3; } int main() { int x=0; f(&x); return x; }'
+assert 5 'void f(int* p, int x) { if (x) { *p=5; return; } *p=7; } int main() { int x=0; f(&x, 1); return x; }'
+assert 7 'void f(int* p, int x) { if (x) { *p=5; return; } *p=7; } int main() { int x=0; f(&x, 0); return x; }'
+assert 3 'int f(void) { return 3; } int main() { return f(); }'
+assert 3 'void f(void); int main() { f(); return 3; } void f(void) { return; }'
+assert 3 'int main() { int x=3; void* p=&x; int* q=p; return *q; }'
+assert 3 'void* f(void* p) { return p; } int main() { int x=3; int* q=f(&x); return *q; }'
+assert 8 'int main() { void* p; return sizeof(p); }'
+assert_fail 'void f() { return 1; } int main() { return 0; }'
+assert_fail 'int f() { return; } int main() { return 0; }'
+assert_fail 'void f() {} int main() { return f(); }'
+assert_fail 'void f() {} int main() { int x=f(); return 0; }'
+assert_fail 'int main() { void x; return 0; }'
+assert_fail 'int f(void x) { return 0; } int main() { return 0; }'
+assert_fail 'int f(void) { return 3; } int main() { return f(1); }'
+assert_fail 'int main() { return sizeof(void); }'
+
+# integer types
+assert 2 'int main() { short x; return sizeof(x); }'
+assert 8 'int main() { long unsigned int x; return sizeof(x); }'
+assert 1 'int main() { _Bool x=5; return x; }'
+assert 44 'int main() { char x=300; return x; }'
+assert 255 'int main() { unsigned char x=-1; return x; }'
+assert 255 'int main() { unsigned char x=255; return x++; }'
+assert 1 'int main() { unsigned char x=255; x++; return x == 0; }'
+assert 1 'int main() { _Bool x=1; return x++; }'
+assert 1 'int main() { unsigned x=-1; return x > 0; }'
+assert 1 'int main() { unsigned x=0; x=x-1; return x / 2 == 2147483647; }'
+assert 15 'int main() { unsigned long x=-1; return x >> 60; }'
+assert 7 'short f(short a, short b) { return a - b; } int main() { return f(10, 3); }'
+assert_fail 'int main() { short char x; return 0; }'
+assert_fail 'int main() { signed unsigned x; return 0; }'
+
+# floating point
+assert 3 'int main() { double x=3.7; return x; }'
+assert 7 'int main() { float x=1.5; return x * 5; }'
+assert 2 'int main() { return 5.0 / 2; }'
+assert 1 'int main() { return 0.1f + 0.2f == 0.3f; }'
+assert 0 'int main() { return 0.1 + 0.2 == 0.3; }'
+assert 1 'int main() { return 2.5 < 3; }'
+assert 1 'int main() { double x=0.0; return !x; }'
+assert 1 'int main() { return 1 / -0.0 < 0; }'
+assert 1 'int main() { double x=0.0; return 1 / -x < 0; }'
+assert 1 'double g=-0.0; int main() { return 1 / g < 0; }'
+assert 3 'double f(double x) { return x + 1; } int main() { return f(2); }'
+assert 3 'float f(float x, int y) { return x * y; } int main() { return f(1.5, 2); }'
+assert 4 'int main() { return sizeof(float); }'
+assert_fail 'int main() { double x=1.5; return x % 2; }'
+assert_fail 'int main() { return 1.5 << 1; }'
+
+# literals
+assert 97 "int main() { return 'a'; }"
+assert 10 "int main() { return '\\n'; }"
+assert 255 "int main() { return '\\xff' + 256; }"
+assert 31 'int main() { return 0x1F; }'
+assert 15 'int main() { return 017; }'
+assert 5 'int main() { return 0b101; }'
+assert 0 'int main() { return -1 < 0ul; }'
+assert 253 'int main() { return -3; }'
+assert 98 'int main() { return "a" "b"[1]; }'
+assert 0 'int main() { return "\x41\0"[1]; }'
+assert_fail 'int main() { return 08; }'
+assert_fail "int main() { return ''; }"
+
+# variadic functions
+assert 6 'int sum(int n, ...) { __builtin_va_list ap; __builtin_va_start(ap, n); int s=0; int i; for (i=0; i<n; i=i+1) s=s+__builtin_va_arg(ap, int); __builtin_va_end(ap); return s; } int main() { return sum(3, 1, 2, 3); }'
+assert 4 'double sum(int n, ...) { __builtin_va_list ap; __builtin_va_start(ap, n); double s=0; int i; for (i=0; i<n; i=i+1) s=s+__builtin_va_arg(ap, double); __builtin_va_end(ap); return s; } int main() { return sum(2, 1.5, 2.5f); }'
+assert 3 'int f(int n, ...) { return n; } int main() { char c=1; return f(3, c, 2.0f); }'
+assert_fail 'int f(int n, ...) { return n; } int main() { return f(); }'
+assert_fail 'int f(int n) { __builtin_va_list ap; __builtin_va_start(ap, n); return n; } int main() { return f(1); }'
+assert_fail 'int f(...);'
+
+# function pointers
+assert 3 'int add(int a, int b) { return a+b; } int main() { int (*fp)(int a, int b); fp=add; return fp(1, 2); }'
+assert 3 'int add(int a, int b) { return a+b; } int main() { int (*fp)(int a, int b); fp=&add; return (*fp)(1, 2); }'
+assert 7 'int add(int a, int b) { return a+b; } int apply(int f(int a, int b), int x) { return f(x, 4); } int main() { return apply(add, 3); }'
+assert 6 'int one() { return 1; } int two() { return 2; } int main() { int (*fs[2])(); fs[0]=one; fs[1]=two; return fs[0]() + fs[1]()*2 + (*one)() + 1; }'
+assert 5 'int g; void set(int x) { g=x; } int main() { void (*f)(int x); f=set; f(5); return g; }'
+assert_fail 'int main() { int x; return x(); }'
+assert_fail 'int add(int a, int b) { return a+b; } int main() { int (*fp)(int a, int b); fp=add; return fp(1); }'
+
+# declarators
+assert 6 'int main() { int a[2][3]; a[1][2]=6; return a[1][2]; }'
+assert 4 'int main() { int a[2][3]; int (*p)[3]; p=a; a[1][0]=4; return p[1][0]; }'
+assert 1 'int main() { long x=257; return *(char *)&x; }'
+assert 3 'int main() { int x=3; void *p=&x; return *(int *)p; }'
+assert 24 'int main() { return sizeof(int [2][3]); }'
+assert 8 'int main() { return sizeof(int (*)[3]); }'
+assert 16 'int main() { return sizeof(char *[2]); }'
+assert 3 'int add(int, int); int add(int a, int b) { return a+b; } int main() { return add(1, 2); }'
+assert 5 'int add(int a, int b) { return a+b; } int apply(int (*)(int, int)); int apply(int (*f)(int, int)) { return f(2, 3); } int main() { return apply(add); }'
+assert_fail 'int add(int, int b) { return b; } int main() { return add(1, 2); }'
+assert_fail 'int main() { int x; return (int (int))x; }'
+
+# initializers
+assert 3 'int main() { int x[3]={1, 2, 3}; return x[2]; }'
+assert 0 'int main() { int x[3]={1}; return x[2]; }'
+assert 5 'int main() { int x[2][3]={{1, 2, 3}, {4, 5, 6}}; return x[1][1]; }'
+assert 5 'int main() { int x[2][3]={1, 2, 3, 4, 5}; return x[1][1]; }'
+assert 4 'int main() { int x[]={1, 2, 3, 4}; return sizeof(x)/sizeof(x[0]); }'
+assert 99 'int main() { char s[]="abc"; return s[2]; }'
+assert 2 'int main() { struct { int x, y; } p={.y=2}; return p.y; }'
+assert 7 'int main() { struct { int a; struct { int b, c; } d; } x={.d.c=7}; return x.d.c; }'
+assert 6 'int main() { int x[3]={[2]=6}; return x[2]+x[0]; }'
+assert 3 'int g[]={1, 2, 3}; int main() { return g[2]; }'
+assert 5 'int g[2][3]={{1, 2, 3}, {4, 5}}; int main() { return g[1][1]+g[1][2]; }'
+assert 98 'char g[]="abc"; int main() { return g[1]; }'
+assert 2 'struct { char a; int b; } g={1, 2}; int main() { return g.b; }'
+assert 3 'union { long a; char b; } g={3}; int main() { return g.a; }'
+assert_fail 'int main() { int x[2]={1, 2, 3}; return x[0]; }'
+assert_fail 'int main() { int x[]; return 0; }'
+assert_fail 'int main() { struct { int x; } p={.y=1}; return 0; }'
+assert_fail 'union { char a; long b; } g={.b=1}; int main() { return 0; }'
+
+# struct passing
+assert 3 'int main() { struct {int a, b;} x, *p=&x; p->a=3; return x.a; }'
+assert 5 'int main() { struct t {int a; struct {char b;} c;} x, *p=&x; p->c.b=5; return (*p).c.b; }'
+assert 7 'int main() { struct t {char a; long b;} x, y; x.a=3; x.b=4; y=x; return y.a+y.b; }'
+assert 9 'struct p {long a, b;}; long sum(struct p x) { return x.a+x.b; } int main() { struct p x; x.a=4; x.b=5; return sum(x); }'
+assert 6 'struct p {char a; double b;}; struct p make(int n) { struct p x; x.a=n; x.b=n; return x; } int main() { struct p x=make(3); return x.a+x.b; }'
+assert 10 'struct p {long a[4];}; struct p make(int n) { struct p x; x.a[3]=n; return x; } long last(struct p x) { return x.a[3]; } int main() { return last(make(10)); }'
+assert 4 'struct p {int a, b;}; struct p make(int n) { struct p x; x.b=n; return x; } int main() { return make(4).b; }'
+assert_fail 'struct p {int a;}; struct q {int a;}; int f(struct p x) { return x.a; } int main() { struct q y; return f(y); }'
+assert_fail 'struct p {int a;}; struct p f() { return 1; } int main() { return 0; }'
+assert_fail 'int main() { int x; return x->a; }'
+
+# struct tags
+assert 10 'struct node {int v; struct node *next;}; int sum(struct node *n, int k) { int s=0; int i; for (i=0; i<k; i++) { s=s+n->v; n=n->next; } return s; } int main() { struct node a, b; a.v=3; b.v=4; a.next=&b; b.next=&a; return sum(&a, 3); }'
+assert 6 'struct t; struct t *p; struct t {int a;} g; int main() { p=&g; g.a=6; return p->a; }'
+assert 1 'int main() { struct t {char a;}; { struct t {char a[4];} y; } struct t x; return sizeof(x); }'
+assert 4 'int main() { struct t {char a;}; { struct t; struct t {char a[4];} y; return sizeof(y); } }'
+assert_fail 'struct t; int main() { struct t x; return 0; }'
+assert_fail 'int main() { struct t {int a;} x; struct t {int b;} y; return 0; }'
+assert_fail 'struct t {struct t x;}; int main() { return 0; }'
+assert_fail 'int main() { struct t *p; return p->a; }'
+
+# storage classes
+assert 4 'static int ret3() { return 4; } int main() { return ret3(); }'
+assert 3 'int count() { static int n; n=n+1; return n; } int main() { count(); count(); return count(); }'
+assert 12 'int f() { static int n=10; return n=n+1; } int g() { static int n=1; return n; } int main() { f(); return f()-g()+1; }'
+assert 6 'extern int a[]; int sum() { return a[0]+a[2]; } int a[3]={1, 2, 3}; int main() { return sum()+a[1]; }'
+assert 5 'static int x=5; extern int x; int main() { extern int x; return x; }'
+assert_fail 'int f(); static int f() { return 1; } int main() { return f(); }'
+assert_fail 'int main() { int y=1; static int x=y; return x; }'
+assert_fail 'int main() { extern int x=1; return x; }'
+assert_fail 'extern char a; int a; int main() { return 0; }'
+
 # practical
 assert 55 'int fib(int x) { return x<=1 ? 1 : fib(x-1) + fib(x-2); } int main() { return fib(9); }'
 assert 1 'int partition(int* a, int p, int r) { int piv = a[r]; int i = p - 1; int j; for (j = p; j < r; ++j) if (a[j] <= piv) { ++i; int tmp = a[i]; a[i] = a[j]; a[j] = tmp; } ++i; int tmp = a[i]; a[i] = a[j]; a[j] = tmp; return i; } int quicksort(int* a, int p, int r) { if (p < r) { int q = partition(a, p, r); quicksort(a, p, q - 1); quicksort(a, q + 1, r); } return 0; } int sorted(int* a, int n) { int i; for (i = 1; i < n; ++i) if (a[i - 1] > a[i]) return 0; return 1; } int main() { int a[9]; a[0] = 8; a[1] = 4; a[2] = 3; a[3] = 0; a[4] = 7; a[5] = 6; a[6] = 5; a[7] = 2; a[8] = 1; quicksort(a, 0, 9); return sorted(a, 9); }'
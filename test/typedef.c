@@ -0,0 +1,24 @@
+#include "test.h"
+
+typedef int MyInt, MyIntArray[4];
+typedef struct Point { int x; int y; } Point;
+
+MyInt add(MyInt a, MyInt b) { return a + b; }
+
+int main()
+{
+  ASSERT(1, ({ typedef int t; t x=1; x; }));
+  ASSERT(1, ({ typedef struct {int a;} t; t x; x.a=1; x.a; }));
+  ASSERT(1, ({ typedef int t; t t=1; t; }));
+  ASSERT(2, ({ typedef struct {int a;} t; { typedef int t; } t x; x.a=2; x.a; }));
+  ASSERT(3, ({ typedef int *p; int x=3; p y=&x; *y; }));
+  ASSERT(3, ({ MyInt x=3; x; }));
+  ASSERT(5, ({ MyIntArray x; x[0]=2; x[3]=3; x[0]+x[3]; }));
+  ASSERT(7, ({ Point p; p.x=3; p.y=4; p.x+p.y; }));
+  ASSERT(4, ({ struct Point p; p.y=4; p.y; }));
+  ASSERT(5, add(2, 3));
+  ASSERT(6, ({ MyInt MyInt=6; MyInt; }));
+  ASSERT(8, ({ typedef char c; c x=(c)8; (MyInt)x; }));
+
+  return 0;
+}
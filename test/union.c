@@ -0,0 +1,20 @@
+#include "test.h"
+
+int main()
+{
+  ASSERT(3, ({ union { int a; char b[4]; } x; x.a=515; (int)x.b[0]; }));
+  ASSERT(2, ({ union { int a; char b[4]; } x; x.a=515; (int)x.b[1]; }));
+  ASSERT(0, ({ union { int a; char b[4]; } x; x.a=515; (int)x.b[2]; }));
+  ASSERT(0, ({ union { int a; char b[4]; } x; x.a=515; (int)x.b[3]; }));
+
+  ASSERT(7, ({ union { int a; int b; } x; x.a=7; x.b; }));
+  ASSERT(5, ({ union u { char c; int i; }; union u x; x.i=0; x.c=(char)5; x.i; }));
+  ASSERT(2, ({ union { char b[9]; int a; } x; x.a=1; x.b[8]=(char)2; (int)x.b[8]; }));
+  ASSERT(9, ({ union { int a; char b; } x; int *p=&x.a; *p=9; (int)x.b; }));
+  ASSERT(6, ({ union { int a; char b; } x[2]; x[1].a=6; x[0].a=1; x[1].a; }));
+  ASSERT(7, ({ struct { union { int a; char b; } u; int c; } s; s.u.a=3; s.c=4; s.u.a+s.c; }));
+  ASSERT(4, ({ union { struct { int a; int b; } s; int c; } x; x.s.a=1; x.s.b=4; x.c=3; x.s.b; }));
+  ASSERT(8, ({ typedef union { int a; char b; } u; u x; x.a=8; x.a; }));
+
+  return 0;
+}
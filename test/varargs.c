@@ -0,0 +1,70 @@
+#include "test.h"
+#include <stdarg.h>
+
+int sprintf(char *buf, char *fmt, ...);
+int strcmp(char *p, char *q);
+
+int sum1(int x, ...)
+{
+  va_list ap;
+  va_start(ap, x);
+  int y = va_arg(ap, int);
+  while (y != 0)
+  {
+    x += y;
+    y = va_arg(ap, int);
+  }
+  va_end(ap);
+  return x;
+}
+
+double sum2(double x, ...)
+{
+  va_list ap;
+  va_start(ap, x);
+  double y = va_arg(ap, double);
+  while (y != 0)
+  {
+    x += y;
+    y = va_arg(ap, double);
+  }
+  va_end(ap);
+  return x;
+}
+
+int count(char *fmt, ...)
+{
+  va_list ap;
+  va_start(ap, fmt);
+  int n = 0;
+  for (; *fmt; fmt++)
+    if (*fmt == 'd')
+      n += va_arg(ap, int);
+    else if (*fmt == 'f')
+      n += va_arg(ap, double);
+    else if (*fmt == 's')
+      n += *va_arg(ap, char *);
+  va_end(ap);
+  return n;
+}
+
+int main()
+{
+  ASSERT(6, sum1(1, 2, 3, 0));
+  ASSERT(55, sum1(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0));
+  ASSERT(6, sum2(1.0, 2.0, 3.0, 0.0));
+  ASSERT(7, sum2(1.5, 2.5, 3.0, 0.0));
+  ASSERT(6, sum2(1.0, 2.0f, 3.0f, 0.0));
+  ASSERT(15, count("dfd", 1, 4.5, 10));
+  ASSERT(98, count("s", "b"));
+  char c = 3;
+  ASSERT(5, count("dd", c, (short)2));
+
+  char buf[100];
+  sprintf(buf, "%d %s %.1f", 42, "abc", 1.5);
+  ASSERT(0, strcmp(buf, "42 abc 1.5"));
+  sprintf(buf, "%c%c", 'h', 'i');
+  ASSERT(0, strcmp(buf, "hi"));
+
+  return 0;
+}
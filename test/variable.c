@@ -14,18 +14,18 @@ int main()
   ASSERT(3, ({ int foo=3; foo; }));
   ASSERT(8, ({ int foo123=3; int bar=5; foo123+bar; }));
 
-  // ASSERT(8, ({ int x; sizeof(x); }));
-  // ASSERT(8, ({ int x; sizeof x; }));
-  // ASSERT(8, ({ int *x; sizeof(x); }));
-  // ASSERT(32, ({ int x[4]; sizeof(x); }));
-  // ASSERT(96, ({ int x[3][4]; sizeof(x); }));
-  // ASSERT(32, ({ int x[3][4]; sizeof(*x); }));
-  // ASSERT(8, ({ int x[3][4]; sizeof(**x); }));
-  // ASSERT(9, ({ int x[3][4]; sizeof(**x) + 1; }));
-  // ASSERT(9, ({ int x[3][4]; sizeof **x + 1; }));
-  // ASSERT(8, ({ int x[3][4]; sizeof(**x + 1); }));
-  // ASSERT(8, ({ int x=1; sizeof(x=2); }));
-  // ASSERT(1, ({ int x=1; sizeof(x=2); x; }));
+  ASSERT(4, ({ int x; sizeof(x); }));
+  ASSERT(4, ({ int x; sizeof x; }));
+  ASSERT(8, ({ int *x; sizeof(x); }));
+  ASSERT(16, ({ int x[4]; sizeof(x); }));
+  ASSERT(48, ({ int x[3][4]; sizeof(x); }));
+  ASSERT(16, ({ int x[3][4]; sizeof(*x); }));
+  ASSERT(4, ({ int x[3][4]; sizeof(**x); }));
+  ASSERT(5, ({ int x[3][4]; sizeof(**x) + 1; }));
+  ASSERT(5, ({ int x[3][4]; sizeof **x + 1; }));
+  ASSERT(4, ({ int x[3][4]; sizeof(**x + 1); }));
+  ASSERT(4, ({ int x=1; sizeof(x=2); }));
+  ASSERT(1, ({ int x=1; sizeof(x=2); x; }));
 
   ASSERT(0, g1);
   ASSERT(3, ({ g1=3; g1; }));
@@ -34,15 +34,15 @@ int main()
   ASSERT(2, ({ g2[0]=0; g2[1]=1; g2[2]=2; g2[3]=3; g2[2]; }));
   ASSERT(3, ({ g2[0]=0; g2[1]=1; g2[2]=2; g2[3]=3; g2[3]; }));
 
-  // ASSERT(8, sizeof(g1));
-  // ASSERT(32, sizeof(g2));
+  ASSERT(4, sizeof(g1));
+  ASSERT(16, sizeof(g2));
 
   ASSERT(1, ({ char x=(char)1; (int)x; }));
   ASSERT(1, ({ char x=(char)1; char y=(char)2; (int)x; }));
   ASSERT(2, ({ char x=(char)1; char y=(char)2; (int)y; }));
 
-  // ASSERT(1, ({ char x; sizeof(x); }));
-  // ASSERT(10, ({ char x[10]; sizeof(x); }));
+  ASSERT(1, ({ char x; sizeof(x); }));
+  ASSERT(10, ({ char x[10]; sizeof(x); }));
 
   ASSERT(2, ({ int x=2; { int x=3; } x; }));
   ASSERT(2, ({ int x=2; { int x=3; } int y=4; x; }));
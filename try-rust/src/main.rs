@@ -12,18 +12,27 @@ enum Target {
   IR1,
 }
 
+enum MacroOption {
+  Define(String),
+  Undef(String),
+}
+
 struct CommandLineOption {
   target: Target,
+  include_paths: Vec<String>,
+  macro_options: Vec<MacroOption>,
   input_path: String,
   output_path: String,
 }
 
 fn show_usage() {
-  eprintln!("try-rust [-ll|-ir1] [-o <path>] <file>")
+  eprintln!("try-rust [-ll|-ir1] [-I <dir>] [-D <name>[=<value>]] [-U <name>] [-o <path>] <file>")
 }
 
 fn read_command_line_option() -> common::Expected<CommandLineOption> {
   let mut target = Target::LL;
+  let mut include_paths = Vec::new();
+  let mut macro_options = Vec::new();
   let mut input_path = String::new();
   let mut output_path = String::from("-");
 
@@ -36,13 +45,37 @@ fn read_command_line_option() -> common::Expected<CommandLineOption> {
       target = Target::LL;
     } else if arg == "-ir1" {
       target = Target::IR1;
+    } else if arg == "-I" {
+      let dir = it
+        .next()
+        .ok_or(diagnostic::Diagnostic::error("missing directory name"))?;
+      include_paths.push(dir);
+    } else if arg.starts_with("-I") {
+      include_paths.push(arg[2..].to_string());
+    } else if arg == "-D" {
+      let def = it
+        .next()
+        .ok_or(diagnostic::Diagnostic::error("missing macro name"))?;
+      macro_options.push(MacroOption::Define(def));
+    } else if arg.starts_with("-D") {
+      macro_options.push(MacroOption::Define(arg[2..].to_string()));
+    } else if arg == "-U" {
+      let name = it
+        .next()
+        .ok_or(diagnostic::Diagnostic::error("missing macro name"))?;
+      macro_options.push(MacroOption::Undef(name));
+    } else if arg.starts_with("-U") {
+      macro_options.push(MacroOption::Undef(arg[2..].to_string()));
     } else if arg == "-o" {
-      output_path = it.next().ok_or("missing file name")?.to_string();
+      output_path = it
+        .next()
+        .ok_or(diagnostic::Diagnostic::error("missing file name"))?
+        .to_string();
     } else if arg.starts_with("-o") {
       output_path = arg[2..].to_string();
     } else if arg.starts_with('-') && arg.len() > 1 {
       show_usage();
-      return err!("unknown argument");
+      return err!("unknown argument `{}`", arg);
     } else {
       input_path = arg;
     }
@@ -50,6 +83,8 @@ fn read_command_line_option() -> common::Expected<CommandLineOption> {
 
   Ok(CommandLineOption {
     target,
+    include_paths,
+    macro_options,
     input_path,
     output_path,
   })
@@ -68,7 +103,7 @@ fn read_file(path: &str) -> common::Expected<String> {
       Err(_) => err!("failed to read from stdin"),
     }
   } else {
-    let mut file = File::open(path).or(err!("file not found"))?;
+    let mut file = File::open(path).or_else(|_| err!("file `{}` not found", path))?;
     let mut input = String::new();
     match file.read_to_string(&mut input) {
       Ok(_) => Ok(input),
@@ -81,7 +116,7 @@ fn write_to_file(path: &str, body: &str) -> common::Expected<()> {
   if path == "-" {
     println!("{}", body);
   } else {
-    let mut file = File::create(path).or(err!("failed to open file"))?;
+    let mut file = File::create(path).or_else(|_| err!("failed to open file `{}`", path))?;
     writeln!(file, "{}", body).or(err!("failed to write to file"))?;
   }
   Ok(())
@@ -89,12 +124,35 @@ fn write_to_file(path: &str, body: &str) -> common::Expected<()> {
 
 // ----- main -----
 
-fn main() -> common::Expected<()> {
+fn main() {
+  let mut source_map = source::SourceMap::new();
+  if let Err(diags) = run(&mut source_map) {
+    for diag in &diags {
+      eprintln!("{}", source_map.render(diag));
+    }
+    std::process::exit(1);
+  }
+}
+
+fn run(source_map: &mut source::SourceMap) -> Result<(), Vec<diagnostic::Diagnostic>> {
   let opt = read_command_line_option()?;
 
   let toplevels = {
     let input = read_file(&opt.input_path)?;
-    let it = tokenize::Tokenizer::new(&input);
+    let name = if opt.input_path == "-" {
+      "<stdin>".to_string()
+    } else {
+      opt.input_path.clone()
+    };
+    let file = source_map.add_file(name, input);
+    let mut pp = preprocess::Preprocessor::new(source_map, opt.include_paths);
+    for macro_option in &opt.macro_options {
+      match macro_option {
+        MacroOption::Define(def) => pp.define_macro(def),
+        MacroOption::Undef(name) => pp.undef_macro(name),
+      }
+    }
+    let it = pp.preprocess(file);
     parse::parse(it)?
   };
 